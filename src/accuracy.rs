@@ -0,0 +1,112 @@
+/// Accuracy.rs keeps score for the tap accuracy practice mode. Each tap is compared to the nearest beat of the running
+/// metronome and the offsets are collected into a few simple stats
+use std::time::Duration;
+
+#[derive(Default)]
+pub struct TapStats {
+    taps: u64,
+    early: u64,
+    late: u64,
+    total_error_ms: f64,
+    total_offset_ms: f64,
+    pub last_offset_ms: f64,
+}
+
+impl TapStats {
+    pub fn new() -> TapStats {
+        TapStats::default()
+    }
+
+    // Adds a tap that landed offset_ms from the nearest beat, negative offsets are early
+    pub fn record(&mut self, offset_ms: f64) {
+        self.taps += 1;
+        self.total_error_ms += offset_ms.abs();
+        self.total_offset_ms += offset_ms;
+        self.last_offset_ms = offset_ms;
+        if offset_ms < 0.0 {
+            self.early += 1;
+        } else if offset_ms > 0.0 {
+            self.late += 1;
+        }
+    }
+
+    pub fn taps(&self) -> u64 {
+        self.taps
+    }
+
+    // Average distance from the beat in milliseconds, early or late
+    pub fn mean_error_ms(&self) -> f64 {
+        if self.taps == 0 {
+            return 0.0;
+        }
+        self.total_error_ms / self.taps as f64
+    }
+
+    // Average offset in milliseconds, positive when the taps tend to be late. Used to calibrate the output latency
+    pub fn mean_offset_ms(&self) -> f64 {
+        if self.taps == 0 {
+            return 0.0;
+        }
+        self.total_offset_ms / self.taps as f64
+    }
+
+    pub fn early_percent(&self) -> f64 {
+        self.percent_of_taps(self.early)
+    }
+
+    pub fn late_percent(&self) -> f64 {
+        self.percent_of_taps(self.late)
+    }
+
+    fn percent_of_taps(&self, count: u64) -> f64 {
+        if self.taps == 0 {
+            return 0.0;
+        }
+        count as f64 * 100.0 / self.taps as f64
+    }
+}
+
+// Works out how far a tap landed from the nearest beat in milliseconds. Taps in the first half of the gap between
+// beats are late for the last beat, taps in the second half are early (negative) for the next one
+pub fn tap_offset_ms(since_last_tick: Duration, ns_delay: u64) -> f64 {
+    let since_last_tick_ns = since_last_tick.as_nanos() as f64 % ns_delay.max(1) as f64;
+    let offset_ns = if since_last_tick_ns > ns_delay as f64 / 2.0 {
+        since_last_tick_ns - ns_delay as f64
+    } else {
+        since_last_tick_ns
+    };
+    offset_ns / 1_000_000.0
+}
+
+// Tests ---------------------------------------------------------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // taps should be measured from whichever beat is closest
+    #[test]
+    fn accuracy_tap_offset_ms() {
+        let ns_delay = 500_000_000;
+        assert_eq!(tap_offset_ms(Duration::from_millis(20), ns_delay), 20.0);
+        assert_eq!(tap_offset_ms(Duration::from_millis(470), ns_delay), -30.0);
+        // a late tap after a missed tick is still measured against the nearest beat
+        assert_eq!(tap_offset_ms(Duration::from_millis(1010), ns_delay), 10.0);
+    }
+
+    // the stats should average the size of the offsets and split them into early and late
+    #[test]
+    fn accuracy_tap_stats() {
+        let mut stats = TapStats::new();
+        assert_eq!(stats.mean_error_ms(), 0.0);
+        stats.record(-10.0);
+        stats.record(20.0);
+        stats.record(30.0);
+        stats.record(0.0);
+        assert_eq!(stats.taps(), 4);
+        assert_eq!(stats.mean_error_ms(), 15.0);
+        assert_eq!(stats.mean_offset_ms(), 10.0);
+        assert_eq!(stats.early_percent(), 25.0);
+        assert_eq!(stats.late_percent(), 50.0);
+        assert_eq!(stats.last_offset_ms, 0.0);
+    }
+}
@@ -3,97 +3,443 @@
 /// in charge of starting the metronome thread and keeping a reference to it's handle
 // App.rs is loosely based on the ratatui JSON editor tutorial found here: https://ratatui.rs/tutorials/json-editor/app/
 use crate::{
+    accuracy::{tap_offset_ms, TapStats},
+    beat_log::BeatLog,
+    config::{Config, CONFIG_PATH, MAX_PANEL_WIDTH, MIN_PANEL_WIDTH},
+    export::export_wav,
+    keymap::{Action, Keymap, KEYMAP_PATH},
+    link::LinkSync,
     menu::Menu,
-    metronome::{InitMetronomeSettings, Metronome, MetronomeSettings},
+    metronome::{
+        absolute_path, bar_progress, click_level, compute_timing, counted_beats_per_bar,
+        delay_times, forget_sound_gains, get_output_device_names, is_final_bar, is_main_beat,
+        is_supported_sound, pendulum_position, round_bpm, time_signature_label, timing_warning,
+        BeatEvent, InitMetronomeSettings, Metronome, MetronomeSettings, Subdivision,
+    },
+    midi::{get_midi_port_names, MidiClock},
+    practice_log::{
+        log_session, practice_totals, SessionStats, MIN_SESSION_LENGTH, PRACTICE_LOG_PATH,
+    },
+    setlist::{parse_time_signature, Setlist, SETLIST_PATH},
+    tempo_map::{TempoMap, TEMPO_MAP_PATH},
+    theme::{theme_index, Theme, THEMES},
+    tone::{parse_tone, tone_label, MAX_TONE_HZ, MIN_TONE_HZ},
 };
-use atomic_float::AtomicF64;
 use color_eyre::{eyre::eyre, Report, Result};
-use crossterm::event::{KeyCode, KeyEvent};
-use std::sync::Arc;
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers, MouseButton, MouseEvent, MouseEventKind};
+use ratatui::{layout::Rect, style::Color};
+use std::sync::mpsc::Receiver;
 use std::thread;
+use std::time::{Duration, Instant};
 use std::{
     fs,
-    sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering},
+    path::{Path, PathBuf},
+    sync::atomic::Ordering,
 };
 
+#[cfg(feature = "http")]
+use crate::http::StatusServer;
+
+// Length and location of the click track written by the export option on the main menu
+const EXPORT_BARS: u64 = 16;
+const EXPORT_PATH: &str = "./click_track.wav";
+
+// Number of items page up / page down move through a menu
+const MENU_PAGE_SIZE: usize = 10;
+
+// Number of ui ticks the title bar stays flashed for after the first beat of a bar
+const FLASH_FRAMES: u64 = 12;
+
+// Number of ui ticks the whole screen stays flashed for after each beat while screen flash is on
+const SCREEN_FLASH_FRAMES: u64 = 1;
+
+// Number of ui ticks each cell of the beat indicator stays lit for after its tick plays
+const PULSE_FRAMES: u64 = 8;
+
+// How long a short message like "Triplets ON" stays in the footer
+const STATUS_MESSAGE_DURATION: Duration = Duration::from_secs(2);
+
+// Number of setting changes that can be undone
+const UNDO_LEVELS: usize = 5;
+
+// Percentage of the screen width the < and > keys move the split between the control and status panels by
+const PANEL_WIDTH_STEP: i16 = 5;
+
+// How far the (,) and (.) keys move the volume, and the number of cells in the volume slider
+const VOLUME_STEP: f64 = 5.0;
+const VOLUME_SLIDER_WIDTH: usize = 10;
+
+// How far the up and down arrows move the bpm in knob mode, holding shift takes the coarse step
+const KNOB_FINE_STEP: i64 = 1;
+const KNOB_COARSE_STEP: i64 = 10;
+
+// Largest output latency in milliseconds, and the taps needed before calibration suggests one
+const MAX_OUTPUT_LATENCY_MS: u64 = 1000;
+const CALIBRATION_TAPS: u64 = 8;
+
+// Furthest in milliseconds a feel offset can move a beat early or late
+const MAX_FEEL_OFFSET_MS: i64 = 100;
+
 // These two enums are used extensively in events.rs and ui.rs to render the correct state and
 // select the right value when editing
-#[derive(PartialEq)]
+#[derive(Clone, Copy, PartialEq)]
 pub enum CurrentScreen {
     Main,
     Editing,
     Exiting,
     SoundSelection,
+    DeviceSelection,
+    Help,
+    Calibration,
     Error,
 }
 
-#[derive(Clone, Copy)]
+// Which setting the sound selection menu is picking a sound for
+#[derive(Clone, Copy, PartialEq)]
+pub enum SoundTarget {
+    Main,
+    Poly,
+    Subdivision,
+    Intro,
+    Beat(usize),
+}
+
+// Whether the bpm has been doubled or halved from the base tempo by the double / half time keys
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum TimeFeel {
+    Normal,
+    Double,
+    Half,
+}
+
+// The counters and beat displays as they were when the display was frozen, the ui shows these instead of the live
+// values until it is unfrozen
+#[derive(Clone)]
+pub struct FrozenDisplay {
+    beat_count: String,
+    bar_count: String,
+    total_bars: String,
+    elapsed: String,
+    bar_progress: Option<f64>,
+    is_final_bar: bool,
+    beat_indicator: Vec<(bool, bool)>,
+    pendulum_position: Option<f64>,
+    output_level: Option<f64>,
+}
+
+// A copy of the values set through the edit menu, kept so a change can be undone. On / off settings aren't kept since
+// selecting them again already undoes them
+#[derive(Clone, PartialEq)]
+pub struct SettingsSnapshot {
+    bpm: f64,
+    ts_note: u64,
+    ts_value: u64,
+    beats_per_bar_override: u64,
+    ts_triplets: bool,
+    sub_eights: bool,
+    sub_sixteens: bool,
+    volume: f64,
+    fade_in_bars: u64,
+    output_latency_ms: u64,
+    pitch: f64,
+    accent_pitch_mult: f64,
+    mute_probability: f64,
+    stop_after_bars: u64,
+    selected_sound: usize,
+    beat_sounds: Vec<usize>,
+    subdivision_sound: usize,
+    accent_groups: Vec<u64>,
+    accent_beats: Vec<u64>,
+    accent_every: u64,
+    beat_offsets: Vec<i64>,
+    click_pan: f64,
+    subdivision_pan: f64,
+    poly_pan: f64,
+    poly_beats: u64,
+    poly_sound: usize,
+    trainer_start_bpm: u64,
+    trainer_target_bpm: u64,
+    trainer_increment: i64,
+    trainer_bars_per_step: u64,
+}
+
+#[derive(Clone, Copy, PartialEq)]
 pub enum CurrentlyEditing {
     Bpm,
     Volume,
+    TimeSignature,
+    TrainerStartBpm,
+    TrainerTargetBpm,
+    TrainerIncrement,
+    TrainerBarsPerStep,
+    PolyBeats,
+    SilentBarChance,
+    StopAfterBars,
+    Pitch,
+    FadeInBars,
+    OutputLatency,
+    BeatsPerBar,
+    AccentGroups,
+    AccentBeats,
+    FeelOffsets,
+    ClickPan,
+    SubdivisionPan,
+    PolyPan,
+    AccentEvery,
+    AccentPitch,
+    ToneHz,
+    SoundName,
+}
+
+impl CurrentlyEditing {
+    // Every setting edited with the pop up, in the order the pop up lists them. SoundName isn't a metronome setting,
+    // it is only opened from the sound selection screen so it isn't listed
+    pub const ALL: [CurrentlyEditing; 23] = [
+        CurrentlyEditing::Bpm,
+        CurrentlyEditing::Volume,
+        CurrentlyEditing::TimeSignature,
+        CurrentlyEditing::TrainerStartBpm,
+        CurrentlyEditing::TrainerTargetBpm,
+        CurrentlyEditing::TrainerIncrement,
+        CurrentlyEditing::TrainerBarsPerStep,
+        CurrentlyEditing::PolyBeats,
+        CurrentlyEditing::SilentBarChance,
+        CurrentlyEditing::StopAfterBars,
+        CurrentlyEditing::Pitch,
+        CurrentlyEditing::FadeInBars,
+        CurrentlyEditing::OutputLatency,
+        CurrentlyEditing::BeatsPerBar,
+        CurrentlyEditing::AccentGroups,
+        CurrentlyEditing::AccentBeats,
+        CurrentlyEditing::FeelOffsets,
+        CurrentlyEditing::ClickPan,
+        CurrentlyEditing::SubdivisionPan,
+        CurrentlyEditing::PolyPan,
+        CurrentlyEditing::AccentEvery,
+        CurrentlyEditing::AccentPitch,
+        CurrentlyEditing::ToneHz,
+    ];
+
+    // The name of the setting in the pop up
+    pub fn name(self) -> &'static str {
+        match self {
+            CurrentlyEditing::Bpm => "Bpm",
+            CurrentlyEditing::Volume => "Volume",
+            CurrentlyEditing::TimeSignature => "Time Signature",
+            CurrentlyEditing::TrainerStartBpm => "Trainer Start Bpm",
+            CurrentlyEditing::TrainerTargetBpm => "Trainer Target Bpm",
+            CurrentlyEditing::TrainerIncrement => "Trainer Increment",
+            CurrentlyEditing::TrainerBarsPerStep => "Trainer Bars Per Step",
+            CurrentlyEditing::PolyBeats => "Polyrhythm Beats",
+            CurrentlyEditing::SilentBarChance => "Silent Bar Chance",
+            CurrentlyEditing::StopAfterBars => "Bars Before Stopping",
+            CurrentlyEditing::Pitch => "Pitch",
+            CurrentlyEditing::FadeInBars => "Fade In",
+            CurrentlyEditing::OutputLatency => "Output Latency",
+            CurrentlyEditing::BeatsPerBar => "Beats Per Bar",
+            CurrentlyEditing::AccentGroups => "Accent Groups",
+            CurrentlyEditing::AccentBeats => "Accented Beats",
+            CurrentlyEditing::FeelOffsets => "Feel Offsets",
+            CurrentlyEditing::ClickPan => "Click Pan",
+            CurrentlyEditing::SubdivisionPan => "Subdivision Pan",
+            CurrentlyEditing::PolyPan => "Polyrhythm Pan",
+            CurrentlyEditing::AccentEvery => "Accent Every",
+            CurrentlyEditing::AccentPitch => "Accent Pitch",
+            CurrentlyEditing::ToneHz => "Reference Tone",
+            CurrentlyEditing::SoundName => "Sound Name",
+        }
+    }
+
+    // Explains what to type, shown as the title of the box the new value is typed into
+    pub fn prompt(self) -> &'static str {
+        match self {
+            CurrentlyEditing::Bpm => "Enter New Bpm (20 - 500, ie. 120 or 120.5)",
+            CurrentlyEditing::Volume => "Enter New Volume (0 - 100)",
+            CurrentlyEditing::TimeSignature => {
+                "Enter New Time Signature (1 - 32 over 2, 4, 8, 16, 32 or 64, ie. 7/8)"
+            }
+            CurrentlyEditing::TrainerStartBpm => "Enter New Trainer Start Bpm",
+            CurrentlyEditing::TrainerTargetBpm => "Enter New Trainer Target Bpm",
+            CurrentlyEditing::TrainerIncrement => "Enter New Trainer Increment",
+            CurrentlyEditing::TrainerBarsPerStep => "Enter New Trainer Bars Per Step",
+            CurrentlyEditing::PolyBeats => "Enter New Polyrhythm Beats",
+            CurrentlyEditing::SilentBarChance => "Enter New Silent Bar Chance (0 - 100%)",
+            CurrentlyEditing::StopAfterBars => {
+                "Enter Bars To Play Before Stopping (0 to never stop)"
+            }
+            CurrentlyEditing::Pitch => "Enter New Pitch (0.25 - 4.0, 1.0 is unchanged)",
+            CurrentlyEditing::FadeInBars => "Enter Bars To Fade In Over (0 - 16, 0 is off)",
+            CurrentlyEditing::OutputLatency => "Enter Output Latency In Milliseconds (0 - 1000)",
+            CurrentlyEditing::BeatsPerBar => {
+                "Enter Beats Per Bar (1 - 64, 0 follows the time signature)"
+            }
+            CurrentlyEditing::AccentGroups => {
+                "Enter Accent Groups Adding Up To The Bar (ie. 2+2+3, empty for beat 1)"
+            }
+            CurrentlyEditing::AccentBeats => {
+                "Enter The Beats To Accent (ie. 4 or 1 3, empty to follow the accent groups)"
+            }
+            CurrentlyEditing::FeelOffsets => {
+                "Enter Milliseconds Late (+) Or Early (-) For Each Beat (ie. 0 10 0 -5, empty for none)"
+            }
+            CurrentlyEditing::ClickPan
+            | CurrentlyEditing::SubdivisionPan
+            | CurrentlyEditing::PolyPan => "Enter New Pan (-100 left to 100 right, 0 is center)",
+            CurrentlyEditing::AccentEvery => "Enter Beats Between Accents (1 - 64, 0 is off)",
+            CurrentlyEditing::AccentPitch => "Enter Accent Pitch (0.25 - 4.0, 1.0 is no accent)",
+            CurrentlyEditing::ToneHz => "Enter A Note (ie. A, Bb, C#5) Or Frequency (20 - 2000 Hz)",
+            CurrentlyEditing::SoundName => "Enter A Name For The Sound (empty to use the file name)",
+        }
+    }
 }
 
 pub struct App {
     pub settings: MetronomeSettings,
     pub current_screen: CurrentScreen,
+    pub help_return_screen: CurrentScreen, // the screen to go back to when the help screen is closed
+    pub help_scroll: u16, // lines the help screen is scrolled down by, kept within the list by the ui
+    pub exit_return_screen: CurrentScreen, // the screen to go back to when quitting is cancelled
     pub currently_editing: Option<CurrentlyEditing>,
     pub metronome_handle: Option<thread::JoinHandle<()>>,
+    pub midi_handle: Option<thread::JoinHandle<()>>,
+    pub link_handle: Option<thread::JoinHandle<()>>,
+    pub http_handle: Option<thread::JoinHandle<()>>,
+    pub beat_log_handle: Option<thread::JoinHandle<()>>,
     pub edit_string: String,
     pub alert_string: String,
+    pub export_string: String, // result of the last click track export, shown in the status panel
     pub main_menu: Menu,
     pub edit_menu: Menu,
     pub sound_selection_menu: Menu,
+    pub edit_field_menu: Menu, // every setting the edit pop up can change, listed down the side of the pop up
+    pub device_selection_menu: Menu,
+    pub main_menu_area: Rect, // where ui.rs last drew the main menu, used to find clicked items
+    pub right_panel_area: Rect, // where ui.rs last drew the right panel menu
+    pub keymap: Keymap,
+    pub config: Config,
+    pub theme_index: usize, // index in THEMES of the colors the ui is drawn with
+    pub panel_width: u16, // percentage of the screen width used by the control panel, the status panel gets the rest
+    pub flash_color: Color, // background color of the title bar on the first beat of each bar
+    pub flash_frames: u64, // ui ticks left before the title bar flash ends
+    pub pulse_frames: u64, // ui ticks left before the lit beat indicator cell goes out
+    pub screen_flash: bool, // flash the whole screen on every beat for users who can't hear the click
+    pub pendulum: bool, // swing a pendulum under the main menu in time with the ticks while running
+    pub screen_flash_frames: u64, // ui ticks left before the whole screen flash ends
+    pub screen_flash_downbeat: bool, // whether the whole screen flash is for the first beat of the bar
+    pub knob_mode: bool, // the up and down arrows change the bpm instead of navigating, for rotary encoders
+    pub display_frozen: Option<FrozenDisplay>, // the displays shown while frozen, None while they follow the metronome
+    pub subdivision_before_triplets: Subdivision, // the subdivision triplets replaced, put back when they go off
+    pub time_feel: TimeFeel, // whether the double / half time keys have changed the bpm from base_bpm
+    pub base_bpm: f64,       // the bpm double and half time are worked out from and go back to
+    pub status_message: Option<(String, Instant)>, // a short message for the footer and when it was shown
+    pub knob_step: i64, // the bpm step the arrows last moved by in knob mode, fine or coarse
+    pub last_pulse: (u64, u64), // the bar and beat that last lit the beat indicator
+    pub beat_events: Receiver<BeatEvent>, // a BeatEvent from the metronome for every tick it plays
+    pub pending_beats: Vec<BeatEvent>, // beats received before they are heard, flashed once their instant arrives
+    pub practice_start: Option<Instant>, // when the metronome was last started, None while it is stopped
+    pub session: Option<SessionStats>, // what has been played since the metronome was started, None while stopped
+    pub practice_log_path: Option<String>, // where finished sessions are logged, None until init so tests don't log
+    pub practice_totals: (u64, Duration), // sessions in the practice log and how long they add up to
+    pub tap_practice: bool,               // score taps against the beat while the metronome runs
+    pub tap_stats: TapStats,              // results of the taps since tap practice was turned on
+    pub calibration_stats: TapStats,      // taps made on the output latency calibration screen
+    pub undo_history: Vec<SettingsSnapshot>, // settings from before the last few changes, newest last
+    pub edit_original: Option<SettingsSnapshot>, // settings from when the edit popup was opened, None when closed
+    pub live_preview: bool, // play the bpm and volume as they are typed in the edit popup
     pub should_quit: bool,
     pub first_edit: bool, // this is used to overwrite the original metronome setting text upon opening the edit window
+    pub sound_target: SoundTarget, // the setting the sound selection menu picks a sound for
+    pub renaming_sound: Option<String>, // file name of the sound being given a display name, None when not renaming
     pub sound_list: Vec<String>,
+    pub output_device_list: Vec<String>,
     pub tick_rate: u64,
 }
 
 impl App {
     pub fn new(init_settings: InitMetronomeSettings, set_tick_rate: u64) -> App {
+        let settings = MetronomeSettings::new(init_settings);
+        let beat_events = settings.subscribe_beats();
         App {
-            settings: MetronomeSettings {
-                bpm: Arc::new(AtomicU64::new(init_settings.bpm)),
-                ns_delay: Arc::new(AtomicU64::new(500_000_000)),
-                ts_note: Arc::new(AtomicU64::new(init_settings.ts_note)),
-                ts_value: Arc::new(AtomicU64::new(init_settings.ts_value)),
-                ts_triplets: Arc::new(AtomicBool::new(false)),
-                sub_eights: Arc::new(AtomicBool::new(false)),
-                sub_sixteens: Arc::new(AtomicBool::new(false)),
-                current_beat_count: Arc::new(AtomicU64::new(0)),
-                beats_per_bar: Arc::new(AtomicU64::new(4)),
-                bar_count: Arc::new(AtomicU64::new(1)),
-                is_running: Arc::new(AtomicBool::new(init_settings.is_running)),
-                volume: Arc::new(AtomicF64::new(init_settings.volume)),
-                sound_list: Vec::new(),
-                selected_sound: Arc::new(AtomicUsize::new(0)),
-                tick_count: Arc::new(AtomicU64::new(0)),
-                debug: Arc::new(AtomicBool::new(init_settings.debug)),
-                error: Arc::new(AtomicBool::new(false)),
-            },
+            settings,
             current_screen: CurrentScreen::Main,
+            help_return_screen: CurrentScreen::Main,
+            help_scroll: 0,
+            exit_return_screen: CurrentScreen::Main,
             currently_editing: None,
             metronome_handle: None,
+            midi_handle: None,
+            link_handle: None,
+            http_handle: None,
+            beat_log_handle: None,
             edit_string: String::new(),
             alert_string: String::new(),
+            export_string: String::new(),
             main_menu: Menu::new(vec![
                 "Start / Stop Metronome".to_string(),
                 "Edit Metronome Settings".to_string(),
+                "Export Click Track".to_string(),
                 "Quit".to_string(),
             ]),
             edit_menu: Menu::new(vec![]),
             sound_selection_menu: Menu::new(vec![]),
+            edit_field_menu: Menu::new(vec![]),
+            device_selection_menu: Menu::new(vec![]),
+            main_menu_area: Rect::default(),
+            right_panel_area: Rect::default(),
+            keymap: Keymap::new(),
+            config: Config::new(),
+            theme_index: 0,
+            panel_width: Config::new().panel_width,
+            flash_color: Color::LightGreen,
+            flash_frames: 0,
+            pulse_frames: 0,
+            screen_flash: false,
+            pendulum: false,
+            screen_flash_frames: 0,
+            screen_flash_downbeat: false,
+            knob_mode: false,
+            display_frozen: None,
+            subdivision_before_triplets: Subdivision::Straight,
+            time_feel: TimeFeel::Normal,
+            base_bpm: 0.0,
+            status_message: None,
+            knob_step: KNOB_FINE_STEP,
+            last_pulse: (0, 0),
+            beat_events,
+            pending_beats: Vec::new(),
+            practice_start: None,
+            session: None,
+            practice_log_path: None,
+            practice_totals: (0, Duration::ZERO),
+            tap_practice: false,
+            tap_stats: TapStats::new(),
+            calibration_stats: TapStats::new(),
+            undo_history: Vec::new(),
+            edit_original: None,
+            live_preview: false,
             should_quit: false,
             first_edit: true,
+            sound_target: SoundTarget::Main,
+            renaming_sound: None,
             sound_list: Vec::new(),
+            output_device_list: Vec::new(),
             tick_rate: set_tick_rate,
         }
     }
 
     pub fn init(&mut self) {
+        self.load_keymap(KEYMAP_PATH);
+        self.load_config(CONFIG_PATH);
+        self.load_setlist(SETLIST_PATH);
+        self.load_tempo_map(TEMPO_MAP_PATH);
+        self.practice_log_path = Some(PRACTICE_LOG_PATH.to_owned());
+        self.practice_totals = practice_totals(PRACTICE_LOG_PATH);
+        self.check_output_device();
         match self.populate_sounds() {
             Ok(()) => {
                 self.spawn_metronome_thread();
+                self.spawn_midi_thread();
+                self.spawn_link_thread();
                 self.main_menu.select(0);
             }
             Err(error) => {
@@ -101,30 +447,251 @@ impl App {
                 self.settings.error.swap(true, Ordering::Relaxed);
             }
         };
-        let ns_delay = self.get_ns_for_note_value();
+        let (ns_delay, beats_per_bar) = self.get_timing();
         self.settings.ns_delay.swap(ns_delay, Ordering::Relaxed);
-        let beats_per_bar = self.get_beats_per_bar();
         self.settings
             .beats_per_bar
             .swap(beats_per_bar, Ordering::Relaxed);
+        self.resize_beat_sounds();
+        // the starting bpm and refresh rate can already be too fast for each other
+        if let Some(timing_warning) = self.get_timing_warning() {
+            if !self.alert_string.is_empty() {
+                self.alert_string += ", ";
+            }
+            self.alert_string += &timing_warning;
+        }
     }
 
-    fn populate_sounds(&mut self) -> Result<(), Report> {
-        // loop through sounds found in /assets and add them to the sound_list vec
+    // Matches the per beat sound map to beats_per_bar, new beats use the selected sound. Accent groups that no longer
+    // add up to the bar go back to accenting beat 1
+    pub fn resize_beat_sounds(&mut self) {
+        let beats_per_bar = self.settings.beats_per_bar.load(Ordering::Relaxed) as usize;
+        let selected_sound = self.settings.selected_sound.load(Ordering::Relaxed);
+        self.settings
+            .beat_sounds
+            .lock()
+            .unwrap()
+            .resize(beats_per_bar, selected_sound);
+        self.settings
+            .beat_enabled
+            .lock()
+            .unwrap()
+            .resize(beats_per_bar, true);
+        let mut accent_groups = self.settings.accent_groups.lock().unwrap();
+        if accent_groups.iter().sum::<u64>() as usize != beats_per_bar {
+            accent_groups.clear();
+        }
+    }
+
+    // Reads the names of the sounds found in the sound folder sorted by name
+    fn read_sound_dir(&mut self) -> Result<Vec<String>, Report> {
         // TODO: In the future, nested sound directories could be nice to organize by type
-        if let Ok(entries) = fs::read_dir("./assets/") {
+        // files that rodio can't decode are skipped and counted in the alert_string
+        let mut sounds = Vec::new();
+        let mut skipped = 0;
+        let sound_dir = self.get_sound_dir();
+        if let Ok(entries) = fs::read_dir(&sound_dir) {
             for entry in entries {
                 let string: String = entry?.file_name().into_string().unwrap();
-                self.sound_list.push(string);
+                if is_supported_sound(&string) {
+                    sounds.push(string);
+                } else {
+                    skipped += 1;
+                }
+            }
+        }
+        sounds.sort();
+        if skipped > 0 {
+            self.alert_string = "Skipped ".to_owned()
+                + &skipped.to_string()
+                + " unsupported files in "
+                + &sound_dir.display().to_string()
+                + ", sounds must be wav, ogg, flac or mp3";
+        }
+        Ok(sounds)
+    }
+
+    pub fn get_sound_dir(&self) -> PathBuf {
+        self.settings.sound_dir.lock().unwrap().clone()
+    }
+
+    // Loads sounds from another folder, relative paths are taken from the working directory at startup so the folder
+    // doesn't change if the working directory does
+    pub fn set_sound_dir(&mut self, sound_dir: &str) {
+        *self.settings.sound_dir.lock().unwrap() = absolute_path(Path::new(sound_dir));
+    }
+
+    // Shown instead of starting when there is nothing in the sound folder to play
+    fn get_no_sounds_alert(&self) -> String {
+        "No sounds found in ".to_owned() + &self.get_sound_dir().display().to_string()
+    }
+
+    fn populate_sounds(&mut self) -> Result<(), Report> {
+        self.sound_list = self.read_sound_dir()?;
+
+        // share these with the metronome settings prior to spawning metronome thread
+        *self.settings.sound_list.lock().unwrap() = self.sound_list.clone();
+
+        // the app still runs without sounds so they can be added while it is open
+        if self.sound_list.is_empty() {
+            if !self.alert_string.is_empty() {
+                self.alert_string += ", ";
             }
+            self.alert_string += &self.get_no_sounds_alert();
         }
 
-        // clone these over to the metronome settings vec prior to spawning metronome thread
-        self.settings.sound_list = self.sound_list.clone();
+        // give the polyrhythm voice a different sound than the main click when there is one
+        if self.sound_list.len() > 1 {
+            self.settings.poly_sound.swap(1, Ordering::Relaxed);
+        }
 
         Ok(())
     }
 
+    // Scans the sound folder again so sounds added while running show up. Every sound setting keeps its sound by name, or
+    // falls back to the first sound if it was removed
+    pub fn refresh_sound_list(&mut self) {
+        let new_list = match self.read_sound_dir() {
+            Ok(new_list) => new_list,
+            Err(_) => return,
+        };
+        // keep the old list rather than leaving nothing to play
+        if new_list.is_empty() {
+            return;
+        }
+        let renumber = |index: usize| {
+            self.sound_list
+                .get(index)
+                .and_then(|name| new_list.iter().position(|sound| sound == name))
+                .unwrap_or(0)
+        };
+        // sounds the settings use that have gone from the folder fall back to the first sound
+        let mut used = vec![
+            self.settings.selected_sound.load(Ordering::Relaxed),
+            self.settings.poly_sound.load(Ordering::Relaxed),
+            self.settings.subdivision_sound.load(Ordering::Relaxed),
+        ];
+        used.extend(self.settings.beat_sounds.lock().unwrap().iter());
+        used.extend(*self.settings.intro_sound.lock().unwrap());
+        let mut missing: Vec<String> = used
+            .iter()
+            .filter_map(|index| self.sound_list.get(*index))
+            .filter(|name| !new_list.contains(name))
+            .cloned()
+            .collect();
+        missing.sort();
+        missing.dedup();
+        if !missing.is_empty() {
+            self.alert_string =
+                missing.join(", ") + " left the sound folder, using " + &new_list[0] + " instead";
+        }
+        for setting in [
+            &self.settings.selected_sound,
+            &self.settings.poly_sound,
+            &self.settings.subdivision_sound,
+        ] {
+            let index = setting.load(Ordering::Relaxed);
+            setting.swap(renumber(index), Ordering::Relaxed);
+        }
+        for sound in self.settings.beat_sounds.lock().unwrap().iter_mut() {
+            *sound = renumber(*sound);
+        }
+        let intro_sound = *self.settings.intro_sound.lock().unwrap();
+        *self.settings.intro_sound.lock().unwrap() = intro_sound.map(renumber);
+        *self.settings.sound_list.lock().unwrap() = new_list.clone();
+        self.sound_list = new_list;
+        forget_sound_gains(&self.settings);
+    }
+
+    // Loads custom keybindings, any invalid entries are skipped and reported in the alert_string
+    fn load_keymap(&mut self, path: &str) {
+        let (keymap, warnings) = Keymap::load(path);
+        self.keymap = keymap;
+        if !warnings.is_empty() {
+            self.alert_string = warnings.join(", ");
+        }
+    }
+
+    // Loads the saved preferences, any invalid entries are skipped and reported in the alert_string
+    fn load_config(&mut self, path: &str) {
+        let (config, mut warnings) = Config::load(path);
+        self.config = config;
+        self.panel_width = self.config.panel_width;
+        match theme_index(&self.config.theme) {
+            Some(index) => self.theme_index = index,
+            None => warnings
+                .push("Unknown theme '".to_owned() + &self.config.theme + "' in config.toml"),
+        }
+        if !warnings.is_empty() {
+            if !self.alert_string.is_empty() {
+                warnings.insert(0, self.alert_string.clone());
+            }
+            self.alert_string = warnings.join(", ");
+        }
+    }
+
+    // Loads the setlist, any invalid sections are skipped and reported in the alert_string
+    fn load_setlist(&mut self, path: &str) {
+        let (setlist, mut warnings) = Setlist::load(path);
+        *self.settings.setlist.lock().unwrap() = setlist;
+        if !warnings.is_empty() {
+            if !self.alert_string.is_empty() {
+                warnings.insert(0, self.alert_string.clone());
+            }
+            self.alert_string = warnings.join(", ");
+        }
+    }
+
+    // Loads the tempo map, any invalid lines are skipped and reported in the alert_string
+    fn load_tempo_map(&mut self, path: &str) {
+        let (tempo_map, mut warnings) = TempoMap::load(path);
+        *self.settings.tempo_map.lock().unwrap() = tempo_map;
+        if !warnings.is_empty() {
+            if !self.alert_string.is_empty() {
+                warnings.insert(0, self.alert_string.clone());
+            }
+            self.alert_string = warnings.join(", ");
+        }
+    }
+
+    // Saves the preferences, a failure is reported in the alert_string
+    pub fn save_config(&mut self, path: &str) {
+        if let Err(error) = self.config.save(path) {
+            self.alert_string = "Could not save config.toml, ".to_owned() + &error.to_string();
+        }
+    }
+
+    // The colors the ui is drawn with
+    pub fn theme(&self) -> &'static Theme {
+        &THEMES[self.theme_index]
+    }
+
+    // Switches to the next built in theme
+    pub fn cycle_theme(&mut self) {
+        self.theme_index = (self.theme_index + 1) % THEMES.len();
+        self.config.theme = self.theme().name.to_owned();
+    }
+
+    // Widens (positive) or narrows (negative) the control panel, keeping room for both panels
+    pub fn adjust_panel_width(&mut self, change: i16) {
+        self.panel_width = (self.panel_width as i16 + change)
+            .clamp(MIN_PANEL_WIDTH as i16, MAX_PANEL_WIDTH as i16)
+            as u16;
+        self.config.panel_width = self.panel_width;
+    }
+
+    // Falls back to the default output device if the saved one is no longer available
+    fn check_output_device(&mut self) {
+        let device_name = self.get_output_device_string();
+        if device_name.is_empty() || get_output_device_names().contains(&device_name) {
+            return;
+        }
+        self.set_output_device(String::new());
+        self.alert_string = "Output device '".to_owned()
+            + &device_name
+            + "' was not found, using the default device instead";
+    }
+
     // Spawns a metronome on its own thread
     fn spawn_metronome_thread(&mut self) {
         let mut metronome = Metronome::new(&self.settings);
@@ -135,624 +702,5371 @@ impl App {
         self.check_error_status();
     }
 
+    // The MIDI clock thread idles until MIDI clock is turned on in the edit menu
+    fn spawn_midi_thread(&mut self) {
+        let mut midi_clock = MidiClock::new(&self.settings);
+        self.midi_handle = Some(thread::spawn(move || {
+            midi_clock.start();
+        }));
+    }
+
+    // The Link thread idles until Ableton Link is turned on in the edit menu
+    fn spawn_link_thread(&mut self) {
+        let mut link_sync = LinkSync::new(&self.settings);
+        self.link_handle = Some(thread::spawn(move || {
+            link_sync.start();
+        }));
+    }
+
+    // The HTTP thread is only started when an address is given on the command line, a failure to bind is shown as an
+    // alert and the metronome carries on without it
+    #[cfg(feature = "http")]
+    pub fn spawn_http_thread(&mut self, address: &str) {
+        let mut server = match StatusServer::new(&self.settings, address) {
+            Ok(server) => server,
+            Err(error) => {
+                self.alert_string = "HTTP server failed to start, ".to_owned() + &error.to_string();
+                return;
+            }
+        };
+        self.http_handle = Some(thread::spawn(move || {
+            server.start();
+        }));
+    }
+
+    // The beat log thread is only started when a file is given on the command line, a file that can't be opened is shown
+    // as an alert and the metronome carries on without it
+    pub fn spawn_beat_log_thread(&mut self, path: &str) {
+        let mut beat_log = match BeatLog::new(&self.settings, path) {
+            Ok(beat_log) => beat_log,
+            Err(error) => {
+                if !self.alert_string.is_empty() {
+                    self.alert_string += ", ";
+                }
+                self.alert_string += &("Beat log failed to open, ".to_owned() + &error.to_string());
+                return;
+            }
+        };
+        self.beat_log_handle = Some(thread::spawn(move || {
+            beat_log.start();
+        }));
+    }
+
+    // Tells the metronome, MIDI clock and Link threads to finish and waits for them, this is called when the app is dropped
+    pub fn stop_metronome_thread(&mut self) {
+        // quitting while playing still counts as practice
+        self.end_session();
+        self.settings.is_running.swap(false, Ordering::Relaxed);
+        self.settings.shutdown.swap(true, Ordering::Relaxed);
+        for handle in [
+            self.metronome_handle.take(),
+            self.midi_handle.take(),
+            self.link_handle.take(),
+            self.http_handle.take(),
+            self.beat_log_handle.take(),
+        ]
+        .into_iter()
+        .flatten()
+        {
+            // a thread that panicked has already stopped, so there is nothing left to clean up
+            let _ = handle.join();
+        }
+    }
+
     // Added these helper functions so app is in charge of its own atomics
-    pub fn get_bpm(&mut self) -> u64 {
+    pub fn get_bpm(&mut self) -> f64 {
         self.settings.bpm.load(Ordering::Relaxed)
     }
     pub fn get_volume(&mut self) -> f64 {
         self.settings.volume.load(Ordering::Relaxed)
     }
+    // Draws the volume as a text slider across the valid range with the value after it, ie. "[#####-----] 50%"
+    pub fn get_volume_slider(&mut self) -> String {
+        let volume = self.get_volume();
+        let filled = ((volume / 100.0 * VOLUME_SLIDER_WIDTH as f64).round() as usize)
+            .min(VOLUME_SLIDER_WIDTH);
+        let mut slider = "[".to_owned()
+            + &"#".repeat(filled)
+            + &"-".repeat(VOLUME_SLIDER_WIDTH - filled)
+            + "] "
+            + &volume.to_string()
+            + "%";
+        // the volume is kept while muted so unmuting goes back to it
+        if self.get_is_muted() {
+            slider += " (muted)";
+        }
+        slider
+    }
+    pub fn get_debug(&mut self) -> bool {
+        self.settings.debug.load(Ordering::Relaxed)
+    }
+    // Shows or hides the debug panel, the tick count only runs while it is shown
+    pub fn toggle_debug(&mut self) {
+        let debug = self.get_debug();
+        self.settings.debug.swap(!debug, Ordering::Relaxed);
+    }
+    // The lines of the debug panel, timings are shown in milliseconds
+    pub fn get_debug_lines(&mut self) -> Vec<String> {
+        let ms = |ns: u64| format!("{:.3} ms", ns as f64 / 1_000_000.0);
+        vec![
+            "Tick count: ".to_owned()
+                + &self.settings.tick_count.load(Ordering::Relaxed).to_string(),
+            "Last beat late by: ".to_owned()
+                + &ms(self.settings.last_jitter_ns.load(Ordering::Relaxed)),
+            "Latest beat: ".to_owned() + &ms(self.settings.max_jitter_ns.load(Ordering::Relaxed)),
+            "Missed beats: ".to_owned()
+                + &self
+                    .settings
+                    .missed_beats
+                    .load(Ordering::Relaxed)
+                    .to_string(),
+        ]
+    }
+    // Warns when the ticks are too close together for the refresh rate, see metronome::timing_warning
+    pub fn get_timing_warning(&mut self) -> Option<String> {
+        timing_warning(
+            self.settings.ns_delay.load(Ordering::Relaxed),
+            self.tick_rate,
+        )
+    }
+    pub fn get_tone_hz(&mut self) -> f64 {
+        self.settings.tone_hz.load(Ordering::Relaxed)
+    }
+    pub fn get_tone_playing(&mut self) -> bool {
+        self.settings.tone_playing.load(Ordering::Relaxed)
+    }
+    // Describes the reference tone, ie. "A4 (440.0 Hz), playing"
+    pub fn get_tone_string(&mut self) -> String {
+        let label = tone_label(self.get_tone_hz());
+        if self.get_tone_playing() {
+            label + ", playing"
+        } else {
+            label
+        }
+    }
+    // Starts or stops the reference tone, it also stops by itself after a few seconds
+    pub fn toggle_tone(&mut self) {
+        let tone_playing = self.get_tone_playing();
+        self.settings
+            .tone_playing
+            .swap(!tone_playing, Ordering::Relaxed);
+    }
+    // Asks the metronome to play the sound highlighted in the sound selection menu once, so it can be heard before it is
+    // picked. Nothing happens when the search matches no sounds
+    pub fn preview_current_sound(&mut self) {
+        if let Some(sound) = self.get_highlighted_sound() {
+            *self.settings.preview_sound.lock().unwrap() = Some(sound);
+        }
+    }
+    pub fn get_accent_pitch_mult(&mut self) -> f64 {
+        self.settings.accent_pitch_mult.load(Ordering::Relaxed)
+    }
+    pub fn get_pitch(&mut self) -> f64 {
+        self.settings.pitch.load(Ordering::Relaxed)
+    }
     pub fn get_is_running(&mut self) -> bool {
         self.settings.is_running.load(Ordering::Relaxed)
     }
+    pub fn get_error_message(&mut self) -> String {
+        self.settings.error_message.lock().unwrap().clone()
+    }
+    pub fn get_is_silent(&mut self) -> bool {
+        self.settings.silent.load(Ordering::Relaxed)
+    }
+    pub fn get_is_muted(&mut self) -> bool {
+        self.settings.muted.load(Ordering::Relaxed)
+    }
+    // Returns the chance of a silent bar as a percentage
+    pub fn get_silent_bar_chance(&mut self) -> f64 {
+        self.settings.mute_probability.load(Ordering::Relaxed) * 100.0
+    }
     pub fn get_time_sig_string(&mut self) -> String {
         let note = self.settings.ts_note.load(Ordering::Relaxed).to_string();
         let value = self.settings.ts_value.load(Ordering::Relaxed).to_string();
         note + "/" + &value
     }
+    pub fn get_subdivision(&mut self) -> Subdivision {
+        Subdivision::from_flags(
+            self.settings.ts_triplets.load(Ordering::Relaxed),
+            self.settings.sub_eights.load(Ordering::Relaxed),
+            self.settings.sub_sixteens.load(Ordering::Relaxed),
+        )
+    }
+    // The time signature with the subdivision and triplets being played, shown in the title bar
+    pub fn get_time_signature_label(&mut self) -> String {
+        time_signature_label(
+            self.settings.ts_note.load(Ordering::Relaxed),
+            self.settings.ts_value.load(Ordering::Relaxed),
+            self.settings.ts_triplets.load(Ordering::Relaxed),
+            self.settings.sub_eights.load(Ordering::Relaxed),
+            self.settings.sub_sixteens.load(Ordering::Relaxed),
+        )
+    }
+    pub fn get_beat_count_string(&mut self) -> String {
+        if let Some(frozen) = &self.display_frozen {
+            return frozen.beat_count.clone();
+        }
+        self.settings
+            .current_beat_count
+            .load(Ordering::Relaxed)
+            .to_string()
+    }
     pub fn get_bar_count_string(&mut self) -> String {
+        if let Some(frozen) = &self.display_frozen {
+            return frozen.bar_count.clone();
+        }
         self.settings.bar_count.load(Ordering::Relaxed).to_string()
     }
-    pub fn get_selected_sound_string(&mut self) -> String {
-        self.sound_list[self.settings.selected_sound.load(Ordering::Relaxed)].to_string()
+    pub fn get_total_bars_string(&mut self) -> String {
+        if let Some(frozen) = &self.display_frozen {
+            return frozen.total_bars.clone();
+        }
+        self.settings.total_bars.load(Ordering::Relaxed).to_string()
     }
-
-    // Metronome settings change functions
-    pub fn change_bpm(&mut self, new_bpm: u64) {
-        if !(self.verify_bpm(new_bpm)) {
-            return;
+    // Returns how long the metronome has been running as mm:ss, this is 00:00 while it is stopped
+    pub fn get_elapsed_string(&mut self) -> String {
+        if let Some(frozen) = &self.display_frozen {
+            return frozen.elapsed.clone();
         }
-        self.settings.bpm.swap(new_bpm, Ordering::Relaxed);
-        let new_ns = self.get_ns_for_note_value();
-        self.settings.ns_delay.swap(new_ns, Ordering::Relaxed);
+        let seconds = match self.practice_start {
+            Some(start) => start.elapsed().as_secs(),
+            None => 0,
+        };
+        format!("{:02}:{:02}", seconds / 60, seconds % 60)
     }
-
-    fn verify_bpm(&mut self, test_bpm: u64) -> bool {
-        if (20..=500).contains(&test_bpm) {
-            return true;
+    // Returns the time between clicks in milliseconds, this includes any subdivision so it is the interval actually heard
+    pub fn get_click_interval_string(&mut self) -> String {
+        let ms = self.settings.ns_delay.load(Ordering::Relaxed) as f64 / 1_000_000.0;
+        format!("{:.1} ms", ms)
+    }
+    // Returns the note values commonly used for delay and reverb times at the current bpm, ie. "1/4 500.0  1/8 250.0"
+    pub fn get_delay_times_string(&mut self) -> String {
+        let times: Vec<String> = delay_times(self.get_bpm())
+            .iter()
+            .map(|(note, ms)| format!("{} {:.1}", note, ms))
+            .collect();
+        times.join("  ") + " ms"
+    }
+    pub fn get_fade_in_bars(&mut self) -> u64 {
+        self.settings.fade_in_bars.load(Ordering::Relaxed)
+    }
+    pub fn get_fade_in_string(&mut self) -> String {
+        match self.get_fade_in_bars() {
+            0 => "off".to_owned(),
+            1 => "1 bar".to_owned(),
+            bars => bars.to_string() + " bars",
         }
-        false
     }
-
-    fn verify_volume(&mut self, test_vol: f64) -> bool {
-        if (1.0..=200.0).contains(&test_vol) {
-            return true;
+    pub fn get_beats_per_bar_override(&mut self) -> u64 {
+        self.settings.beats_per_bar_override.load(Ordering::Relaxed)
+    }
+    // Returns the accent groups joined like "2+2+3", or "beat 1" when only the first beat is accented
+    pub fn get_accent_groups_string(&mut self) -> String {
+        let accent_groups = self.settings.accent_groups.lock().unwrap();
+        if accent_groups.is_empty() {
+            return "beat 1".to_owned();
         }
-        false
+        let groups: Vec<String> = accent_groups
+            .iter()
+            .map(|group| group.to_string())
+            .collect();
+        groups.join("+")
     }
-
-    pub fn change_bpm_editor(&mut self) -> bool {
-        if self.edit_string.is_empty() {
-            false
-        } else {
-            let new_bpm: u64 = match self.edit_string.parse() {
-                Ok(new_value) => new_value,
-                Err(_) => return false,
-            };
-            if self.verify_bpm(new_bpm) {
-                self.settings.bpm.swap(new_bpm, Ordering::Relaxed);
-                let new_ns_delay = self.get_ns_for_note_value();
-                self.settings.ns_delay.swap(new_ns_delay, Ordering::Relaxed);
-                self.clear_strings();
-                self.currently_editing = None;
-                true
-            } else {
-                self.edit_string.clear();
-                false
-            }
+    // Returns the accented beats counting from 1 like "1 3", or "off" when the accent groups decide
+    pub fn get_accent_beats_string(&mut self) -> String {
+        let accent_beats = self.settings.accent_beats.lock().unwrap();
+        if accent_beats.is_empty() {
+            return "off".to_owned();
         }
+        let beats: Vec<String> = accent_beats
+            .iter()
+            .map(|beat| (beat + 1).to_string())
+            .collect();
+        beats.join(" ")
     }
-
-    pub fn change_volume_editor(&mut self) -> bool {
-        if self.edit_string.is_empty() {
-            false
+    // Returns where a voice is panned as a percentage, from -100 (left) to 100 (right)
+    pub fn get_pan(&mut self, editing: CurrentlyEditing) -> i64 {
+        let pan = match editing {
+            CurrentlyEditing::SubdivisionPan => &self.settings.subdivision_pan,
+            CurrentlyEditing::PolyPan => &self.settings.poly_pan,
+            _ => &self.settings.click_pan,
+        };
+        (pan.load(Ordering::Relaxed) * 100.0).round() as i64
+    }
+    pub fn get_accent_every(&mut self) -> u64 {
+        self.settings.accent_every.load(Ordering::Relaxed)
+    }
+    // Describes how often the accent every few beats is played, ie. "every 3 beats" or "off"
+    pub fn get_accent_every_string(&mut self) -> String {
+        match self.get_accent_every() {
+            0 => "off".to_owned(),
+            1 => "every beat".to_owned(),
+            beats => "every ".to_owned() + &beats.to_string() + " beats",
+        }
+    }
+    // Describes where a voice is panned, ie. "center" or "40% left"
+    pub fn get_pan_string(&mut self, editing: CurrentlyEditing) -> String {
+        match self.get_pan(editing) {
+            0 => "center".to_owned(),
+            pan if pan < 0 => (-pan).to_string() + "% left",
+            pan => pan.to_string() + "% right",
+        }
+    }
+    // Returns the feel offset of each beat in milliseconds like "0 10 0 -5", or "off" when every beat is on the click
+    pub fn get_feel_offsets_string(&mut self) -> String {
+        let beat_offsets = self.settings.beat_offsets.lock().unwrap();
+        if beat_offsets.is_empty() {
+            return "off".to_owned();
+        }
+        let offsets: Vec<String> = beat_offsets
+            .iter()
+            .map(|offset| offset.to_string())
+            .collect();
+        offsets.join(" ")
+    }
+    pub fn get_beats_per_bar_string(&mut self) -> String {
+        let beats_per_bar = self
+            .settings
+            .beats_per_bar
+            .load(Ordering::Relaxed)
+            .to_string();
+        if self.get_beats_per_bar_override() == 0 {
+            beats_per_bar + " (from time signature)"
         } else {
-            let new_volume: f64 = match self.edit_string.parse() {
-                Ok(new_value) => new_value,
-                Err(_) => return false,
-            };
-            if self.verify_volume(new_volume) {
-                self.settings.volume.swap(new_volume, Ordering::Relaxed);
-                self.clear_strings();
-                self.currently_editing = None;
-                true
-            } else {
-                self.edit_string.clear();
-                false
-            }
+            beats_per_bar
         }
     }
-
-    pub fn toggle_metronome(&mut self) {
-        let currently_playing = self.settings.is_running.load(Ordering::Relaxed);
-        self.settings
-            .is_running
-            .swap(!currently_playing, Ordering::Relaxed);
-        // This will trigger if the metronome fails to load a file
-        self.check_error_status();
+    pub fn get_output_latency(&mut self) -> u64 {
+        self.settings.output_latency_ms.load(Ordering::Relaxed)
     }
-
-    // Convert a bpm value to the nanosecond delay (1/4 notes)
-    fn get_ns_from_bpm(&mut self) -> u64 {
-        (60_000_000_000.0_f64 / self.settings.bpm.load(Ordering::Relaxed) as f64).round() as u64
+    pub fn get_stop_after_bars(&mut self) -> u64 {
+        self.settings.stop_after_bars.load(Ordering::Relaxed)
     }
-
-    // Take the current nanosecond delay and divide it based on the value note in the time signature
-    fn get_ns_for_note_value(&mut self) -> u64 {
-        let value = self.settings.ts_value.load(Ordering::Relaxed);
-        let mut current_ns_delay = self.get_ns_from_bpm(); // length of a quarter note
-
-        // Handle triplet meters like 12/8
-        if value == 8 {
-            current_ns_delay = (current_ns_delay as f64 / 3_f64).round() as u64;
-        } else if value != 4 {
-            current_ns_delay = match value {
-                64 => (current_ns_delay as f64 / 16_f64).round() as u64,
-                32 => (current_ns_delay as f64 / 8_f64).round() as u64,
-                16 => (current_ns_delay as f64 / 4_f64).round() as u64,
-                8 => (current_ns_delay as f64 / 2_f64).round() as u64,
-                _ => current_ns_delay,
-            }
+    // Describes when the metronome will stop, including how many bars are left while it is running
+    pub fn get_stop_after_bars_string(&mut self) -> String {
+        let stop_after_bars = self.get_stop_after_bars();
+        if stop_after_bars == 0 {
+            return "never".to_owned();
+        }
+        let mut stop_string = stop_after_bars.to_string() + " bars";
+        if self.get_is_running() {
+            let bars_left = (stop_after_bars + 1)
+                .saturating_sub(self.settings.bar_count.load(Ordering::Relaxed));
+            stop_string += &(" (".to_owned() + &bars_left.to_string() + " left)");
+        }
+        stop_string
+    }
+    pub fn get_selected_sound_string(&mut self) -> String {
+        self.get_sound_name(self.settings.selected_sound.load(Ordering::Relaxed))
+    }
+    // Returns the name of a sound in the sound_list, or "none" when there is no sound at that index
+    pub fn get_sound_name(&self, sound: usize) -> String {
+        match self.sound_list.get(sound) {
+            Some(name) => self.get_sound_display_name(name),
+            None => "none".to_owned(),
+        }
+    }
+    // The name a sound is shown with, its display name from the config or else its file name
+    pub fn get_sound_display_name(&self, file_name: &str) -> String {
+        match self.config.sound_display_names.get(file_name) {
+            Some(display_name) => display_name.clone(),
+            None => file_name.to_owned(),
+        }
+    }
+    // The index in the sound_list of the sound highlighted in the sound selection menu, which lists display names.
+    // None when the search matches no sounds
+    fn get_highlighted_sound(&self) -> Option<usize> {
+        let item = self.sound_selection_menu.selected_item()?;
+        self.sound_list
+            .iter()
+            .position(|sound| self.get_sound_display_name(sound) == *item)
+    }
+    // Puts any sound setting pointing past the end of the sound list back on the first sound, so a list that changed
+    // under the settings doesn't leave beats silently skipped
+    fn check_sound_indices(&mut self) {
+        let sound_count = self.sound_list.len();
+        if sound_count == 0 {
+            return;
         }
-        // Calculate 8ths or 16ths subdivision in 4/4
-        if value == 4 {
-            if self.settings.sub_eights.load(Ordering::Relaxed) {
-                current_ns_delay = (current_ns_delay as f64 / 2_f64).round() as u64;
-            } else if self.settings.sub_sixteens.load(Ordering::Relaxed) {
-                current_ns_delay = (current_ns_delay as f64 / 4_f64).round() as u64;
+        let mut reset = false;
+        for setting in [
+            &self.settings.selected_sound,
+            &self.settings.poly_sound,
+            &self.settings.subdivision_sound,
+        ] {
+            if setting.load(Ordering::Relaxed) >= sound_count {
+                setting.swap(0, Ordering::Relaxed);
+                reset = true;
             }
-            // This was helpful in thinking about triplet calculation:
-            // https://math.stackexchange.com/questions/2646908/calculating-delay-time-in-milliseconds
-            if self.settings.ts_triplets.load(Ordering::Relaxed) {
-                current_ns_delay = (current_ns_delay as f64 / 3_f64 * 2_f64).round() as u64;
+        }
+        for sound in self.settings.beat_sounds.lock().unwrap().iter_mut() {
+            if *sound >= sound_count {
+                *sound = 0;
+                reset = true;
             }
         }
-
-        current_ns_delay
+        let mut intro_sound = self.settings.intro_sound.lock().unwrap();
+        if intro_sound.is_some_and(|sound| sound >= sound_count) {
+            *intro_sound = Some(0);
+            reset = true;
+        }
+        drop(intro_sound);
+        if reset {
+            self.alert_string = "A sound was missing from the sound list, using ".to_owned()
+                + &self.sound_list[0]
+                + " instead";
+        }
     }
-
-    // Calculate and return the number of metronome beats per bar (based on time signature and subdivision)
-    fn get_beats_per_bar(&mut self) -> u64 {
-        let mut num_ticks = self.settings.ts_note.load(Ordering::Relaxed);
-        if self.settings.ts_triplets.load(Ordering::Relaxed) {
-            num_ticks = (num_ticks as f64 * 1.5_f64).round() as u64;
+    // Whether the metronome has something to play, visual only mode can start without any sounds
+    pub fn can_start(&mut self) -> bool {
+        !self.sound_list.is_empty() || self.get_is_silent()
+    }
+    pub fn get_beat_sounds(&mut self) -> Vec<usize> {
+        self.settings.beat_sounds.lock().unwrap().clone()
+    }
+    pub fn get_link_enabled(&mut self) -> bool {
+        self.settings.link_enabled.load(Ordering::Relaxed)
+    }
+    // Describes whether Link is on and how many peers are in the session
+    pub fn get_link_string(&mut self) -> String {
+        if !self.get_link_enabled() {
+            return "off".to_owned();
         }
-        if self.settings.sub_eights.load(Ordering::Relaxed) {
-            num_ticks *= 2;
-        } else if self.settings.sub_sixteens.load(Ordering::Relaxed) {
-            num_ticks *= 4;
+        let peers = self.settings.link_peers.load(Ordering::Relaxed);
+        let peer_word = if peers == 1 { " peer" } else { " peers" };
+        "on (".to_owned() + &peers.to_string() + peer_word + ")"
+    }
+    pub fn get_midi_enabled(&mut self) -> bool {
+        self.settings.midi_enabled.load(Ordering::Relaxed)
+    }
+    pub fn get_midi_port_string(&mut self) -> String {
+        self.settings.midi_port.lock().unwrap().clone()
+    }
+    pub fn get_poly_enabled(&mut self) -> bool {
+        self.settings.poly_enabled.load(Ordering::Relaxed)
+    }
+    pub fn get_poly_beats(&mut self) -> u64 {
+        self.settings.poly_beats.load(Ordering::Relaxed)
+    }
+    pub fn get_poly_sound_string(&mut self) -> String {
+        self.get_sound_name(self.settings.poly_sound.load(Ordering::Relaxed))
+    }
+    pub fn get_subdivision_sound_string(&mut self) -> String {
+        self.get_sound_name(self.settings.subdivision_sound.load(Ordering::Relaxed))
+    }
+    pub fn get_intro_sound_string(&mut self) -> String {
+        match *self.settings.intro_sound.lock().unwrap() {
+            Some(sound) => self.get_sound_name(sound),
+            None => "off".to_owned(),
         }
-        num_ticks
+    }
+    pub fn get_audio_error(&mut self) -> String {
+        self.settings.audio_error.lock().unwrap().clone()
+    }
+    pub fn get_output_device_string(&mut self) -> String {
+        self.settings.output_device.lock().unwrap().clone()
+    }
+    pub fn get_trainer_enabled(&mut self) -> bool {
+        self.settings.trainer_enabled.load(Ordering::Relaxed)
+    }
+    pub fn get_trainer_stop_at_target(&mut self) -> bool {
+        self.settings.trainer_stop_at_target.load(Ordering::Relaxed)
     }
 
-    pub fn clear_strings(&mut self) {
-        self.alert_string.clear();
-        self.edit_string.clear();
+    // The current value of a setting edited with the pop up, as the pop up shows it
+    pub fn get_edit_value_string(&mut self, editing: CurrentlyEditing) -> String {
+        match editing {
+            CurrentlyEditing::Bpm => self.get_bpm().to_string(),
+            CurrentlyEditing::Volume => self.get_volume().to_string(),
+            CurrentlyEditing::TimeSignature => format!(
+                "{}/{}",
+                self.settings.ts_note.load(Ordering::Relaxed),
+                self.settings.ts_value.load(Ordering::Relaxed)
+            ),
+            CurrentlyEditing::TrainerStartBpm
+            | CurrentlyEditing::TrainerTargetBpm
+            | CurrentlyEditing::TrainerIncrement
+            | CurrentlyEditing::TrainerBarsPerStep => self.get_trainer_value(editing).to_string(),
+            CurrentlyEditing::PolyBeats => self.get_poly_beats().to_string(),
+            CurrentlyEditing::SilentBarChance => self.get_silent_bar_chance().to_string() + "%",
+            CurrentlyEditing::StopAfterBars => self.get_stop_after_bars().to_string(),
+            CurrentlyEditing::Pitch => self.get_pitch().to_string(),
+            CurrentlyEditing::FadeInBars => self.get_fade_in_string(),
+            CurrentlyEditing::OutputLatency => self.get_output_latency().to_string() + " ms",
+            CurrentlyEditing::BeatsPerBar => self.get_beats_per_bar_string(),
+            CurrentlyEditing::AccentGroups => self.get_accent_groups_string(),
+            CurrentlyEditing::AccentBeats => self.get_accent_beats_string(),
+            CurrentlyEditing::FeelOffsets => self.get_feel_offsets_string(),
+            CurrentlyEditing::ClickPan
+            | CurrentlyEditing::SubdivisionPan
+            | CurrentlyEditing::PolyPan => self.get_pan(editing).to_string(),
+            CurrentlyEditing::AccentEvery => self.get_accent_every().to_string(),
+            CurrentlyEditing::AccentPitch => self.get_accent_pitch_mult().to_string() + "x",
+            CurrentlyEditing::ToneHz => self.get_tone_string(),
+            CurrentlyEditing::SoundName => match self.renaming_sound.clone() {
+                Some(file_name) => self.get_sound_display_name(&file_name),
+                None => String::new(),
+            },
+        }
     }
 
-    pub fn check_error_status(&mut self) {
-        if self.settings.error.load(Ordering::Relaxed) {
-            self.current_screen = CurrentScreen::Error;
+    // Returns the current value of one of the tempo trainer settings being edited, only the increment can be negative
+    pub fn get_trainer_value(&mut self, editing: CurrentlyEditing) -> i64 {
+        match editing {
+            CurrentlyEditing::TrainerStartBpm => {
+                self.settings.trainer_start_bpm.load(Ordering::Relaxed) as i64
+            }
+            CurrentlyEditing::TrainerTargetBpm => {
+                self.settings.trainer_target_bpm.load(Ordering::Relaxed) as i64
+            }
+            CurrentlyEditing::TrainerIncrement => {
+                self.settings.trainer_increment.load(Ordering::Relaxed)
+            }
+            CurrentlyEditing::TrainerBarsPerStep => {
+                self.settings.trainer_bars_per_step.load(Ordering::Relaxed) as i64
+            }
+            _ => 0,
         }
     }
 
-    pub fn refresh_edit_menu(&mut self) {
-        let edit_menu_selection = self.edit_menu.state.selected();
-        let is_playing = if self.get_is_running() { "yes" } else { "no" };
-        let mut edit_menu_vec = vec![
-            "playing: ".to_owned() + is_playing,
-            "bpm: ".to_owned() + &self.get_bpm().to_string(),
-            "volume: ".to_owned() + &self.get_volume().to_string(),
-            "select sound: ".to_owned() + &self.get_selected_sound_string(),
-            "Time signature: ".to_owned() + &self.get_time_sig_string(),
-            "Bar count: ".to_owned() + &self.get_bar_count_string(),
-            "Back to main menu".to_owned(),
-        ];
-        // Add debug displays
-        if self.settings.debug.load(Ordering::Relaxed) {
-            edit_menu_vec.push("\n// DEBUG // ".to_owned());
-            edit_menu_vec.push(
-                "TICK COUNT: ".to_owned()
-                    + &self.settings.tick_count.load(Ordering::Relaxed).to_string(),
-            );
-            edit_menu_vec.push(
-                "Current NS Delay: ".to_owned()
-                    + &self.settings.ns_delay.load(Ordering::Relaxed).to_string(),
-            );
+    // Metronome settings change functions
+    pub fn change_bpm(&mut self, new_bpm: f64) {
+        if !(self.verify_bpm(new_bpm)) {
+            return;
         }
-        self.edit_menu.set_items(edit_menu_vec);
+        self.settings
+            .bpm
+            .swap(round_bpm(new_bpm), Ordering::Relaxed);
+        let (new_ns, _) = self.get_timing();
+        self.settings.ns_delay.swap(new_ns, Ordering::Relaxed);
+    }
 
-        // clippy hates this no matter what I do...
-        if let Some(..) = edit_menu_selection {
-            self.edit_menu.select(edit_menu_selection.unwrap());
+    // Moves on to the next subdivision and works out the timing for it, pressing again before a change held for the
+    // next bar is applied moves on from the held one
+    pub fn cycle_subdivision(&mut self) {
+        let mut time_signature = self.settings.next_time_signature();
+        let subdivision = Subdivision::from_flags(
+            time_signature.triplets,
+            time_signature.sub_eights,
+            time_signature.sub_sixteens,
+        );
+        let (triplets, sub_eights, sub_sixteens) = subdivision.next().flags();
+        time_signature.triplets = triplets;
+        time_signature.sub_eights = sub_eights;
+        time_signature.sub_sixteens = sub_sixteens;
+        self.settings.change_time_signature(time_signature);
+    }
+
+    // Switches straight into or out of triplets. Triplets replace any other subdivision, which is remembered and played
+    // again when they are turned off. Like cycle_subdivision, a change held for the next bar is the one switched
+    pub fn toggle_triplets(&mut self) {
+        let mut time_signature = self.settings.next_time_signature();
+        let subdivision = Subdivision::from_flags(
+            time_signature.triplets,
+            time_signature.sub_eights,
+            time_signature.sub_sixteens,
+        );
+        let new_subdivision = if subdivision == Subdivision::Triplets {
+            self.subdivision_before_triplets
+        } else {
+            self.subdivision_before_triplets = subdivision;
+            Subdivision::Triplets
+        };
+        let (triplets, sub_eights, sub_sixteens) = new_subdivision.flags();
+        time_signature.triplets = triplets;
+        time_signature.sub_eights = sub_eights;
+        time_signature.sub_sixteens = sub_sixteens;
+        self.settings.change_time_signature(time_signature);
+        self.show_status_message(if triplets {
+            "Triplets ON"
+        } else {
+            "Triplets OFF"
+        });
+    }
+
+    // Shows a short message in the footer for STATUS_MESSAGE_DURATION
+    pub fn show_status_message(&mut self, message: &str) {
+        self.status_message = Some((message.to_owned(), Instant::now()));
+    }
+
+    // The footer message, None once it has been shown for long enough
+    pub fn get_status_message(&mut self) -> Option<String> {
+        match &self.status_message {
+            Some((message, shown)) if shown.elapsed() < STATUS_MESSAGE_DURATION => {
+                Some(message.clone())
+            }
+            _ => None,
         }
     }
 
-    pub fn refresh_sound_selection_menu(&mut self) {
-        // list sounds
-        self.sound_selection_menu.set_items(self.sound_list.clone());
-        // select the current sound
-        self.sound_selection_menu
-            .select(self.settings.selected_sound.load(Ordering::Relaxed));
+    // Jumps to the bpm saved in a quickslot (counting from 0)
+    pub fn jump_to_quickslot(&mut self, slot: usize) {
+        if let Some(bpm) = self.config.bpm_quickslots.get(slot) {
+            self.change_bpm(*bpm as f64);
+        }
     }
 
-    // TODO: Separate ui nav code from app -----------------------------------------------------------------------------
-    pub fn update(&mut self, key: KeyEvent) -> Result<String, Report> {
-        let mut ask_for_quit = false; // used to prevent pressing q to quit entire program with no warning
+    // Saves the current bpm into a quickslot (counting from 0), the config still needs saving to keep it. Quickslots
+    // hold whole bpm so fractional tempos are rounded
+    pub fn set_quickslot(&mut self, slot: usize) {
+        let bpm = self.get_bpm();
+        if let Some(quickslot) = self.config.bpm_quickslots.get_mut(slot) {
+            *quickslot = bpm.round() as u64;
+        }
+    }
 
-        // If in error mode, return error
-        if self.settings.error.load(Ordering::Relaxed) {
-            return Err(eyre!("App.update() Something went wrong!"));
+    // Doubles or halves the bpm from the base tempo, pressing the same key again goes back to the base. Switching
+    // straight from double to half time (or back) works from the same base
+    pub fn toggle_time_feel(&mut self, time_feel: TimeFeel) {
+        let current_feel = self.get_time_feel();
+        if current_feel == TimeFeel::Normal {
+            self.base_bpm = self.get_bpm();
         }
-        // global keyboard shortcuts and menu navigation controls
-        match key.code {
-            // navigate menu items
-            KeyCode::Up
-            | KeyCode::Left
-            | KeyCode::BackTab
-            | KeyCode::Down
-            | KeyCode::Right
-            | KeyCode::Tab
-            | KeyCode::Esc => {
-                self.menu_navigate(key);
-            }
-            KeyCode::Char('+') => {
-                let old_bpm = self.get_bpm();
-                self.change_bpm(old_bpm + 10);
-            }
-            KeyCode::Char('-') => {
-                let old_bpm = self.get_bpm();
-                self.change_bpm(old_bpm - 10);
-            }
-            // toggle metronome on/off
-            KeyCode::Char('t') => {
-                if self.currently_editing.is_none() {
-                    self.toggle_metronome();
-                }
+        let new_feel = if current_feel == time_feel {
+            TimeFeel::Normal
+        } else {
+            time_feel
+        };
+        let new_bpm = time_feel_bpm(self.base_bpm, new_feel);
+        self.change_bpm(new_bpm);
+        self.time_feel = new_feel;
+    }
+
+    // Double or half time only lasts while the bpm is still the one it set, changing the tempo any other way (nudging,
+    // the editor, the trainer) makes that the new normal
+    pub fn get_time_feel(&mut self) -> TimeFeel {
+        if self.time_feel != TimeFeel::Normal
+            && self.get_bpm() != time_feel_bpm(self.base_bpm, self.time_feel)
+        {
+            self.time_feel = TimeFeel::Normal;
+        }
+        self.time_feel
+    }
+
+    // Describes double or half time for the status panel, ie. "    Double time of 120", empty at the base tempo
+    pub fn get_time_feel_string(&mut self) -> String {
+        let name = match self.get_time_feel() {
+            TimeFeel::Normal => return String::new(),
+            TimeFeel::Double => "Double",
+            TimeFeel::Half => "Half",
+        };
+        "    ".to_owned() + name + " time of " + &self.base_bpm.to_string()
+    }
+
+    // Raises or lowers the bpm by amount, stopping at the edges of the valid range
+    pub fn nudge_bpm(&mut self, amount: i64) {
+        let new_bpm = (self.get_bpm() + amount as f64).clamp(20.0, 500.0);
+        self.change_bpm(new_bpm);
+    }
+
+    fn verify_bpm(&mut self, test_bpm: f64) -> bool {
+        if (20.0..=500.0).contains(&test_bpm) {
+            return true;
+        }
+        false
+    }
+
+    fn verify_volume(&mut self, test_vol: f64) -> bool {
+        if (0.0..=100.0).contains(&test_vol) {
+            return true;
+        }
+        false
+    }
+
+    // Saves the edit_string as the new bpm. Invalid input keeps the old bpm and explains why in the alert_string
+    pub fn change_bpm_editor(&mut self) -> bool {
+        if self.edit_string.is_empty() {
+            self.alert_string = "Please enter a new bpm".to_owned();
+            return false;
+        }
+        // only allow plain numbers with up to one decimal place, ie. 120 or 120.5
+        let (whole, tenths) = self
+            .edit_string
+            .split_once('.')
+            .unwrap_or((self.edit_string.as_str(), ""));
+        if whole.is_empty()
+            || tenths.len() > 1
+            || !whole
+                .chars()
+                .chain(tenths.chars())
+                .all(|c| c.is_ascii_digit())
+        {
+            self.alert_string =
+                "BPM must be a number between 20 and 500 with up to one decimal place".to_owned();
+            self.edit_string.clear();
+            return false;
+        }
+        // anything too long to parse is far out of range anyway
+        let new_bpm: f64 = self.edit_string.parse().unwrap_or(f64::MAX);
+        if self.verify_bpm(new_bpm) {
+            self.settings.bpm.swap(new_bpm, Ordering::Relaxed);
+            let (new_ns_delay, _) = self.get_timing();
+            self.settings.ns_delay.swap(new_ns_delay, Ordering::Relaxed);
+            self.clear_strings();
+            self.currently_editing = None;
+            true
+        } else {
+            self.alert_string = "BPM must be between 20 and 500".to_owned();
+            self.edit_string.clear();
+            false
+        }
+    }
+
+    // Saves the edit_string as the new volume. Invalid input keeps the old volume and explains why in the alert_string
+    pub fn change_volume_editor(&mut self) -> bool {
+        if self.edit_string.is_empty() {
+            self.alert_string = "Please enter a new volume".to_owned();
+            return false;
+        }
+        // only allow plain decimal numbers, rust would otherwise also parse things like "nan", "inf" or "1e2"
+        let is_number = self
+            .edit_string
+            .chars()
+            .all(|c| c.is_ascii_digit() || c == '.');
+        let new_volume: f64 = if is_number {
+            self.edit_string.parse().unwrap_or(f64::NAN)
+        } else {
+            f64::NAN
+        };
+        if new_volume.is_nan() {
+            self.alert_string = "Volume must be a number between 0 and 100".to_owned();
+            self.edit_string.clear();
+            false
+        } else if self.verify_volume(new_volume) {
+            self.settings.volume.swap(new_volume, Ordering::Relaxed);
+            self.clear_strings();
+            self.currently_editing = None;
+            true
+        } else {
+            self.alert_string = "Volume must be between 0 and 100".to_owned();
+            self.edit_string.clear();
+            false
+        }
+    }
+
+    // Renders EXPORT_BARS bars of the current settings to a wav file at path
+    pub fn export_click_track(&mut self, path: &str) {
+        self.export_string = match export_wav(&self.settings, path, EXPORT_BARS) {
+            Ok(()) => "saved ".to_owned() + &EXPORT_BARS.to_string() + " bars to " + path,
+            Err(error) => "failed, ".to_owned() + &error.to_string(),
+        };
+    }
+
+    pub fn get_smooth_clicks(&mut self) -> bool {
+        self.settings.smooth_clicks.load(Ordering::Relaxed)
+    }
+
+    pub fn toggle_smooth_clicks(&mut self) {
+        let smooth_clicks = self.get_smooth_clicks();
+        self.settings
+            .smooth_clicks
+            .swap(!smooth_clicks, Ordering::Relaxed);
+    }
+
+    pub fn get_normalize_sounds(&mut self) -> bool {
+        self.settings.normalize_sounds.load(Ordering::Relaxed)
+    }
+
+    // The metronome measures the sounds as normalizing is turned on
+    pub fn toggle_normalize_sounds(&mut self) {
+        let normalize_sounds = self.get_normalize_sounds();
+        self.settings
+            .normalize_sounds
+            .swap(!normalize_sounds, Ordering::Relaxed);
+    }
+
+    pub fn get_final_bar_cue(&mut self) -> bool {
+        self.settings.final_bar_cue.load(Ordering::Relaxed)
+    }
+
+    pub fn toggle_final_bar_cue(&mut self) {
+        let final_bar_cue = self.settings.final_bar_cue.load(Ordering::Relaxed);
+        self.settings
+            .final_bar_cue
+            .swap(!final_bar_cue, Ordering::Relaxed);
+    }
+
+    // Whether the final bar cue is playing, so the ui can show it too
+    pub fn get_is_final_bar(&mut self) -> bool {
+        if let Some(frozen) = &self.display_frozen {
+            return frozen.is_final_bar;
+        }
+        self.get_is_running()
+            && self.get_final_bar_cue()
+            && is_final_bar(
+                self.settings.bar_count.load(Ordering::Relaxed),
+                self.get_stop_after_bars(),
+            )
+    }
+
+    pub fn get_downbeat_only(&mut self) -> bool {
+        self.settings.downbeat_only.load(Ordering::Relaxed)
+    }
+
+    pub fn toggle_downbeat_only(&mut self) {
+        let downbeat_only = self.settings.downbeat_only.load(Ordering::Relaxed);
+        self.settings
+            .downbeat_only
+            .swap(!downbeat_only, Ordering::Relaxed);
+    }
+
+    pub fn toggle_silent(&mut self) {
+        let silent = self.settings.silent.load(Ordering::Relaxed);
+        self.settings.silent.swap(!silent, Ordering::Relaxed);
+    }
+
+    // Asks the metronome to jump back to beat 1 of the first bar on its next loop, it has to be running
+    pub fn restart_bar(&mut self) {
+        if self.get_is_running() {
+            self.settings.restart.swap(true, Ordering::Relaxed);
+        }
+    }
+
+    // Starts the total bar count again from 0, it otherwise carries on across stopping and starting
+    pub fn reset_total_bars(&mut self) {
+        self.settings.total_bars.swap(0, Ordering::Relaxed);
+    }
+
+    pub fn toggle_mute(&mut self) {
+        let muted = self.settings.muted.load(Ordering::Relaxed);
+        self.settings.muted.swap(!muted, Ordering::Relaxed);
+    }
+
+    // Saves the edit_string to the tempo trainer setting currently being edited
+    pub fn change_trainer_editor(&mut self, editing: CurrentlyEditing) -> bool {
+        if self.edit_string.is_empty() {
+            return false;
+        }
+        let new_value: i64 = match self.edit_string.parse() {
+            Ok(new_value) => new_value,
+            Err(_) => return false,
+        };
+        let is_valid = match editing {
+            CurrentlyEditing::TrainerStartBpm | CurrentlyEditing::TrainerTargetBpm => {
+                self.verify_bpm(new_value as f64)
             }
-            // quit at any time
-            KeyCode::Char('q') => {
-                if self.current_screen != CurrentScreen::Exiting {
-                    self.current_screen = CurrentScreen::Exiting;
-                    self.edit_menu.deselect();
-                    self.currently_editing = None;
-                    self.clear_strings();
-                    ask_for_quit = true;
-                }
+            CurrentlyEditing::TrainerIncrement => (-100..=100).contains(&new_value),
+            CurrentlyEditing::TrainerBarsPerStep => (1..=64).contains(&new_value),
+            _ => false,
+        };
+        if !is_valid {
+            self.edit_string.clear();
+            return false;
+        }
+        // the increment is the only signed setting, the rest were checked to be positive above
+        let setting = match editing {
+            CurrentlyEditing::TrainerIncrement => {
+                self.settings
+                    .trainer_increment
+                    .swap(new_value, Ordering::Relaxed);
+                self.clear_strings();
+                self.currently_editing = None;
+                return true;
+            }
+            CurrentlyEditing::TrainerStartBpm => &self.settings.trainer_start_bpm,
+            CurrentlyEditing::TrainerTargetBpm => &self.settings.trainer_target_bpm,
+            CurrentlyEditing::TrainerBarsPerStep => &self.settings.trainer_bars_per_step,
+            _ => return false,
+        };
+        setting.swap(new_value as u64, Ordering::Relaxed);
+        self.clear_strings();
+        self.currently_editing = None;
+        true
+    }
+
+    // Sets the output device by name (empty for the system default) and tells the metronome to reopen its stream
+    pub fn set_output_device(&mut self, device_name: String) {
+        *self.settings.output_device.lock().unwrap() = device_name;
+        self.settings
+            .output_device_changed
+            .swap(true, Ordering::Relaxed);
+    }
+
+    pub fn change_poly_beats_editor(&mut self) -> bool {
+        if self.edit_string.is_empty() {
+            false
+        } else {
+            let new_beats: u64 = match self.edit_string.parse() {
+                Ok(new_value) => new_value,
+                Err(_) => return false,
+            };
+            if (1..=16).contains(&new_beats) {
+                self.settings.poly_beats.swap(new_beats, Ordering::Relaxed);
+                self.clear_strings();
+                self.currently_editing = None;
+                true
+            } else {
+                self.edit_string.clear();
+                false
             }
-            _ => {}
         }
+    }
 
-        // Screen specific keyboard shortcuts
-        // Main screen ---------------------------------------------------------------------------------------------
-        match self.current_screen {
-            CurrentScreen::Main => {
-                if key.code == KeyCode::Enter {
-                    let current_selection = self.main_menu.state.selected().unwrap();
-                    // TODO: This is messy and bad, magic numbers are not scalable
-                    match current_selection {
-                        0 => {
-                            // start / stop metronome
-                            self.toggle_metronome();
-                        }
-                        1 => {
-                            // enter edit menu
-                            self.switch_screen(CurrentScreen::Editing);
-                        }
-                        2 => {
-                            // enter quit menu
-                            self.current_screen = CurrentScreen::Exiting;
-                        }
-                        _ => {}
-                    }
-                }
+    // Changes the selected sound, beats in the sound map and the subdivision sound that were using the old selected
+    // sound follow it
+    pub fn set_selected_sound(&mut self, new_sound: usize) {
+        let old_sound = self
+            .settings
+            .selected_sound
+            .swap(new_sound, Ordering::Relaxed);
+        let _ = self.settings.subdivision_sound.compare_exchange(
+            old_sound,
+            new_sound,
+            Ordering::Relaxed,
+            Ordering::Relaxed,
+        );
+        for sound in self.settings.beat_sounds.lock().unwrap().iter_mut() {
+            if *sound == old_sound {
+                *sound = new_sound;
             }
-            // Edit screen -----------------------------------------------------------------------------------------
-            CurrentScreen::Editing => match key.code {
-                // When editing a value, add / remove characters from the edit_string
-                KeyCode::Char(value) => {
-                    if self.currently_editing.is_some() {
-                        if self.first_edit {
-                            self.edit_string.clear();
-                            self.first_edit = false;
-                        }
-                        self.edit_string.push(value);
-                    }
-                }
-                KeyCode::Backspace => {
-                    if self.currently_editing.is_some() {
-                        self.edit_string.pop();
-                    }
-                }
-                // When editing a value, save the result or retry if failed
-                KeyCode::Enter => {
-                    if let Some(editing) = &self.currently_editing {
-                        match editing {
-                            CurrentlyEditing::Bpm => {
-                                if self.change_bpm_editor() {
-                                    self.edit_menu.select(1);
-                                    self.first_edit = true;
-                                } else {
-                                    self.alert_string =
-                                        "Please input a value between 20 and 500".to_owned();
-                                }
-                            }
-                            CurrentlyEditing::Volume => {
-                                if self.change_volume_editor() {
-                                    self.edit_menu.select(2);
-                                    self.first_edit = true;
-                                } else {
-                                    self.alert_string =
-                                        "Please input a value between 1.0 and 200.0".to_owned();
-                                }
-                            }
-                        }
-                    } else {
-                        // Main edit menu --------------------------------------------
-                        // TODO: This is messy and bad, magic numbers are not scalable
-                        let current_selection = self.edit_menu.state.selected().unwrap();
-                        match current_selection {
-                            0 => {
-                                // start / stop metronome
-                                self.toggle_metronome()
-                            }
-                            1 => {
-                                // edit bpm
-                                self.edit_string = self.get_bpm().to_string();
-                                self.currently_editing = Some(CurrentlyEditing::Bpm);
-                                self.edit_menu.deselect();
-                            }
-                            2 => {
-                                // edit volume
-                                self.edit_string = self.get_volume().to_string();
-                                self.currently_editing = Some(CurrentlyEditing::Volume);
-                                self.edit_menu.deselect();
-                            }
-                            3 => {
-                                // sound selection menu
-                                self.switch_screen(CurrentScreen::SoundSelection);
-                            }
-                            4 => {
-                                // edit time signature
-                                // TODO: Add the editing functionality for this :)
-                            }
-                            5 => {
-                                // bar count display, do nothing
-                            }
-                            6 => {
-                                // back to main menu
-                                self.switch_screen(CurrentScreen::Main);
-                            }
-                            _ => {}
-                        }
-                    }
-                }
-                _ => {}
-            },
-            // Sound Selection Screen ------------------------------------------------------------------------------
-            CurrentScreen::SoundSelection => {
-                if key.code == KeyCode::Enter {
-                    let selection = self.sound_selection_menu.state.selected().unwrap();
-                    if selection <= self.sound_list.len() {
-                        self.settings
-                            .selected_sound
-                            .swap(selection, Ordering::Relaxed);
-                    }
-                    self.switch_screen(CurrentScreen::Editing);
-                }
+        }
+    }
+
+    // Clears the per beat customization, every beat and the subdivisions go back to the selected sound and are played
+    // on the click, and only beat 1 is accented. Only the shared maps change, so a running metronome carries on from the beat it is on
+    pub fn reset_beat_customization(&mut self) {
+        let beats_per_bar = self.settings.beats_per_bar.load(Ordering::Relaxed) as usize;
+        let selected_sound = self.settings.selected_sound.load(Ordering::Relaxed);
+        *self.settings.beat_sounds.lock().unwrap() = vec![selected_sound; beats_per_bar];
+        *self.settings.beat_enabled.lock().unwrap() = vec![true; beats_per_bar];
+        self.settings
+            .subdivision_sound
+            .swap(selected_sound, Ordering::Relaxed);
+        self.settings.accent_groups.lock().unwrap().clear();
+        self.settings.accent_beats.lock().unwrap().clear();
+        self.settings.beat_offsets.lock().unwrap().clear();
+    }
+
+    // Sets the sound played on one beat of the bar (counting from 0)
+    pub fn set_beat_sound(&mut self, beat: usize, new_sound: usize) {
+        if let Some(sound) = self.settings.beat_sounds.lock().unwrap().get_mut(beat) {
+            *sound = new_sound;
+        }
+    }
+
+    pub fn change_stop_after_bars_editor(&mut self) -> bool {
+        if self.edit_string.is_empty() {
+            false
+        } else {
+            let new_bars: u64 = match self.edit_string.parse() {
+                Ok(new_value) => new_value,
+                Err(_) => return false,
+            };
+            if (0..=999).contains(&new_bars) {
+                self.settings
+                    .stop_after_bars
+                    .swap(new_bars, Ordering::Relaxed);
+                self.clear_strings();
+                self.currently_editing = None;
+                true
+            } else {
+                self.edit_string.clear();
+                false
             }
-            // Exit screen -----------------------------------------------------------------------------------------
-            CurrentScreen::Exiting => match key.code {
-                KeyCode::Char('y') | KeyCode::Char('q') | KeyCode::Enter => {
-                    // Quit
-                    if !ask_for_quit {
-                        self.should_quit = true;
-                    }
-                }
-                KeyCode::Char('n') | KeyCode::Backspace | KeyCode::Esc | KeyCode::Tab => {
-                    // Reset the menu state to a default value
-                    self.current_screen = CurrentScreen::Main;
-                    self.currently_editing = None;
-                    self.clear_strings();
-                    self.first_edit = true;
-                    self.main_menu.select(0);
-                }
-                _ => {}
-            },
-            // Error screen ----------------------------------------------------------------------------------------
-            CurrentScreen::Error => {
-                // Press any char to quit, could not find an "any" keybind in Crossterm
-                if let KeyCode::Char(_) = key.code {
-                    return Err(eyre!(
-                        "ReadyMetronome experienced a terminal error! Sorry about that..."
-                    ));
+        }
+    }
+
+    // Saves the edit_string as the number of bars the volume fades in over after starting
+    pub fn change_fade_in_bars_editor(&mut self) -> bool {
+        let new_bars: u64 = match self.edit_string.parse() {
+            Ok(new_value) => new_value,
+            Err(_) => return false,
+        };
+        if (0..=16).contains(&new_bars) {
+            self.settings.fade_in_bars.swap(new_bars, Ordering::Relaxed);
+            self.clear_strings();
+            self.currently_editing = None;
+            true
+        } else {
+            self.edit_string.clear();
+            false
+        }
+    }
+
+    // Raises or lowers the volume by amount, stopping at the edges of the valid range. The running click picks it up on
+    // its next beat
+    pub fn nudge_volume(&mut self, amount: f64) {
+        let new_volume = (self.get_volume() + amount).clamp(0.0, 100.0);
+        self.settings.volume.swap(new_volume, Ordering::Relaxed);
+    }
+
+    // Saves the edit_string as the time signature, ie. "7/8". The subdivision and beats per bar are kept, and like the
+    // other time signature changes it can be held until the next bar
+    pub fn change_time_signature_editor(&mut self) -> bool {
+        match parse_time_signature(&self.edit_string) {
+            Some((ts_note, ts_value)) => {
+                let mut time_signature = self.settings.next_time_signature();
+                time_signature.ts_note = ts_note;
+                time_signature.ts_value = ts_value;
+                self.settings.change_time_signature(time_signature);
+                self.clear_strings();
+                self.currently_editing = None;
+                true
+            }
+            None => {
+                self.edit_string.clear();
+                false
+            }
+        }
+    }
+
+    // Saves the edit_string as the number of beats counted per bar, 0 goes back to following the time signature
+    pub fn change_beats_per_bar_editor(&mut self) -> bool {
+        let new_beats: u64 = match self.edit_string.parse() {
+            Ok(new_value) => new_value,
+            Err(_) => return false,
+        };
+        if (0..=64).contains(&new_beats) {
+            let mut time_signature = self.settings.next_time_signature();
+            time_signature.beats_per_bar_override = new_beats;
+            self.settings.change_time_signature(time_signature);
+            self.clear_strings();
+            self.currently_editing = None;
+            true
+        } else {
+            self.edit_string.clear();
+            false
+        }
+    }
+
+    // Saves the edit_string as the accent groups, ie. "2+2+3". The groups must add up to the beats per bar, and an
+    // empty string goes back to accenting beat 1
+    pub fn change_accent_groups_editor(&mut self) -> bool {
+        let beats_per_bar = self.settings.beats_per_bar.load(Ordering::Relaxed);
+        match parse_accent_groups(&self.edit_string) {
+            Some(groups) if groups.is_empty() || groups.iter().sum::<u64>() == beats_per_bar => {
+                *self.settings.accent_groups.lock().unwrap() = groups;
+                self.clear_strings();
+                self.currently_editing = None;
+                true
+            }
+            _ => {
+                self.edit_string.clear();
+                false
+            }
+        }
+    }
+
+    // Saves the edit_string as the beats to accent counting from 1, ie. "4" or "1 3". The beats must be in the bar, and an
+    // empty string goes back to the accent groups
+    pub fn change_accent_beats_editor(&mut self) -> bool {
+        let beats_per_bar = self.settings.beats_per_bar.load(Ordering::Relaxed);
+        match parse_accent_beats(&self.edit_string) {
+            Some(mut beats) if beats.iter().all(|beat| *beat < beats_per_bar) => {
+                beats.sort_unstable();
+                beats.dedup();
+                *self.settings.accent_beats.lock().unwrap() = beats;
+                self.clear_strings();
+                self.currently_editing = None;
+                true
+            }
+            _ => {
+                self.edit_string.clear();
+                false
+            }
+        }
+    }
+
+    // Saves the edit_string as the feel offsets, a number of milliseconds for each beat ie. "0 10 0 -5". Beats after the
+    // last offset are on the click, so an empty string or all zeros goes back to the mechanical click
+    pub fn change_feel_offsets_editor(&mut self) -> bool {
+        match parse_feel_offsets(&self.edit_string) {
+            Some(mut offsets) => {
+                while offsets.last() == Some(&0) {
+                    offsets.pop();
                 }
+                *self.settings.beat_offsets.lock().unwrap() = offsets;
+                self.clear_strings();
+                self.currently_editing = None;
+                true
+            }
+            None => {
+                self.edit_string.clear();
+                false
+            }
+        }
+    }
+
+    // Saves the edit_string as the number of beats between the accents that run across the bar lines, 0 turns it off
+    pub fn change_accent_every_editor(&mut self) -> bool {
+        match self.edit_string.trim().parse() {
+            Ok(new_beats) if (0..=64).contains(&new_beats) => {
+                self.settings
+                    .accent_every
+                    .swap(new_beats, Ordering::Relaxed);
+                self.clear_strings();
+                self.currently_editing = None;
+                true
+            }
+            _ => {
+                self.edit_string.clear();
+                false
+            }
+        }
+    }
+
+    // Saves the edit_string as where a voice is panned, from -100 (left) to 100 (right)
+    pub fn change_pan_editor(&mut self, editing: CurrentlyEditing) -> bool {
+        let new_pan: i64 = match self.edit_string.trim().parse() {
+            Ok(new_pan) if (-100..=100).contains(&new_pan) => new_pan,
+            _ => {
+                self.edit_string.clear();
+                return false;
+            }
+        };
+        let pan = match editing {
+            CurrentlyEditing::SubdivisionPan => &self.settings.subdivision_pan,
+            CurrentlyEditing::PolyPan => &self.settings.poly_pan,
+            _ => &self.settings.click_pan,
+        };
+        pan.swap(new_pan as f64 / 100.0, Ordering::Relaxed);
+        self.clear_strings();
+        self.currently_editing = None;
+        true
+    }
+
+    // Saves the edit_string as the reference tone, either a note name like Bb or a frequency in Hz
+    pub fn change_tone_editor(&mut self) -> bool {
+        match parse_tone(&self.edit_string) {
+            Some(tone_hz) => {
+                self.settings.tone_hz.swap(tone_hz, Ordering::Relaxed);
+                self.clear_strings();
+                self.currently_editing = None;
+                true
+            }
+            None => {
+                self.edit_string.clear();
+                false
+            }
+        }
+    }
+
+    // Saves the edit_string as the output latency in milliseconds
+    pub fn change_output_latency_editor(&mut self) -> bool {
+        let new_latency: u64 = match self.edit_string.parse() {
+            Ok(new_value) => new_value,
+            Err(_) => return false,
+        };
+        if (0..=MAX_OUTPUT_LATENCY_MS).contains(&new_latency) {
+            self.settings
+                .output_latency_ms
+                .swap(new_latency, Ordering::Relaxed);
+            self.clear_strings();
+            self.currently_editing = None;
+            true
+        } else {
+            self.edit_string.clear();
+            false
+        }
+    }
+
+    // Saves the edit_string as the playback speed multiplier used to change the pitch of the sounds
+    pub fn change_pitch_editor(&mut self) -> bool {
+        if self.edit_string.is_empty() {
+            false
+        } else {
+            let new_pitch: f64 = match self.edit_string.parse() {
+                Ok(new_value) => new_value,
+                Err(_) => return false,
+            };
+            if (0.25..=4.0).contains(&new_pitch) {
+                self.settings.pitch.swap(new_pitch, Ordering::Relaxed);
+                self.clear_strings();
+                self.currently_editing = None;
+                true
+            } else {
+                self.edit_string.clear();
+                false
+            }
+        }
+    }
+
+    // Saves the edit_string as the accent pitch multiplier, 1.0 plays accents at the same pitch as the other beats
+    pub fn change_accent_pitch_editor(&mut self) -> bool {
+        let new_mult: f64 = match self.edit_string.parse() {
+            Ok(new_value) => new_value,
+            Err(_) => return false,
+        };
+        if (0.25..=4.0).contains(&new_mult) {
+            self.settings
+                .accent_pitch_mult
+                .swap(new_mult, Ordering::Relaxed);
+            self.clear_strings();
+            self.currently_editing = None;
+            true
+        } else {
+            self.edit_string.clear();
+            false
+        }
+    }
+
+    // Saves the edit_string as the percent chance of a bar being silent
+    pub fn change_silent_bar_editor(&mut self) -> bool {
+        if self.edit_string.is_empty() {
+            false
+        } else {
+            let new_chance: f64 = match self.edit_string.parse() {
+                Ok(new_value) => new_value,
+                Err(_) => return false,
+            };
+            if (0.0..=100.0).contains(&new_chance) {
+                self.settings
+                    .mute_probability
+                    .swap(new_chance / 100.0, Ordering::Relaxed);
+                self.clear_strings();
+                self.currently_editing = None;
+                true
+            } else {
+                self.edit_string.clear();
+                false
+            }
+        }
+    }
+
+    pub fn toggle_link(&mut self) {
+        let enabled = self.settings.link_enabled.load(Ordering::Relaxed);
+        self.settings.link_enabled.swap(!enabled, Ordering::Relaxed);
+    }
+
+    pub fn toggle_midi(&mut self) {
+        let enabled = self.settings.midi_enabled.load(Ordering::Relaxed);
+        self.settings.midi_enabled.swap(!enabled, Ordering::Relaxed);
+    }
+
+    // Moves the MIDI clock to the next port, going back to the virtual port after the last one
+    pub fn cycle_midi_port(&mut self, port_names: &[String]) {
+        let current_port = self.get_midi_port_string();
+        let next_port = match port_names.iter().position(|name| *name == current_port) {
+            Some(index) => port_names.get(index + 1).cloned().unwrap_or_default(),
+            None if current_port.is_empty() => port_names.first().cloned().unwrap_or_default(),
+            None => String::new(),
+        };
+        *self.settings.midi_port.lock().unwrap() = next_port;
+    }
+
+    // Turning tap practice on starts a fresh set of results
+    pub fn toggle_tap_practice(&mut self) {
+        self.tap_practice = !self.tap_practice;
+        if self.tap_practice {
+            self.tap_stats = TapStats::new();
+        }
+    }
+
+    // Scores a tap against the nearest beat of the running metronome
+    pub fn record_tap(&mut self) {
+        if !self.tap_practice || !self.get_is_running() {
+            return;
+        }
+        if let Some(offset_ms) = self.tap_offset() {
+            self.tap_stats.record(offset_ms);
+        }
+    }
+
+    // How far from the nearest beat a tap made now is, None if no beat has been played yet
+    fn tap_offset(&mut self) -> Option<f64> {
+        let last_tick_time = (*self.settings.last_tick_time.lock().unwrap())?;
+        let ns_delay = self.settings.ns_delay.load(Ordering::Relaxed);
+        // with an output latency the last tick is heard a little after it is played, so it may still be ahead
+        let now = Instant::now();
+        let since_last_tick = match last_tick_time.checked_duration_since(now) {
+            Some(until_tick) => Duration::from_nanos(ns_delay).saturating_sub(until_tick),
+            None => now.duration_since(last_tick_time),
+        };
+        Some(tap_offset_ms(since_last_tick, ns_delay))
+    }
+
+    // Opens the calibration screen with a fresh set of taps, starting the metronome to tap along with
+    pub fn start_calibration(&mut self) {
+        self.calibration_stats = TapStats::new();
+        if !self.get_is_running() {
+            self.toggle_metronome();
+        }
+        self.switch_screen(CurrentScreen::Calibration);
+    }
+
+    pub fn record_calibration_tap(&mut self) {
+        if !self.get_is_running() {
+            return;
+        }
+        if let Some(offset_ms) = self.tap_offset() {
+            self.calibration_stats.record(offset_ms);
+        }
+    }
+
+    // The output latency that would have put the calibration taps on the beat. Taps that land late on average mean
+    // the click is heard late, so the latency grows by the average offset
+    pub fn get_suggested_latency(&mut self) -> Option<u64> {
+        if self.calibration_stats.taps() < CALIBRATION_TAPS {
+            return None;
+        }
+        let suggested = self.get_output_latency() as f64 + self.calibration_stats.mean_offset_ms();
+        Some((suggested.round().max(0.0) as u64).min(MAX_OUTPUT_LATENCY_MS))
+    }
+
+    // Uses the suggested output latency, returns false if there haven't been enough taps yet
+    pub fn apply_suggested_latency(&mut self) -> bool {
+        match self.get_suggested_latency() {
+            Some(latency) => {
+                self.settings
+                    .output_latency_ms
+                    .swap(latency, Ordering::Relaxed);
+                true
             }
+            None => false,
+        }
+    }
+
+    // Lines shown on the calibration screen
+    pub fn get_calibration_lines(&mut self) -> Vec<String> {
+        let mut lines = vec![
+            "Tap (space) in time with the click you hear.".to_owned(),
+            "Current output latency: ".to_owned() + &self.get_output_latency().to_string() + " ms",
+            "Taps: ".to_owned() + &self.calibration_stats.taps().to_string(),
+        ];
+        if self.calibration_stats.taps() > 0 {
+            lines.push(format!(
+                "Mean offset: {:.1} ms",
+                self.calibration_stats.mean_offset_ms()
+            ));
+        }
+        match self.get_suggested_latency() {
+            Some(latency) => lines.push(
+                "Suggested output latency: ".to_owned()
+                    + &latency.to_string()
+                    + " ms, (enter) to use it",
+            ),
+            None => lines.push(format!(
+                "Keep tapping, {} taps are needed for a suggestion",
+                CALIBRATION_TAPS
+            )),
+        }
+        lines
+    }
+
+    // Lines shown in the tap accuracy results panel
+    pub fn get_tap_results(&mut self) -> Vec<String> {
+        if self.tap_stats.taps() == 0 {
+            return vec!["Press (a) in time".to_owned(), "with the click".to_owned()];
+        }
+        let last_offset = self.tap_stats.last_offset_ms;
+        let last_direction = if last_offset < 0.0 {
+            "early"
+        } else if last_offset > 0.0 {
+            "late"
+        } else {
+            "on the beat"
+        };
+        vec![
+            "Taps: ".to_owned() + &self.tap_stats.taps().to_string(),
+            format!("Last: {:.1} ms {}", last_offset.abs(), last_direction),
+            format!("Mean error: {:.1} ms", self.tap_stats.mean_error_ms()),
+            format!(
+                "Early: {:.0}%  Late: {:.0}%",
+                self.tap_stats.early_percent(),
+                self.tap_stats.late_percent()
+            ),
+        ]
+    }
+
+    pub fn toggle_poly(&mut self) {
+        let enabled = self.settings.poly_enabled.load(Ordering::Relaxed);
+        self.settings.poly_enabled.swap(!enabled, Ordering::Relaxed);
+    }
+
+    pub fn get_setlist_enabled(&mut self) -> bool {
+        self.settings.setlist_enabled.load(Ordering::Relaxed)
+    }
+
+    // Playing the setlist needs at least one section in setlist.toml
+    pub fn toggle_setlist(&mut self) {
+        if self.settings.setlist.lock().unwrap().sections.is_empty() {
+            self.alert_string = "No sections found in setlist.toml".to_owned();
+            self.settings.setlist_enabled.swap(false, Ordering::Relaxed);
+            return;
+        }
+        let enabled = self.settings.setlist_enabled.load(Ordering::Relaxed);
+        self.settings
+            .setlist_enabled
+            .swap(!enabled, Ordering::Relaxed);
+    }
+
+    pub fn toggle_setlist_loop(&mut self) {
+        let mut setlist = self.settings.setlist.lock().unwrap();
+        setlist.looping = !setlist.looping;
+    }
+
+    pub fn get_tempo_map_enabled(&mut self) -> bool {
+        self.settings.tempo_map_enabled.load(Ordering::Relaxed)
+    }
+
+    // Following the tempo map needs at least one tempo change in tempo_map.csv
+    pub fn toggle_tempo_map(&mut self) {
+        if self.settings.tempo_map.lock().unwrap().points.is_empty() {
+            self.alert_string = "No tempo changes found in tempo_map.csv".to_owned();
+            self.settings
+                .tempo_map_enabled
+                .swap(false, Ordering::Relaxed);
+            return;
+        }
+        let enabled = self.settings.tempo_map_enabled.load(Ordering::Relaxed);
+        self.settings
+            .tempo_map_enabled
+            .swap(!enabled, Ordering::Relaxed);
+    }
+
+    pub fn toggle_tempo_map_interpolate(&mut self) {
+        let mut tempo_map = self.settings.tempo_map.lock().unwrap();
+        tempo_map.interpolate = !tempo_map.interpolate;
+    }
+
+    pub fn get_tempo_map_string(&mut self) -> String {
+        let change_count = self.settings.tempo_map.lock().unwrap().points.len();
+        let enabled = if self.get_tempo_map_enabled() {
+            "on"
+        } else {
+            "off"
+        };
+        match change_count {
+            1 => enabled.to_owned() + " (1 change)",
+            count => enabled.to_owned() + " (" + &count.to_string() + " changes)",
+        }
+    }
+
+    pub fn get_setlist_string(&mut self) -> String {
+        let section_count = self.settings.setlist.lock().unwrap().sections.len();
+        let enabled = if self.get_setlist_enabled() {
+            "on"
+        } else {
+            "off"
+        };
+        match section_count {
+            1 => enabled.to_owned() + " (1 section)",
+            count => enabled.to_owned() + " (" + &count.to_string() + " sections)",
+        }
+    }
+
+    // Describes the section being played and the one after it, None while the setlist isn't playing
+    pub fn get_setlist_position(&mut self) -> Option<String> {
+        if !self.get_setlist_enabled() || !self.get_is_running() {
+            return None;
+        }
+        let bar = self.settings.bar_count.load(Ordering::Relaxed);
+        let setlist = self.settings.setlist.lock().unwrap();
+        let (index, section_bar) = setlist.section_for_bar(bar)?;
+        let section = &setlist.sections[index];
+        let next = match setlist.next_section(index) {
+            Some(next_section) => next_section.name.clone(),
+            None => "end".to_owned(),
+        };
+        let mut position = format!(
+            "Section: {}, bar {} of {}    Next: {}",
+            section.name, section_bar, section.bars, next
+        );
+        if !section.note.is_empty() {
+            position += &format!("    Note: {}", section.note);
         }
+        Some(position)
+    }
+
+    pub fn toggle_trainer(&mut self) {
+        let enabled = self.settings.trainer_enabled.load(Ordering::Relaxed);
+        self.settings
+            .trainer_enabled
+            .swap(!enabled, Ordering::Relaxed);
+    }
+
+    pub fn toggle_trainer_stop_at_target(&mut self) {
+        let stop = self.settings.trainer_stop_at_target.load(Ordering::Relaxed);
+        self.settings
+            .trainer_stop_at_target
+            .swap(!stop, Ordering::Relaxed);
+    }
+
+    pub fn toggle_metronome(&mut self) {
+        let currently_playing = self.settings.is_running.load(Ordering::Relaxed);
+        if !currently_playing && !self.can_start() {
+            self.alert_string = self.get_no_sounds_alert();
+            return;
+        }
+        // there is no next bar to wait for once stopped
+        if currently_playing {
+            self.settings.apply_pending_time_signature();
+        } else {
+            self.check_sound_indices();
+        }
+        self.settings
+            .is_running
+            .swap(!currently_playing, Ordering::Relaxed);
+        // This will trigger if the metronome fails to load a file
+        self.check_error_status();
+    }
+
+    // The panic key, stops the metronome, the reference tone and any preview and cuts off every sound still playing
+    // without quitting
+    pub fn stop_all_sound(&mut self) {
+        if self.settings.is_running.load(Ordering::Relaxed) {
+            self.settings.apply_pending_time_signature();
+            self.settings.is_running.swap(false, Ordering::Relaxed);
+        }
+        self.settings.tone_playing.swap(false, Ordering::Relaxed);
+        *self.settings.preview_sound.lock().unwrap() = None;
+        self.settings.stop_all_sound.swap(true, Ordering::Relaxed);
+        self.show_status_message("All sound stopped");
+    }
+
+    // Copies the current values of the settings that can be undone
+    pub fn snapshot(&mut self) -> SettingsSnapshot {
+        SettingsSnapshot {
+            bpm: self.get_bpm(),
+            ts_note: self.settings.ts_note.load(Ordering::Relaxed),
+            ts_value: self.settings.ts_value.load(Ordering::Relaxed),
+            beats_per_bar_override: self.get_beats_per_bar_override(),
+            ts_triplets: self.settings.ts_triplets.load(Ordering::Relaxed),
+            sub_eights: self.settings.sub_eights.load(Ordering::Relaxed),
+            sub_sixteens: self.settings.sub_sixteens.load(Ordering::Relaxed),
+            volume: self.get_volume(),
+            fade_in_bars: self.get_fade_in_bars(),
+            output_latency_ms: self.get_output_latency(),
+            pitch: self.get_pitch(),
+            accent_pitch_mult: self.get_accent_pitch_mult(),
+            mute_probability: self.settings.mute_probability.load(Ordering::Relaxed),
+            stop_after_bars: self.get_stop_after_bars(),
+            selected_sound: self.settings.selected_sound.load(Ordering::Relaxed),
+            beat_sounds: self.get_beat_sounds(),
+            subdivision_sound: self.settings.subdivision_sound.load(Ordering::Relaxed),
+            accent_groups: self.settings.accent_groups.lock().unwrap().clone(),
+            accent_beats: self.settings.accent_beats.lock().unwrap().clone(),
+            accent_every: self.get_accent_every(),
+            beat_offsets: self.settings.beat_offsets.lock().unwrap().clone(),
+            click_pan: self.settings.click_pan.load(Ordering::Relaxed),
+            subdivision_pan: self.settings.subdivision_pan.load(Ordering::Relaxed),
+            poly_pan: self.settings.poly_pan.load(Ordering::Relaxed),
+            poly_beats: self.get_poly_beats(),
+            poly_sound: self.settings.poly_sound.load(Ordering::Relaxed),
+            trainer_start_bpm: self.settings.trainer_start_bpm.load(Ordering::Relaxed),
+            trainer_target_bpm: self.settings.trainer_target_bpm.load(Ordering::Relaxed),
+            trainer_increment: self.settings.trainer_increment.load(Ordering::Relaxed),
+            trainer_bars_per_step: self.settings.trainer_bars_per_step.load(Ordering::Relaxed),
+        }
+    }
+
+    // Remembers the settings from before a change, only the last UNDO_LEVELS changes are kept
+    fn push_undo(&mut self, snapshot: SettingsSnapshot) {
+        if self.undo_history.len() >= UNDO_LEVELS {
+            self.undo_history.remove(0);
+        }
+        self.undo_history.push(snapshot);
+    }
+
+    // Puts the settings back to how they were before the last change and recomputes the timing to match
+    pub fn undo(&mut self) {
+        let snapshot = match self.undo_history.pop() {
+            Some(snapshot) => snapshot,
+            None => return,
+        };
+        // a time signature change still waiting for the next bar is dropped along with the undone change
+        *self.settings.pending_time_sig.lock().unwrap() = None;
+        self.settings.bpm.swap(snapshot.bpm, Ordering::Relaxed);
+        self.settings
+            .ts_note
+            .swap(snapshot.ts_note, Ordering::Relaxed);
+        self.settings
+            .ts_value
+            .swap(snapshot.ts_value, Ordering::Relaxed);
+        self.settings
+            .beats_per_bar_override
+            .swap(snapshot.beats_per_bar_override, Ordering::Relaxed);
+        self.settings
+            .ts_triplets
+            .swap(snapshot.ts_triplets, Ordering::Relaxed);
+        self.settings
+            .sub_eights
+            .swap(snapshot.sub_eights, Ordering::Relaxed);
+        self.settings
+            .sub_sixteens
+            .swap(snapshot.sub_sixteens, Ordering::Relaxed);
+        self.settings
+            .volume
+            .swap(snapshot.volume, Ordering::Relaxed);
+        self.settings
+            .fade_in_bars
+            .swap(snapshot.fade_in_bars, Ordering::Relaxed);
+        self.settings
+            .output_latency_ms
+            .swap(snapshot.output_latency_ms, Ordering::Relaxed);
+        self.settings.pitch.swap(snapshot.pitch, Ordering::Relaxed);
+        self.settings
+            .accent_pitch_mult
+            .swap(snapshot.accent_pitch_mult, Ordering::Relaxed);
+        self.settings
+            .mute_probability
+            .swap(snapshot.mute_probability, Ordering::Relaxed);
+        self.settings
+            .stop_after_bars
+            .swap(snapshot.stop_after_bars, Ordering::Relaxed);
+        self.settings
+            .poly_beats
+            .swap(snapshot.poly_beats, Ordering::Relaxed);
+        self.settings
+            .click_pan
+            .swap(snapshot.click_pan, Ordering::Relaxed);
+        self.settings
+            .subdivision_pan
+            .swap(snapshot.subdivision_pan, Ordering::Relaxed);
+        self.settings
+            .poly_pan
+            .swap(snapshot.poly_pan, Ordering::Relaxed);
+        self.settings
+            .trainer_start_bpm
+            .swap(snapshot.trainer_start_bpm, Ordering::Relaxed);
+        self.settings
+            .trainer_target_bpm
+            .swap(snapshot.trainer_target_bpm, Ordering::Relaxed);
+        self.settings
+            .trainer_increment
+            .swap(snapshot.trainer_increment, Ordering::Relaxed);
+        self.settings
+            .trainer_bars_per_step
+            .swap(snapshot.trainer_bars_per_step, Ordering::Relaxed);
+
+        // the sound list may have been rescanned since, so sounds that no longer exist fall back to the first one
+        let sound_count = self.sound_list.len();
+        let valid_sound = |sound: usize| if sound < sound_count { sound } else { 0 };
+        self.settings
+            .selected_sound
+            .swap(valid_sound(snapshot.selected_sound), Ordering::Relaxed);
+        self.settings
+            .poly_sound
+            .swap(valid_sound(snapshot.poly_sound), Ordering::Relaxed);
+        self.settings
+            .subdivision_sound
+            .swap(valid_sound(snapshot.subdivision_sound), Ordering::Relaxed);
+        *self.settings.beat_sounds.lock().unwrap() =
+            snapshot.beat_sounds.into_iter().map(valid_sound).collect();
+        *self.settings.accent_groups.lock().unwrap() = snapshot.accent_groups;
+        *self.settings.accent_beats.lock().unwrap() = snapshot.accent_beats;
+        self.settings
+            .accent_every
+            .swap(snapshot.accent_every, Ordering::Relaxed);
+        *self.settings.beat_offsets.lock().unwrap() = snapshot.beat_offsets;
+
+        let (ns_delay, beats_per_bar) = self.get_timing();
+        self.settings.ns_delay.swap(ns_delay, Ordering::Relaxed);
+        self.settings
+            .beats_per_bar
+            .swap(beats_per_bar, Ordering::Relaxed);
+        self.resize_beat_sounds();
+    }
+
+    // Switches between changing the time signature straight away and holding changes until the next bar. Anything
+    // being held is applied when switching back
+    pub fn toggle_time_sig_on_downbeat(&mut self) {
+        let on_downbeat = self.settings.time_sig_on_downbeat.load(Ordering::Relaxed);
+        self.settings
+            .time_sig_on_downbeat
+            .swap(!on_downbeat, Ordering::Relaxed);
+        if on_downbeat {
+            self.settings.apply_pending_time_signature();
+        }
+    }
+
+    pub fn toggle_screen_flash(&mut self) {
+        self.screen_flash = !self.screen_flash;
+        self.screen_flash_frames = 0;
+    }
+
+    pub fn toggle_pendulum(&mut self) {
+        self.pendulum = !self.pendulum;
+    }
+
+    // Holds the counters, beat indicator, progress bar, pendulum and flashes where they are so they can be pointed out,
+    // the metronome keeps playing underneath. Unfreezing picks the live values back up
+    pub fn toggle_display_frozen(&mut self) {
+        if self.display_frozen.take().is_some() {
+            self.pending_beats.clear();
+            return;
+        }
+        self.display_frozen = Some(FrozenDisplay {
+            beat_count: self.get_beat_count_string(),
+            bar_count: self.get_bar_count_string(),
+            total_bars: self.get_total_bars_string(),
+            elapsed: self.get_elapsed_string(),
+            bar_progress: self.get_bar_progress(),
+            is_final_bar: self.get_is_final_bar(),
+            beat_indicator: self.get_beat_indicator(),
+            pendulum_position: self.get_pendulum_position(),
+            output_level: self.get_output_level(),
+        });
+    }
+
+    pub fn toggle_knob_mode(&mut self) {
+        self.knob_mode = !self.knob_mode;
+        self.knob_step = KNOB_FINE_STEP;
+    }
+
+    // In knob mode up / down nudge the bpm by the fine step, or the coarse step with shift held. Returns whether the key
+    // was used, other keys and screens without a bpm to change carry on as normal
+    fn knob_key(&mut self, key: KeyEvent) -> bool {
+        if !self.knob_mode
+            || self.currently_editing.is_some()
+            || !matches!(
+                self.current_screen,
+                CurrentScreen::Main | CurrentScreen::Editing
+            )
+        {
+            return false;
+        }
+        let direction = match key.code {
+            KeyCode::Up => 1,
+            KeyCode::Down => -1,
+            _ => return false,
+        };
+        self.knob_step = if key.modifiers.contains(KeyModifiers::SHIFT) {
+            KNOB_COARSE_STEP
+        } else {
+            KNOB_FINE_STEP
+        };
+        self.nudge_bpm(direction * self.knob_step);
+        true
+    }
+
+    pub fn toggle_live_preview(&mut self) {
+        self.live_preview = !self.live_preview;
+    }
+
+    // With live preview on, a valid bpm or volume is sent to the metronome as it is typed so it can be heard before
+    // it is saved
+    fn preview_edit(&mut self) {
+        if !self.live_preview {
+            return;
+        }
+        match self.currently_editing {
+            Some(CurrentlyEditing::Bpm) => {
+                if let Ok(new_bpm) = self.edit_string.parse() {
+                    self.change_bpm(new_bpm);
+                }
+            }
+            Some(CurrentlyEditing::Volume) => {
+                if let Ok(new_volume) = self.edit_string.parse() {
+                    if self.verify_volume(new_volume) {
+                        self.settings.volume.swap(new_volume, Ordering::Relaxed);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    // Puts back the bpm and volume from when the edit popup was opened after a live preview is discarded
+    fn discard_preview(&mut self) {
+        if !self.live_preview {
+            return;
+        }
+        if let Some(edit_original) = self.edit_original.clone() {
+            self.change_bpm(edit_original.bpm);
+            self.settings
+                .volume
+                .swap(edit_original.volume, Ordering::Relaxed);
+        }
+    }
+
+    // Calculates the nanosecond delay and beats per bar from the current bpm, time signature and subdivision
+    fn get_timing(&mut self) -> (u64, u64) {
+        let (ns_delay, beats_per_bar) = compute_timing(
+            self.get_bpm(),
+            self.settings.ts_note.load(Ordering::Relaxed),
+            self.settings.ts_value.load(Ordering::Relaxed),
+            self.settings.ts_triplets.load(Ordering::Relaxed),
+            self.settings.sub_eights.load(Ordering::Relaxed),
+            self.settings.sub_sixteens.load(Ordering::Relaxed),
+        );
+        let beats_per_bar_override = self.get_beats_per_bar_override();
+        (
+            ns_delay,
+            counted_beats_per_bar(beats_per_bar, beats_per_bar_override),
+        )
+    }
+
+    pub fn clear_strings(&mut self) {
+        self.alert_string.clear();
+        self.edit_string.clear();
+    }
+
+    pub fn check_error_status(&mut self) {
+        if self.settings.error.load(Ordering::Relaxed) {
+            self.current_screen = CurrentScreen::Error;
+        }
+    }
+
+    // Lists every setting the edit pop up can change with its current value, highlighting the one being edited
+    pub fn refresh_edit_field_menu(&mut self) {
+        let mut fields = Vec::new();
+        for editing in CurrentlyEditing::ALL {
+            fields.push(editing.name().to_owned() + ": " + &self.get_edit_value_string(editing));
+        }
+        self.edit_field_menu.set_items(fields);
+        let current = self
+            .currently_editing
+            .and_then(|editing| CurrentlyEditing::ALL.iter().position(|e| *e == editing));
+        if let Some(current) = current {
+            self.edit_field_menu.select(current);
+        }
+    }
+
+    pub fn refresh_edit_menu(&mut self) {
+        let edit_menu_selection = self.edit_menu.state.selected();
+        let is_playing = if self.get_is_running() { "yes" } else { "no" };
+        let is_muted = if self.get_is_muted() { "yes" } else { "no" };
+        let output_device = if self.get_output_device_string().is_empty() {
+            "default".to_owned()
+        } else {
+            self.get_output_device_string()
+        };
+        let poly_enabled = if self.get_poly_enabled() { "on" } else { "off" };
+        let midi_enabled = if self.get_midi_enabled() { "on" } else { "off" };
+        let tap_practice = if self.tap_practice { "on" } else { "off" };
+        let is_silent = if self.get_is_silent() { "on" } else { "off" };
+        let live_preview = if self.live_preview { "on" } else { "off" };
+        let screen_flash = if self.screen_flash { "on" } else { "off" };
+        let pendulum = if self.pendulum { "on" } else { "off" };
+        let time_sig_changes = if self.settings.time_sig_on_downbeat.load(Ordering::Relaxed) {
+            "at the next bar"
+        } else {
+            "immediately"
+        };
+        let time_sig_pending = if self.settings.pending_time_sig.lock().unwrap().is_some() {
+            "    (changing at the next bar)"
+        } else {
+            ""
+        };
+        let subdivision_sound = if self.get_subdivision() == Subdivision::Straight {
+            self.get_subdivision_sound_string() + " (subdivisions off)"
+        } else {
+            self.get_subdivision_sound_string()
+        };
+        let downbeat_only = if self.get_downbeat_only() {
+            "on"
+        } else {
+            "off"
+        };
+        let smooth_clicks = if self.get_smooth_clicks() {
+            "on"
+        } else {
+            "off"
+        };
+        let normalize_sounds = if self.get_normalize_sounds() {
+            "on"
+        } else {
+            "off"
+        };
+        let final_bar_cue = if self.get_final_bar_cue() {
+            "on"
+        } else {
+            "off"
+        };
+        let setlist_loop = if self.settings.setlist.lock().unwrap().looping {
+            "yes"
+        } else {
+            "no"
+        };
+        let tempo_map_changes = if self.settings.tempo_map.lock().unwrap().interpolate {
+            "gradual"
+        } else {
+            "stepped"
+        };
+        let midi_port = if self.get_midi_port_string().is_empty() {
+            "virtual".to_owned()
+        } else {
+            self.get_midi_port_string()
+        };
+        let trainer_enabled = if self.get_trainer_enabled() {
+            "on"
+        } else {
+            "off"
+        };
+        let trainer_stop = if self.get_trainer_stop_at_target() {
+            "yes"
+        } else {
+            "no"
+        };
+        let mut edit_menu_vec = vec![
+            "playing: ".to_owned() + is_playing,
+            "bpm: ".to_owned()
+                + &self.get_bpm().to_string()
+                + &self.get_time_feel_string()
+                + "    Click every: "
+                + &self.get_click_interval_string(),
+            "volume: ".to_owned() + &self.get_volume_slider(),
+            "select sound: ".to_owned() + &self.get_selected_sound_string(),
+            "Time signature: ".to_owned()
+                + &self.get_time_sig_string()
+                + "    Subdivision: "
+                + self.get_subdivision().name()
+                + time_sig_pending,
+            "Bar count: ".to_owned()
+                + &self.get_bar_count_string()
+                + "    Total bars: "
+                + &self.get_total_bars_string()
+                + "    Elapsed: "
+                + &self.get_elapsed_string(),
+            "Tempo trainer: ".to_owned() + trainer_enabled,
+            "Trainer start bpm: ".to_owned()
+                + &self
+                    .get_trainer_value(CurrentlyEditing::TrainerStartBpm)
+                    .to_string(),
+            "Trainer target bpm: ".to_owned()
+                + &self
+                    .get_trainer_value(CurrentlyEditing::TrainerTargetBpm)
+                    .to_string(),
+            "Trainer increment: ".to_owned()
+                + &self
+                    .get_trainer_value(CurrentlyEditing::TrainerIncrement)
+                    .to_string(),
+            "Trainer bars per step: ".to_owned()
+                + &self
+                    .get_trainer_value(CurrentlyEditing::TrainerBarsPerStep)
+                    .to_string(),
+            "Trainer stop at target: ".to_owned() + trainer_stop,
+            "Output device: ".to_owned() + &output_device,
+            "muted: ".to_owned() + is_muted,
+            "Polyrhythm: ".to_owned() + poly_enabled,
+            "Polyrhythm beats per bar: ".to_owned() + &self.get_poly_beats().to_string(),
+            "Polyrhythm sound: ".to_owned() + &self.get_poly_sound_string(),
+            "Silent bar chance: ".to_owned() + &self.get_silent_bar_chance().to_string() + "%",
+            "Stop after: ".to_owned() + &self.get_stop_after_bars_string(),
+            "Pitch: ".to_owned() + &self.get_pitch().to_string() + "x",
+            "MIDI clock: ".to_owned() + midi_enabled,
+            "MIDI port: ".to_owned() + &midi_port,
+            "Ableton Link: ".to_owned() + &self.get_link_string(),
+            "Theme: ".to_owned() + self.theme().name,
+            "Tap accuracy: ".to_owned() + tap_practice,
+            "Visual only (no audio): ".to_owned() + is_silent,
+            "Fade in: ".to_owned() + &self.get_fade_in_string(),
+            "Live preview while editing: ".to_owned() + live_preview,
+            "Output latency: ".to_owned() + &self.get_output_latency().to_string() + " ms",
+            "Calibrate output latency".to_owned(),
+            "Downbeat only: ".to_owned() + downbeat_only,
+            "Setlist: ".to_owned() + &self.get_setlist_string(),
+            "Setlist loop: ".to_owned() + setlist_loop,
+            "Beats per bar: ".to_owned() + &self.get_beats_per_bar_string(),
+            "Final bar cue: ".to_owned() + final_bar_cue,
+            "Accent groups: ".to_owned() + &self.get_accent_groups_string(),
+            "Accent pitch: ".to_owned() + &self.get_accent_pitch_mult().to_string() + "x",
+            "Reference tone: ".to_owned() + &self.get_tone_string(),
+            "Smooth click edges: ".to_owned() + smooth_clicks,
+            "Screen flash: ".to_owned() + screen_flash,
+            "Subdivision sound: ".to_owned() + &subdivision_sound,
+            "Time signature changes: ".to_owned() + time_sig_changes,
+            "Intro sound: ".to_owned() + &self.get_intro_sound_string(),
+            "Pendulum: ".to_owned() + pendulum,
+            "Feel offsets: ".to_owned() + &self.get_feel_offsets_string(),
+            "Click pan: ".to_owned() + &self.get_pan_string(CurrentlyEditing::ClickPan),
+            "Subdivision pan: ".to_owned() + &self.get_pan_string(CurrentlyEditing::SubdivisionPan),
+            "Polyrhythm pan: ".to_owned() + &self.get_pan_string(CurrentlyEditing::PolyPan),
+            "Accent every: ".to_owned() + &self.get_accent_every_string(),
+            "Normalize sound levels: ".to_owned() + normalize_sounds,
+            "Accented beats: ".to_owned() + &self.get_accent_beats_string(),
+            "Tempo map: ".to_owned() + &self.get_tempo_map_string(),
+            "Tempo map changes: ".to_owned() + tempo_map_changes,
+            "Back to main menu".to_owned(),
+        ];
+        // List the sound played on each beat of the bar
+        for (beat, sound) in self.get_beat_sounds().iter().enumerate() {
+            edit_menu_vec.push(
+                "Beat ".to_owned()
+                    + &(beat + 1).to_string()
+                    + " sound: "
+                    + &self.get_sound_name(*sound),
+            );
+        }
+        // Delay times for setting up effects to the tempo
+        edit_menu_vec.push("Delay times: ".to_owned() + &self.get_delay_times_string());
+        // Show where the setlist is up to
+        if let Some(setlist_position) = self.get_setlist_position() {
+            edit_menu_vec.push(setlist_position);
+        }
+        // Remind the user which bpm the number keys jump to
+        let quickslots: Vec<String> = self
+            .config
+            .bpm_quickslots
+            .iter()
+            .enumerate()
+            .map(|(slot, bpm)| (slot + 1).to_string() + ":" + &bpm.to_string())
+            .collect();
+        edit_menu_vec.push("Quickslots: ".to_owned() + &quickslots.join("  "));
+        // Show the result of the last click track export
+        if !self.export_string.is_empty() {
+            edit_menu_vec.push("Last export: ".to_owned() + &self.export_string);
+        }
+        // Explain the silence while the metronome is waiting for an audio output
+        let audio_error = self.get_audio_error();
+        if !audio_error.is_empty() {
+            edit_menu_vec.push(audio_error + ", retrying...");
+        }
+        // Add up the practice logged so far
+        if let Some(practice_totals) = self.get_practice_totals_string() {
+            edit_menu_vec.push("Practice log: ".to_owned() + &practice_totals);
+        }
+        // Warn while the ticks are too close together for the refresh rate to keep them steady
+        if let Some(timing_warning) = self.get_timing_warning() {
+            edit_menu_vec.push(timing_warning);
+        }
+        // Add debug displays
+        if self.settings.debug.load(Ordering::Relaxed) {
+            edit_menu_vec.push("\n// DEBUG // ".to_owned());
+            edit_menu_vec.push(
+                "TICK COUNT: ".to_owned()
+                    + &self.settings.tick_count.load(Ordering::Relaxed).to_string(),
+            );
+            edit_menu_vec.push(
+                "Current NS Delay: ".to_owned()
+                    + &self.settings.ns_delay.load(Ordering::Relaxed).to_string(),
+            );
+            // the timing resolution, ticks can land up to one refresh late
+            edit_menu_vec.push(
+                "REFRESH RATE: ".to_owned()
+                    + &self.tick_rate.to_string()
+                    + " ns ("
+                    + &(self.settings.ns_delay.load(Ordering::Relaxed) / self.tick_rate.max(1))
+                        .to_string()
+                    + " checks per tick)",
+            );
+        }
+        self.edit_menu.set_items(edit_menu_vec);
+
+        // clippy hates this no matter what I do...
+        if let Some(..) = edit_menu_selection {
+            self.edit_menu.select(edit_menu_selection.unwrap());
+        }
+        self.refresh_edit_field_menu();
+    }
+
+    // Starts the practice timer when the metronome starts and clears it when it stops, the metronome thread can stop
+    // itself so this is checked every frame rather than in toggle_metronome. The session stats follow along and are
+    // logged once it stops
+    pub fn refresh_practice_timer(&mut self) {
+        if !self.get_is_running() {
+            self.practice_start = None;
+            self.end_session();
+            return;
+        }
+        if self.practice_start.is_none() {
+            self.practice_start = Some(Instant::now());
+        }
+        let bpm = self.get_bpm();
+        let bar = self.settings.bar_count.load(Ordering::Relaxed);
+        self.session
+            .get_or_insert_with(|| SessionStats::new(bpm))
+            .update(bpm, bar);
+    }
+
+    // Adds the session that just finished to the practice log, sessions shorter than MIN_SESSION_LENGTH are dropped
+    fn end_session(&mut self) {
+        let session = match self.session.take() {
+            Some(session) => session,
+            None => return,
+        };
+        let path = match &self.practice_log_path {
+            Some(path) if session.duration() >= MIN_SESSION_LENGTH => path.clone(),
+            _ => return,
+        };
+        match log_session(&path, &session) {
+            Ok(()) => self.practice_totals = practice_totals(&path),
+            Err(error) => self.alert_string = format!("Could not write to {}, {}", path, error),
+        }
+    }
+
+    // Describes the practice log's totals, ie. "12 sessions, 3h 05m", None before any session has been logged
+    pub fn get_practice_totals_string(&mut self) -> Option<String> {
+        let (sessions, total) = self.practice_totals;
+        if sessions == 0 {
+            return None;
+        }
+        let minutes = total.as_secs() / 60;
+        Some(format!(
+            "{} session{}, {}h {:02}m",
+            sessions,
+            if sessions == 1 { "" } else { "s" },
+            minutes / 60,
+            minutes % 60
+        ))
+    }
+
+    // Flashes the title bar on the first beat of each bar and pulses the beat indicator on every tick, going by the
+    // metronome's beat events so each flash lands when its beat is heard. With screen flash on the whole screen flashes
+    // on every main beat too. None of them flash while the metronome is stopped
+    pub fn refresh_flash(&mut self) {
+        // beats played while the display is frozen are never shown, so they aren't kept for later either
+        if self.display_frozen.is_some() {
+            self.beat_events.try_iter().for_each(drop);
+            return;
+        }
+        self.pending_beats.extend(self.beat_events.try_iter());
+        if !self.get_is_running() {
+            self.flash_frames = 0;
+            self.pulse_frames = 0;
+            self.screen_flash_frames = 0;
+            self.last_pulse = (0, 0);
+            self.pending_beats.clear();
+            return;
+        }
+        let now = Instant::now();
+        let (heard, pending): (Vec<BeatEvent>, Vec<BeatEvent>) = self
+            .pending_beats
+            .drain(..)
+            .partition(|event| event.instant <= now);
+        self.pending_beats = pending;
+        let beats_per_bar = self.settings.beats_per_bar.load(Ordering::Relaxed);
+        let ts_note = self.settings.ts_note.load(Ordering::Relaxed);
+        for event in heard {
+            if event.beat == 1 {
+                self.flash_frames = FLASH_FRAMES;
+            }
+            self.last_pulse = (event.bar, event.beat);
+            self.pulse_frames = PULSE_FRAMES;
+            if self.screen_flash && is_main_beat(event.beat - 1, beats_per_bar, ts_note) {
+                self.screen_flash_frames = SCREEN_FLASH_FRAMES;
+                self.screen_flash_downbeat = event.beat == 1;
+            }
+        }
+    }
+
+    // Counts down the title bar flash, screen flash and beat indicator pulse, called once per ui tick
+    pub fn decay_flash(&mut self) {
+        if self.display_frozen.is_some() {
+            return;
+        }
+        self.flash_frames = self.flash_frames.saturating_sub(1);
+        self.pulse_frames = self.pulse_frames.saturating_sub(1);
+        self.screen_flash_frames = self.screen_flash_frames.saturating_sub(1);
+    }
+
+    // One (main beat, lit) pair for each tick of the bar. Ticks that fall between the beats of the time signature are
+    // subdivisions, and only the tick that just played is lit while its pulse lasts
+    pub fn get_beat_indicator(&mut self) -> Vec<(bool, bool)> {
+        if let Some(frozen) = &self.display_frozen {
+            return frozen.beat_indicator.clone();
+        }
+        let beats_per_bar = self.settings.beats_per_bar.load(Ordering::Relaxed);
+        let ts_note = self.settings.ts_note.load(Ordering::Relaxed);
+        let (_, last_beat) = self.last_pulse;
+        (0..beats_per_bar)
+            .map(|tick| {
+                (
+                    is_main_beat(tick, beats_per_bar, ts_note),
+                    self.pulse_frames > 0 && tick + 1 == last_beat,
+                )
+            })
+            .collect()
+    }
+
+    // How far through the bar the metronome is for the progress gauge, None while it is stopped
+    pub fn get_bar_progress(&mut self) -> Option<f64> {
+        if let Some(frozen) = &self.display_frozen {
+            return frozen.bar_progress;
+        }
+        if !self.get_is_running() {
+            return None;
+        }
+        let last_tick_time = (*self.settings.last_tick_time.lock().unwrap())?;
+        Some(bar_progress(
+            self.settings.current_beat_count.load(Ordering::Relaxed),
+            self.settings.beats_per_bar.load(Ordering::Relaxed),
+            Instant::now().saturating_duration_since(last_tick_time),
+            self.settings.ns_delay.load(Ordering::Relaxed),
+        ))
+    }
+
+    // Where the pendulum is across its swing (0.0 - 1.0), following the metronome's beat phase. None while the pendulum
+    // is off or the metronome is stopped, it waits on the left until the first tick
+    pub fn get_pendulum_position(&mut self) -> Option<f64> {
+        if let Some(frozen) = &self.display_frozen {
+            return frozen.pendulum_position;
+        }
+        if !self.pendulum || !self.get_is_running() {
+            return None;
+        }
+        let beat = self.settings.current_beat_count.load(Ordering::Relaxed);
+        if beat == 0 {
+            return Some(0.0);
+        }
+        let bar = self.settings.bar_count.load(Ordering::Relaxed).max(1);
+        let beats_per_bar = self.settings.beats_per_bar.load(Ordering::Relaxed);
+        // counting every tick since starting keeps the swing alternating across bars with an odd number of ticks
+        let tick = (bar - 1) * beats_per_bar + beat;
+        Some(pendulum_position(
+            tick,
+            self.settings.beat_phase.load(Ordering::Relaxed),
+        ))
+    }
+
+    // Returns how far the output meter is lit while the metronome is running. It stays dark when nothing can be heard,
+    // so a silent meter points to muting, visual only mode or a missing audio output
+    pub fn get_output_level(&mut self) -> Option<f64> {
+        if let Some(frozen) = &self.display_frozen {
+            return frozen.output_level;
+        }
+        if !self.get_is_running() {
+            return None;
+        }
+        if self.get_is_muted() || self.get_is_silent() || !self.get_audio_error().is_empty() {
+            return Some(0.0);
+        }
+        let level = match *self.settings.last_tick_time.lock().unwrap() {
+            Some(last_tick_time) => click_level(
+                Instant::now().saturating_duration_since(last_tick_time),
+                self.get_volume(),
+            ),
+            None => 0.0,
+        };
+        Some(level)
+    }
+
+    pub fn refresh_sound_selection_menu(&mut self) {
+        // list sounds by their display names
+        let display_names = self
+            .sound_list
+            .iter()
+            .map(|sound| self.get_sound_display_name(sound))
+            .collect();
+        self.sound_selection_menu.set_items(display_names);
+        // select the current sound
+        let selected_sound = match self.sound_target {
+            SoundTarget::Main => self.settings.selected_sound.load(Ordering::Relaxed),
+            SoundTarget::Poly => self.settings.poly_sound.load(Ordering::Relaxed),
+            SoundTarget::Subdivision => self.settings.subdivision_sound.load(Ordering::Relaxed),
+            SoundTarget::Intro => self
+                .settings
+                .intro_sound
+                .lock()
+                .unwrap()
+                .unwrap_or(self.settings.selected_sound.load(Ordering::Relaxed)),
+            SoundTarget::Beat(beat) => self.get_beat_sounds()[beat],
+        };
+        self.sound_selection_menu.select(selected_sound);
+    }
+
+    pub fn refresh_device_selection_menu(&mut self) {
+        // list the default device followed by every available output device
+        self.output_device_list = vec!["default".to_owned()];
+        self.output_device_list.extend(get_output_device_names());
+        self.device_selection_menu
+            .set_items(self.output_device_list.clone());
+        // select the current device
+        let device_name = self.get_output_device_string();
+        let selection = self
+            .output_device_list
+            .iter()
+            .skip(1)
+            .position(|name| *name == device_name)
+            .map_or(0, |index| index + 1);
+        self.device_selection_menu.select(selection);
+    }
+
+    // TODO: Separate ui nav code from app -----------------------------------------------------------------------------
+    pub fn update(&mut self, key: KeyEvent) -> Result<String, Report> {
+        let action = self.keymap.action(key.code);
+        self.update_action(key, action)
+    }
+
+    // Left clicking a menu item selects it and then acts as if the select key was pressed
+    pub fn update_mouse(&mut self, mouse: MouseEvent) -> Result<String, Report> {
+        if mouse.kind != MouseEventKind::Down(MouseButton::Left) {
+            return Ok("App updated".to_string());
+        }
+        // only the menu on the current screen can be clicked, and not while a value is being edited
+        let area = match self.current_screen {
+            CurrentScreen::Main => self.main_menu_area,
+            CurrentScreen::Editing if self.currently_editing.is_none() => self.right_panel_area,
+            CurrentScreen::SoundSelection | CurrentScreen::DeviceSelection => self.right_panel_area,
+            _ => return Ok("App updated".to_string()),
+        };
+        let row = match clicked_row(area, mouse.column, mouse.row) {
+            Some(row) => row,
+            None => return Ok("App updated".to_string()),
+        };
+        let menu = match self.current_menu() {
+            Some(menu) => menu,
+            None => return Ok("App updated".to_string()),
+        };
+        let index = menu.state.offset() + row;
+        if index >= menu.items.len() {
+            return Ok("App updated".to_string());
+        }
+        menu.select(index);
+        self.update_action(KeyEvent::from(KeyCode::Null), Some(Action::Select))
+    }
+
+    // Handles a key press, action is what the key is bound to in the keymap. Any setting it changes can be undone
+    fn update_action(&mut self, key: KeyEvent, action: Option<Action>) -> Result<String, Report> {
+        // while the edit popup is open the change is measured from the settings it was opened with, so a live
+        // preview is recorded as a single change once it is saved
+        let before = match &self.edit_original {
+            Some(edit_original) => edit_original.clone(),
+            None => self.snapshot(),
+        };
+        let history_len = self.undo_history.len();
+        let result = self.handle_action(key, action);
+        if self.currently_editing.is_some() {
+            if self.edit_original.is_none() {
+                self.edit_original = Some(before);
+            }
+        } else {
+            self.edit_original = None;
+            // an undo shortens the history and mustn't be recorded as a change itself
+            if self.undo_history.len() == history_len && self.snapshot() != before {
+                self.push_undo(before);
+            }
+        }
+        result
+    }
+
+    fn handle_action(&mut self, key: KeyEvent, action: Option<Action>) -> Result<String, Report> {
+        let mut ask_for_quit = false; // used to prevent pressing q to quit entire program with no warning
+
+        // If in error mode, return error
+        if self.settings.error.load(Ordering::Relaxed) {
+            return Err(eyre!("App.update() Something went wrong!"));
+        }
+        // the help screen scrolls through its list and closes with back or the help key, returning to where it was
+        // opened from. Quitting still works from it
+        if self.current_screen == CurrentScreen::Help && action != Some(Action::Quit) {
+            match action {
+                Some(Action::NavigateUp) => self.help_scroll = self.help_scroll.saturating_sub(1),
+                Some(Action::NavigateDown) => self.help_scroll = self.help_scroll.saturating_add(1),
+                Some(Action::PageUp) => {
+                    self.help_scroll = self.help_scroll.saturating_sub(MENU_PAGE_SIZE as u16)
+                }
+                Some(Action::PageDown) => {
+                    self.help_scroll = self.help_scroll.saturating_add(MENU_PAGE_SIZE as u16)
+                }
+                Some(Action::First) => self.help_scroll = 0,
+                Some(Action::Last) => self.help_scroll = u16::MAX,
+                Some(Action::Back | Action::Help) => self.current_screen = self.help_return_screen,
+                _ => {}
+            }
+            return Ok("App updated".to_string());
+        }
+        // ctrl + p plays the highlighted sound, it is checked first so the p isn't typed into the search
+        if self.current_screen == CurrentScreen::SoundSelection
+            && key.code == KeyCode::Char('p')
+            && key.modifiers.contains(KeyModifiers::CONTROL)
+        {
+            self.preview_current_sound();
+            return Ok("App updated".to_string());
+        }
+        // while a sound is being renamed the pop up takes the typing instead of the search
+        if self.current_screen == CurrentScreen::SoundSelection && self.currently_editing.is_some()
+        {
+            self.update_sound_rename(key, action);
+            return Ok("App updated".to_string());
+        }
+        // ctrl + r gives the highlighted sound a display name
+        if self.current_screen == CurrentScreen::SoundSelection
+            && key.code == KeyCode::Char('r')
+            && key.modifiers.contains(KeyModifiers::CONTROL)
+        {
+            self.start_sound_rename();
+            return Ok("App updated".to_string());
+        }
+        // The sound selection screen captures typed characters to search the sound list
+        if self.current_screen == CurrentScreen::SoundSelection && self.update_sound_filter(key) {
+            return Ok("App updated".to_string());
+        }
+        // the arrows turn the bpm instead of moving through the menu in knob mode
+        if self.knob_key(key) {
+            return Ok("App updated".to_string());
+        }
+        // global keyboard shortcuts and menu navigation controls
+        match action {
+            // navigate menu items
+            Some(
+                Action::NavigateUp
+                | Action::NavigateDown
+                | Action::Back
+                | Action::PageUp
+                | Action::PageDown
+                | Action::First
+                | Action::Last,
+            ) => {
+                self.menu_navigate(action);
+            }
+            // toggle metronome on/off from the main and edit screens
+            Some(Action::ToggleRun) => {
+                if self.currently_editing.is_none()
+                    && matches!(
+                        self.current_screen,
+                        CurrentScreen::Main | CurrentScreen::Editing
+                    )
+                {
+                    self.toggle_metronome();
+                }
+            }
+            // quit at any time
+            Some(Action::Quit) => {
+                if self.current_screen != CurrentScreen::Exiting {
+                    if self.currently_editing.is_some() {
+                        self.discard_preview();
+                    }
+                    self.open_exit_dialog();
+                    ask_for_quit = true;
+                }
+            }
+            // the shortcuts are handled below, after the quickslots
+            _ => {}
+        }
+        // jump to a quickslot bpm on the main screen, or save the bpm to it with shift
+        if self.current_screen == CurrentScreen::Main {
+            if let Some((slot, save)) = quickslot_key(key) {
+                if save {
+                    self.set_quickslot(slot);
+                    self.save_config(CONFIG_PATH);
+                } else {
+                    self.jump_to_quickslot(slot);
+                }
+                return Ok("App updated".to_string());
+            }
+        }
+        // the quit dialog only answers yes or no, so its keys can't change the settings behind it as well
+        if self.current_screen != CurrentScreen::Exiting {
+            match action {
+                // open the help screen
+                Some(Action::Help)
+                    if self.currently_editing.is_none()
+                        && self.current_screen != CurrentScreen::Error =>
+                {
+                    self.help_return_screen = self.current_screen;
+                    self.help_scroll = 0;
+                    self.current_screen = CurrentScreen::Help;
+                    return Ok("App updated".to_string());
+                }
+                // nudge the bpm without opening the editor
+                Some(Action::BpmUp) if self.currently_editing.is_none() => self.nudge_bpm(1),
+                Some(Action::BpmDown) if self.currently_editing.is_none() => self.nudge_bpm(-1),
+                Some(Action::BpmUpTen) if self.currently_editing.is_none() => self.nudge_bpm(10),
+                Some(Action::BpmDownTen) if self.currently_editing.is_none() => self.nudge_bpm(-10),
+                // tap along with the click in tap practice
+                Some(Action::Tap) if self.currently_editing.is_none() => self.record_tap(),
+                // undo the last setting change
+                Some(Action::Undo) if self.currently_editing.is_none() => self.undo(),
+                // step through the subdivisions
+                Some(Action::CycleSubdivision) if self.currently_editing.is_none() => {
+                    self.cycle_subdivision()
+                }
+                // start again from beat 1 without stopping
+                Some(Action::RestartBar) if self.currently_editing.is_none() => self.restart_bar(),
+                // move the split between the control and status panels and remember it
+                Some(Action::NarrowPanel) if self.currently_editing.is_none() => {
+                    self.adjust_panel_width(-PANEL_WIDTH_STEP);
+                    self.save_config(CONFIG_PATH);
+                }
+                Some(Action::WidenPanel) if self.currently_editing.is_none() => {
+                    self.adjust_panel_width(PANEL_WIDTH_STEP);
+                    self.save_config(CONFIG_PATH);
+                }
+                // play or stop the reference tone
+                Some(Action::ToggleTone) if self.currently_editing.is_none() => self.toggle_tone(),
+                // show or hide the debug panel
+                Some(Action::ToggleDebug) if self.currently_editing.is_none() => {
+                    self.toggle_debug()
+                }
+                // switch the up and down arrows between navigating and changing the bpm
+                Some(Action::ToggleKnobMode) if self.currently_editing.is_none() => {
+                    self.toggle_knob_mode()
+                }
+                // nudge the volume without opening the editor
+                Some(Action::VolumeUp) if self.currently_editing.is_none() => {
+                    self.nudge_volume(VOLUME_STEP)
+                }
+                Some(Action::VolumeDown) if self.currently_editing.is_none() => {
+                    self.nudge_volume(-VOLUME_STEP)
+                }
+                // put every beat back on the selected sound with only beat 1 accented
+                Some(Action::ResetBeats) if self.currently_editing.is_none() => {
+                    self.reset_beat_customization()
+                }
+                // jump straight to editing the bpm, volume or time signature
+                Some(Action::EditBpm) if self.can_open_editor() => {
+                    self.open_editor(CurrentlyEditing::Bpm);
+                    return Ok("App updated".to_string());
+                }
+                Some(Action::EditVolume) if self.can_open_editor() => {
+                    self.open_editor(CurrentlyEditing::Volume);
+                    return Ok("App updated".to_string());
+                }
+                Some(Action::EditTimeSignature) if self.can_open_editor() => {
+                    self.open_editor(CurrentlyEditing::TimeSignature);
+                    return Ok("App updated".to_string());
+                }
+                // start the total bar count again
+                Some(Action::ResetTotalBars) if self.currently_editing.is_none() => {
+                    self.reset_total_bars()
+                }
+                // freeze the counters and beat displays, or let them follow the metronome again
+                Some(Action::FreezeDisplay) if self.currently_editing.is_none() => {
+                    self.toggle_display_frozen()
+                }
+                // jump in and out of triplets
+                Some(Action::ToggleTriplets) if self.currently_editing.is_none() => {
+                    self.toggle_triplets()
+                }
+                // play in double or half time, and back to the base tempo. Shifted digits save quickslots on the main
+                // screen, so these stay on letters
+                Some(Action::DoubleTime) if self.currently_editing.is_none() => {
+                    self.toggle_time_feel(TimeFeel::Double)
+                }
+                Some(Action::HalfTime) if self.currently_editing.is_none() => {
+                    self.toggle_time_feel(TimeFeel::Half)
+                }
+                // mute / unmute the click while the metronome keeps counting
+                Some(Action::ToggleMute) if self.currently_editing.is_none() => self.toggle_mute(),
+                // stop every sound straight away
+                Some(Action::StopAllSound) if self.currently_editing.is_none() => {
+                    self.stop_all_sound()
+                }
+                _ => {}
+            }
+        }
+
+        // Screen specific keyboard shortcuts
+        // Main screen ---------------------------------------------------------------------------------------------
+        match self.current_screen {
+            CurrentScreen::Main => {
+                if action == Some(Action::Select) {
+                    let current_selection = self.main_menu.state.selected().unwrap();
+                    // TODO: This is messy and bad, magic numbers are not scalable
+                    match current_selection {
+                        0 => {
+                            // start / stop metronome
+                            self.toggle_metronome();
+                        }
+                        1 => {
+                            // enter edit menu
+                            self.switch_screen(CurrentScreen::Editing);
+                        }
+                        2 => {
+                            // render a click track to a wav file
+                            self.export_click_track(EXPORT_PATH);
+                        }
+                        3 => {
+                            // enter quit menu
+                            self.open_exit_dialog();
+                        }
+                        _ => {}
+                    }
+                }
+            }
+            // Edit screen -----------------------------------------------------------------------------------------
+            CurrentScreen::Editing => match key.code {
+                // When editing a value, add / remove characters from the edit_string
+                KeyCode::Char(value) if action != Some(Action::Select) => {
+                    if self.currently_editing.is_some() {
+                        if self.first_edit {
+                            self.edit_string.clear();
+                            self.first_edit = false;
+                        }
+                        self.edit_string.push(value);
+                        self.preview_edit();
+                    }
+                }
+                KeyCode::Backspace if action != Some(Action::Select) => {
+                    if self.currently_editing.is_some() {
+                        self.edit_string.pop();
+                        self.preview_edit();
+                    }
+                }
+                // When editing a value, save the result or retry if failed
+                _ if action == Some(Action::Select) => {
+                    if let Some(editing) = &self.currently_editing {
+                        match editing {
+                            // the bpm and volume editors write their own alert_string on failure
+                            CurrentlyEditing::Bpm => {
+                                if self.change_bpm_editor() {
+                                    self.edit_menu.select(1);
+                                    self.first_edit = true;
+                                }
+                            }
+                            CurrentlyEditing::Volume => {
+                                if self.change_volume_editor() {
+                                    self.edit_menu.select(2);
+                                    self.first_edit = true;
+                                }
+                            }
+                            CurrentlyEditing::TrainerStartBpm
+                            | CurrentlyEditing::TrainerTargetBpm
+                            | CurrentlyEditing::TrainerIncrement
+                            | CurrentlyEditing::TrainerBarsPerStep => {
+                                let editing = *editing;
+                                if self.change_trainer_editor(editing) {
+                                    self.edit_menu.select(match editing {
+                                        CurrentlyEditing::TrainerStartBpm => 7,
+                                        CurrentlyEditing::TrainerTargetBpm => 8,
+                                        CurrentlyEditing::TrainerIncrement => 9,
+                                        _ => 10,
+                                    });
+                                    self.first_edit = true;
+                                } else {
+                                    self.alert_string = match editing {
+                                        CurrentlyEditing::TrainerIncrement => {
+                                            "Please input a value between -100 and 100".to_owned()
+                                        }
+                                        CurrentlyEditing::TrainerBarsPerStep => {
+                                            "Please input a value between 1 and 64".to_owned()
+                                        }
+                                        _ => "Please input a value between 20 and 500".to_owned(),
+                                    };
+                                }
+                            }
+                            CurrentlyEditing::StopAfterBars => {
+                                if self.change_stop_after_bars_editor() {
+                                    self.edit_menu.select(18);
+                                    self.first_edit = true;
+                                } else {
+                                    self.alert_string =
+                                        "Please input a value between 0 and 999".to_owned();
+                                }
+                            }
+                            CurrentlyEditing::FadeInBars => {
+                                if self.change_fade_in_bars_editor() {
+                                    self.edit_menu.select(26);
+                                    self.first_edit = true;
+                                } else {
+                                    self.alert_string =
+                                        "Please input a value between 0 and 16".to_owned();
+                                }
+                            }
+                            CurrentlyEditing::TimeSignature => {
+                                if self.change_time_signature_editor() {
+                                    self.edit_menu.select(4);
+                                    self.first_edit = true;
+                                } else {
+                                    self.alert_string =
+                                        "Please input a time signature like 4/4 or 7/8".to_owned();
+                                }
+                            }
+                            CurrentlyEditing::BeatsPerBar => {
+                                if self.change_beats_per_bar_editor() {
+                                    self.edit_menu.select(33);
+                                    self.first_edit = true;
+                                } else {
+                                    self.alert_string =
+                                        "Please input a value between 0 and 64".to_owned();
+                                }
+                            }
+                            CurrentlyEditing::AccentPitch => {
+                                if self.change_accent_pitch_editor() {
+                                    self.edit_menu.select(36);
+                                    self.first_edit = true;
+                                } else {
+                                    self.alert_string =
+                                        "Please input a value between 0.25 and 4.0".to_owned();
+                                }
+                            }
+                            // renamed from the sound selection screen, see update_sound_rename
+                            CurrentlyEditing::SoundName => {}
+                            CurrentlyEditing::ToneHz => {
+                                if self.change_tone_editor() {
+                                    self.edit_menu.select(37);
+                                    self.first_edit = true;
+                                } else {
+                                    self.alert_string = format!(
+                                        "Please input a note like A or Bb, or a frequency between {} and {} Hz",
+                                        MIN_TONE_HZ, MAX_TONE_HZ
+                                    );
+                                }
+                            }
+                            CurrentlyEditing::ClickPan
+                            | CurrentlyEditing::SubdivisionPan
+                            | CurrentlyEditing::PolyPan => {
+                                let editing = *editing;
+                                if self.change_pan_editor(editing) {
+                                    self.edit_menu.select(match editing {
+                                        CurrentlyEditing::ClickPan => 45,
+                                        CurrentlyEditing::SubdivisionPan => 46,
+                                        _ => 47,
+                                    });
+                                    self.first_edit = true;
+                                } else {
+                                    self.alert_string =
+                                        "Please input a pan between -100 (left) and 100 (right)"
+                                            .to_owned();
+                                }
+                            }
+                            CurrentlyEditing::AccentEvery => {
+                                if self.change_accent_every_editor() {
+                                    self.edit_menu.select(48);
+                                    self.first_edit = true;
+                                } else {
+                                    self.alert_string =
+                                        "Please input a value between 0 and 64".to_owned();
+                                }
+                            }
+                            CurrentlyEditing::FeelOffsets => {
+                                if self.change_feel_offsets_editor() {
+                                    self.edit_menu.select(44);
+                                    self.first_edit = true;
+                                } else {
+                                    self.alert_string = format!(
+                                        "Please input milliseconds between -{} and {} for each beat, ie. 0 10 0 -5",
+                                        MAX_FEEL_OFFSET_MS, MAX_FEEL_OFFSET_MS
+                                    );
+                                }
+                            }
+                            CurrentlyEditing::AccentBeats => {
+                                if self.change_accent_beats_editor() {
+                                    self.edit_menu.select(50);
+                                    self.first_edit = true;
+                                } else {
+                                    self.alert_string = format!(
+                                        "Please input beats between 1 and {}, ie. 4 or 1 3",
+                                        self.settings.beats_per_bar.load(Ordering::Relaxed)
+                                    );
+                                }
+                            }
+                            CurrentlyEditing::AccentGroups => {
+                                if self.change_accent_groups_editor() {
+                                    self.edit_menu.select(35);
+                                    self.first_edit = true;
+                                } else {
+                                    self.alert_string = format!(
+                                        "Please input groups adding up to {} beats, ie. 2+2+3",
+                                        self.settings.beats_per_bar.load(Ordering::Relaxed)
+                                    );
+                                }
+                            }
+                            CurrentlyEditing::OutputLatency => {
+                                if self.change_output_latency_editor() {
+                                    self.edit_menu.select(28);
+                                    self.first_edit = true;
+                                } else {
+                                    self.alert_string = format!(
+                                        "Please input a value between 0 and {}",
+                                        MAX_OUTPUT_LATENCY_MS
+                                    );
+                                }
+                            }
+                            CurrentlyEditing::Pitch => {
+                                if self.change_pitch_editor() {
+                                    self.edit_menu.select(19);
+                                    self.first_edit = true;
+                                } else {
+                                    self.alert_string =
+                                        "Please input a value between 0.25 and 4.0".to_owned();
+                                }
+                            }
+                            CurrentlyEditing::SilentBarChance => {
+                                if self.change_silent_bar_editor() {
+                                    self.edit_menu.select(17);
+                                    self.first_edit = true;
+                                } else {
+                                    self.alert_string =
+                                        "Please input a value between 0 and 100".to_owned();
+                                }
+                            }
+                            CurrentlyEditing::PolyBeats => {
+                                if self.change_poly_beats_editor() {
+                                    self.edit_menu.select(15);
+                                    self.first_edit = true;
+                                } else {
+                                    self.alert_string =
+                                        "Please input a value between 1 and 16".to_owned();
+                                }
+                            }
+                        }
+                    } else {
+                        // Main edit menu --------------------------------------------
+                        // TODO: This is messy and bad, magic numbers are not scalable
+                        let current_selection = self.edit_menu.state.selected().unwrap();
+                        match current_selection {
+                            0 => {
+                                // start / stop metronome
+                                self.toggle_metronome()
+                            }
+                            1 => {
+                                // edit bpm
+                                self.edit_string = self.get_bpm().to_string();
+                                self.currently_editing = Some(CurrentlyEditing::Bpm);
+                                self.edit_menu.deselect();
+                            }
+                            2 => {
+                                // edit volume
+                                self.edit_string = self.get_volume().to_string();
+                                self.currently_editing = Some(CurrentlyEditing::Volume);
+                                self.edit_menu.deselect();
+                            }
+                            3 => {
+                                // sound selection menu
+                                self.sound_target = SoundTarget::Main;
+                                self.switch_screen(CurrentScreen::SoundSelection);
+                            }
+                            4 => {
+                                // edit time signature
+                                self.open_editor(CurrentlyEditing::TimeSignature);
+                            }
+                            5 => {
+                                // bar count display, do nothing
+                            }
+                            6 => {
+                                // toggle tempo trainer
+                                self.toggle_trainer();
+                            }
+                            7..=10 => {
+                                // edit tempo trainer settings
+                                let editing = match current_selection {
+                                    7 => CurrentlyEditing::TrainerStartBpm,
+                                    8 => CurrentlyEditing::TrainerTargetBpm,
+                                    9 => CurrentlyEditing::TrainerIncrement,
+                                    _ => CurrentlyEditing::TrainerBarsPerStep,
+                                };
+                                self.edit_string = self.get_trainer_value(editing).to_string();
+                                self.currently_editing = Some(editing);
+                                self.edit_menu.deselect();
+                            }
+                            11 => {
+                                // toggle stopping once the trainer target is reached
+                                self.toggle_trainer_stop_at_target();
+                            }
+                            12 => {
+                                // output device selection menu
+                                self.switch_screen(CurrentScreen::DeviceSelection);
+                            }
+                            13 => {
+                                // mute / unmute
+                                self.toggle_mute();
+                            }
+                            14 => {
+                                // toggle polyrhythm voice
+                                self.toggle_poly();
+                            }
+                            15 => {
+                                // edit polyrhythm beats per bar
+                                self.edit_string = self.get_poly_beats().to_string();
+                                self.currently_editing = Some(CurrentlyEditing::PolyBeats);
+                                self.edit_menu.deselect();
+                            }
+                            16 => {
+                                // polyrhythm sound selection menu
+                                self.sound_target = SoundTarget::Poly;
+                                self.switch_screen(CurrentScreen::SoundSelection);
+                            }
+                            17 => {
+                                // edit the chance of a silent bar
+                                self.edit_string = self.get_silent_bar_chance().to_string();
+                                self.currently_editing = Some(CurrentlyEditing::SilentBarChance);
+                                self.edit_menu.deselect();
+                            }
+                            18 => {
+                                // edit the number of bars to play before stopping
+                                self.edit_string = self.get_stop_after_bars().to_string();
+                                self.currently_editing = Some(CurrentlyEditing::StopAfterBars);
+                                self.edit_menu.deselect();
+                            }
+                            19 => {
+                                // edit the pitch of the sounds
+                                self.edit_string = self.get_pitch().to_string();
+                                self.currently_editing = Some(CurrentlyEditing::Pitch);
+                                self.edit_menu.deselect();
+                            }
+                            20 => {
+                                // toggle sending MIDI clock
+                                self.toggle_midi();
+                            }
+                            21 => {
+                                // send MIDI clock to the next port
+                                let port_names = get_midi_port_names();
+                                self.cycle_midi_port(&port_names);
+                            }
+                            22 => {
+                                // toggle syncing with an Ableton Link session
+                                self.toggle_link();
+                            }
+                            23 => {
+                                // switch to the next color theme and remember it
+                                self.cycle_theme();
+                                self.save_config(CONFIG_PATH);
+                            }
+                            24 => {
+                                // toggle scoring taps against the beat
+                                self.toggle_tap_practice();
+                            }
+                            25 => {
+                                // toggle visual only mode
+                                self.toggle_silent();
+                            }
+                            26 => {
+                                // edit the number of bars to fade in over
+                                self.edit_string = self.get_fade_in_bars().to_string();
+                                self.currently_editing = Some(CurrentlyEditing::FadeInBars);
+                                self.edit_menu.deselect();
+                            }
+                            27 => {
+                                // toggle hearing the bpm and volume while they are typed
+                                self.toggle_live_preview();
+                            }
+                            28 => {
+                                // edit the output latency
+                                self.edit_string = self.get_output_latency().to_string();
+                                self.currently_editing = Some(CurrentlyEditing::OutputLatency);
+                                self.edit_menu.deselect();
+                            }
+                            29 => {
+                                // tap along with the click to measure the output latency
+                                self.start_calibration();
+                            }
+                            30 => {
+                                // toggle playing only the first beat of each bar
+                                self.toggle_downbeat_only();
+                            }
+                            31 => {
+                                // toggle playing through the setlist
+                                self.toggle_setlist();
+                            }
+                            32 => {
+                                // toggle going back to the first section after the last
+                                self.toggle_setlist_loop();
+                            }
+                            33 => {
+                                // edit the number of beats counted per bar
+                                self.edit_string = self.get_beats_per_bar_override().to_string();
+                                self.currently_editing = Some(CurrentlyEditing::BeatsPerBar);
+                                self.edit_menu.deselect();
+                            }
+                            34 => {
+                                // toggle pitching up the last bar before stopping
+                                self.toggle_final_bar_cue();
+                            }
+                            35 => {
+                                // edit how the beats of the bar are grouped for accents
+                                self.edit_string = self.get_accent_groups_string();
+                                self.currently_editing = Some(CurrentlyEditing::AccentGroups);
+                                self.edit_menu.deselect();
+                            }
+                            36 => {
+                                // edit how much higher the accented beats are pitched
+                                self.edit_string = self.get_accent_pitch_mult().to_string();
+                                self.currently_editing = Some(CurrentlyEditing::AccentPitch);
+                                self.edit_menu.deselect();
+                            }
+                            37 => {
+                                // edit the reference tone's note or frequency
+                                self.edit_string = self.get_tone_hz().to_string();
+                                self.currently_editing = Some(CurrentlyEditing::ToneHz);
+                                self.edit_menu.deselect();
+                            }
+                            38 => {
+                                // toggle fading each sound in so it doesn't pop
+                                self.toggle_smooth_clicks();
+                            }
+                            39 => {
+                                // toggle flashing the whole screen on every beat
+                                self.toggle_screen_flash();
+                            }
+                            40 => {
+                                // subdivision sound selection menu
+                                self.sound_target = SoundTarget::Subdivision;
+                                self.switch_screen(CurrentScreen::SoundSelection);
+                            }
+                            41 => {
+                                // toggle holding time signature changes until the next bar
+                                self.toggle_time_sig_on_downbeat();
+                            }
+                            42 => {
+                                // intro sound selection menu, or turn the intro off if there is one
+                                if self.settings.intro_sound.lock().unwrap().take().is_none() {
+                                    self.sound_target = SoundTarget::Intro;
+                                    self.switch_screen(CurrentScreen::SoundSelection);
+                                }
+                            }
+                            43 => {
+                                // toggle the pendulum under the main menu
+                                self.toggle_pendulum();
+                            }
+                            44 => {
+                                // edit how far each beat is played from the click
+                                let offsets: Vec<String> = self
+                                    .settings
+                                    .beat_offsets
+                                    .lock()
+                                    .unwrap()
+                                    .iter()
+                                    .map(|offset| offset.to_string())
+                                    .collect();
+                                self.edit_string = offsets.join(" ");
+                                self.currently_editing = Some(CurrentlyEditing::FeelOffsets);
+                                self.edit_menu.deselect();
+                            }
+                            45..=47 => {
+                                // edit where the beats, subdivisions or polyrhythm are placed between the speakers
+                                let editing = match current_selection {
+                                    45 => CurrentlyEditing::ClickPan,
+                                    46 => CurrentlyEditing::SubdivisionPan,
+                                    _ => CurrentlyEditing::PolyPan,
+                                };
+                                self.edit_string = self.get_pan(editing).to_string();
+                                self.currently_editing = Some(editing);
+                                self.edit_menu.deselect();
+                            }
+                            48 => {
+                                // edit how many beats apart the accents that run across the bar lines are
+                                self.edit_string = self.get_accent_every().to_string();
+                                self.currently_editing = Some(CurrentlyEditing::AccentEvery);
+                                self.edit_menu.deselect();
+                            }
+                            49 => {
+                                // toggle evening out the levels of the sounds
+                                self.toggle_normalize_sounds();
+                            }
+                            50 => {
+                                // edit which beats of the bar are accented
+                                self.edit_string = self.get_accent_beats_string();
+                                if self.edit_string == "off" {
+                                    self.edit_string.clear();
+                                }
+                                self.currently_editing = Some(CurrentlyEditing::AccentBeats);
+                                self.edit_menu.deselect();
+                            }
+                            51 => {
+                                // toggle following the tempo map
+                                self.toggle_tempo_map();
+                            }
+                            52 => {
+                                // toggle sliding between the tempo map's changes instead of jumping
+                                self.toggle_tempo_map_interpolate();
+                            }
+                            53 => {
+                                // back to main menu
+                                self.switch_screen(CurrentScreen::Main);
+                            }
+                            _ => {
+                                // per beat sound selection, listed after the back option
+                                let beat = current_selection - 54;
+                                if beat < self.get_beat_sounds().len() {
+                                    self.sound_target = SoundTarget::Beat(beat);
+                                    self.switch_screen(CurrentScreen::SoundSelection);
+                                }
+                            }
+                        }
+                    }
+                }
+                _ => {}
+            },
+            // Sound Selection Screen ------------------------------------------------------------------------------
+            CurrentScreen::SoundSelection => {
+                if action == Some(Action::Select) {
+                    // the menu may be filtered so look the selection up by name
+                    if let Some(selection) = self.get_highlighted_sound() {
+                        match self.sound_target {
+                            SoundTarget::Main => self.set_selected_sound(selection),
+                            SoundTarget::Poly => {
+                                self.settings.poly_sound.swap(selection, Ordering::Relaxed);
+                            }
+                            SoundTarget::Subdivision => {
+                                self.settings
+                                    .subdivision_sound
+                                    .swap(selection, Ordering::Relaxed);
+                            }
+                            SoundTarget::Intro => {
+                                *self.settings.intro_sound.lock().unwrap() = Some(selection);
+                            }
+                            SoundTarget::Beat(beat) => self.set_beat_sound(beat, selection),
+                        }
+                    }
+                    self.switch_screen(CurrentScreen::Editing);
+                    match self.sound_target {
+                        SoundTarget::Main => {}
+                        SoundTarget::Poly => self.edit_menu.select(16),
+                        SoundTarget::Subdivision => self.edit_menu.select(40),
+                        SoundTarget::Intro => self.edit_menu.select(42),
+                        SoundTarget::Beat(beat) => self.edit_menu.select(54 + beat),
+                    }
+                }
+            }
+            // Device Selection Screen -----------------------------------------------------------------------------
+            CurrentScreen::DeviceSelection => {
+                if action == Some(Action::Select) {
+                    let selection = self.device_selection_menu.state.selected().unwrap();
+                    if selection == 0 {
+                        self.set_output_device(String::new());
+                    } else if selection < self.output_device_list.len() {
+                        let device_name = self.output_device_list[selection].clone();
+                        self.set_output_device(device_name);
+                    }
+                    self.switch_screen(CurrentScreen::Editing);
+                    self.edit_menu.select(12);
+                }
+            }
+            // Exit screen -----------------------------------------------------------------------------------------
+            CurrentScreen::Exiting => {
+                if key.code == KeyCode::Char('y')
+                    || matches!(action, Some(Action::Quit | Action::Select))
+                {
+                    // Quit
+                    if !ask_for_quit {
+                        self.should_quit = true;
+                    }
+                } else if matches!(
+                    key.code,
+                    KeyCode::Char('n') | KeyCode::Backspace | KeyCode::Tab
+                ) || action == Some(Action::Back)
+                {
+                    // Return to the screen the dialog was opened from, where it was left
+                    self.current_screen = self.exit_return_screen;
+                    self.first_edit = true;
+                    if let Some(menu) = self.current_menu() {
+                        menu.restore();
+                    }
+                }
+            }
+            // Calibration screen ------------------------------------------------------------------------------------
+            CurrentScreen::Calibration => {
+                if key.code == KeyCode::Char(' ') {
+                    self.record_calibration_tap();
+                } else if action == Some(Action::Select) && self.apply_suggested_latency() {
+                    self.switch_screen(CurrentScreen::Editing);
+                    self.edit_menu.select(28);
+                }
+            }
+            // Help screen is closed before reaching here
+            CurrentScreen::Help => {}
+            // Error screen ----------------------------------------------------------------------------------------
+            CurrentScreen::Error => {
+                // Press any char to quit, could not find an "any" keybind in Crossterm
+                if let KeyCode::Char(_) = key.code {
+                    return Err(eyre!(
+                        "ReadyMetronome experienced a terminal error! Sorry about that..."
+                    ));
+                }
+            }
+        }
+
+        Ok("App updated".to_string())
+    }
+
+    // Opens the pop up to give the highlighted sound a display name, starting from the name it has now
+    pub fn start_sound_rename(&mut self) {
+        let sound = match self.get_highlighted_sound() {
+            Some(sound) => sound,
+            None => return,
+        };
+        let file_name = self.sound_list[sound].clone();
+        self.edit_string = self.get_sound_display_name(&file_name);
+        self.renaming_sound = Some(file_name);
+        self.currently_editing = Some(CurrentlyEditing::SoundName);
+        self.first_edit = true;
+    }
+
+    // Types into the sound name pop up, enter saves the name to the config and esc leaves it as it was
+    fn update_sound_rename(&mut self, key: KeyEvent, action: Option<Action>) {
+        match key.code {
+            _ if action == Some(Action::Select) => {
+                self.change_sound_name_editor();
+                self.save_config(CONFIG_PATH);
+            }
+            _ if action == Some(Action::Back) => {
+                self.renaming_sound = None;
+                self.currently_editing = None;
+                self.clear_strings();
+                self.first_edit = true;
+            }
+            KeyCode::Char(value) => {
+                if self.first_edit {
+                    self.edit_string.clear();
+                    self.first_edit = false;
+                }
+                self.edit_string.push(value);
+            }
+            KeyCode::Backspace => {
+                self.edit_string.pop();
+            }
+            _ => {}
+        }
+    }
+
+    // Saves the edit_string as the display name of the sound being renamed, an empty name or the file name itself
+    // goes back to showing the file name. The config still needs saving to keep it
+    pub fn change_sound_name_editor(&mut self) {
+        let file_name = match self.renaming_sound.take() {
+            Some(file_name) => file_name,
+            None => return,
+        };
+        let display_name = self.edit_string.trim().to_owned();
+        if display_name.is_empty() || display_name == file_name {
+            self.config.sound_display_names.remove(&file_name);
+        } else {
+            self.config
+                .sound_display_names
+                .insert(file_name.clone(), display_name);
+        }
+        self.clear_strings();
+        self.currently_editing = None;
+        self.first_edit = true;
+        // the list is rebuilt with the new name, keeping the renamed sound highlighted
+        self.refresh_sound_selection_menu();
+        if let Some(sound) = self.sound_list.iter().position(|sound| *sound == file_name) {
+            self.sound_selection_menu.select(sound);
+        }
+    }
+
+    // Adds / removes characters from the sound selection search filter, returns true if the key was used
+    fn update_sound_filter(&mut self, key: KeyEvent) -> bool {
+        let mut filter = self.sound_selection_menu.filter.clone();
+        let action = self.keymap.action(key.code);
+        match key.code {
+            // back clears the search first, and goes back to the edit menu once it is empty
+            _ if action == Some(Action::Back) => {
+                if filter.is_empty() {
+                    return false;
+                }
+                filter.clear();
+            }
+            KeyCode::Char(value) => filter.push(value),
+            KeyCode::Backspace => {
+                filter.pop();
+            }
+            _ => return false,
+        }
+        self.sound_selection_menu.set_filter(&filter);
+        true
+    }
+
+    fn switch_screen(&mut self, new_screen: CurrentScreen) {
+        match new_screen {
+            CurrentScreen::Main => {
+                self.edit_menu.deselect();
+                self.sound_selection_menu.deselect();
+                self.device_selection_menu.deselect();
+                self.first_edit = true;
+                self.main_menu.restore();
+            }
+            CurrentScreen::Editing => {
+                self.main_menu.deselect();
+                self.sound_selection_menu.deselect();
+                self.device_selection_menu.deselect();
+                self.edit_menu.restore();
+            }
+            CurrentScreen::SoundSelection => {
+                self.main_menu.deselect();
+                self.edit_menu.deselect();
+                self.refresh_sound_list();
+                self.refresh_sound_selection_menu();
+            }
+            CurrentScreen::DeviceSelection => {
+                self.main_menu.deselect();
+                self.edit_menu.deselect();
+                self.refresh_device_selection_menu();
+            }
+            CurrentScreen::Exiting => {
+                self.main_menu.deselect();
+                self.edit_menu.deselect();
+                self.sound_selection_menu.deselect();
+                self.device_selection_menu.deselect();
+                self.currently_editing = None;
+                self.clear_strings();
+            }
+            CurrentScreen::Calibration => {
+                self.main_menu.deselect();
+                self.edit_menu.deselect();
+            }
+            CurrentScreen::Help => {}
+            CurrentScreen::Error => {
+                // Probably unnecessary but might as well while I'm here?
+                self.main_menu.deselect();
+                self.edit_menu.deselect();
+                self.sound_selection_menu.deselect();
+                self.device_selection_menu.deselect();
+            }
+        }
+        self.current_screen = new_screen;
+    }
+
+    // Asks whether to really quit, remembering the screen to go back to if the answer is no. A value being edited is
+    // dropped, and the menus keep their place for when the dialog is closed
+    fn open_exit_dialog(&mut self) {
+        self.exit_return_screen = self.current_screen;
+        self.switch_screen(CurrentScreen::Exiting);
+    }
+
+    // Whether a setting's edit pop up can be opened by its hotkey, only from the main and edit screens and never while
+    // a value is already being typed
+    fn can_open_editor(&self) -> bool {
+        self.currently_editing.is_none()
+            && matches!(
+                self.current_screen,
+                CurrentScreen::Main | CurrentScreen::Editing
+            )
+    }
+
+    // Opens the edit pop up for a setting filled in with its current value, moving to the edit screen if needed
+    fn open_editor(&mut self, editing: CurrentlyEditing) {
+        if self.current_screen != CurrentScreen::Editing {
+            self.switch_screen(CurrentScreen::Editing);
+        }
+        self.edit_string = self.get_edit_value_string(editing);
+        self.currently_editing = Some(editing);
+        self.first_edit = true;
+        self.edit_menu.deselect();
+    }
+
+    // Returns the menu being navigated on the current screen, if there is one
+    fn current_menu(&mut self) -> Option<&mut Menu> {
+        match self.current_screen {
+            CurrentScreen::Main => Some(&mut self.main_menu),
+            CurrentScreen::Editing if self.currently_editing.is_none() => Some(&mut self.edit_menu),
+            CurrentScreen::SoundSelection => Some(&mut self.sound_selection_menu),
+            CurrentScreen::DeviceSelection => Some(&mut self.device_selection_menu),
+            _ => None,
+        }
+    }
+
+    fn menu_navigate(&mut self, action: Option<Action>) {
+        match action {
+            Some(Action::PageUp) => {
+                if let Some(menu) = self.current_menu() {
+                    menu.page_up(MENU_PAGE_SIZE);
+                }
+            }
+            Some(Action::PageDown) => {
+                if let Some(menu) = self.current_menu() {
+                    menu.page_down(MENU_PAGE_SIZE);
+                }
+            }
+            Some(Action::First) => {
+                if let Some(menu) = self.current_menu() {
+                    menu.first();
+                }
+            }
+            Some(Action::Last) => {
+                if let Some(menu) = self.current_menu() {
+                    menu.last();
+                }
+            }
+            Some(Action::NavigateUp) => match self.current_screen {
+                CurrentScreen::Main => {
+                    self.main_menu.previous();
+                }
+                CurrentScreen::Editing => {
+                    if self.currently_editing.is_none() {
+                        self.edit_menu.previous();
+                    }
+                }
+                CurrentScreen::SoundSelection => {
+                    self.sound_selection_menu.previous();
+                }
+                CurrentScreen::DeviceSelection => {
+                    self.device_selection_menu.previous();
+                }
+                CurrentScreen::Exiting
+                | CurrentScreen::Calibration
+                | CurrentScreen::Help
+                | CurrentScreen::Error => {}
+            },
+            Some(Action::NavigateDown) => match self.current_screen {
+                CurrentScreen::Main => {
+                    self.main_menu.next();
+                }
+                CurrentScreen::Editing => {
+                    if self.currently_editing.is_none() {
+                        self.edit_menu.next();
+                    }
+                }
+                CurrentScreen::SoundSelection => {
+                    self.sound_selection_menu.next();
+                }
+                CurrentScreen::DeviceSelection => {
+                    self.device_selection_menu.next();
+                }
+                CurrentScreen::Exiting
+                | CurrentScreen::Calibration
+                | CurrentScreen::Help
+                | CurrentScreen::Error => {}
+            },
+            Some(Action::Back) => {
+                match self.current_screen {
+                    CurrentScreen::Main => {}
+                    CurrentScreen::Editing => {
+                        // if in EditMode return to EditScreen, if in EditScreen return to MainScreen
+                        if self.currently_editing.is_some() {
+                            self.discard_preview();
+                            self.edit_menu.restore();
+                            self.currently_editing = None;
+                            self.clear_strings();
+                            self.first_edit = true;
+                        } else {
+                            self.current_screen = CurrentScreen::Main;
+                            self.edit_menu.deselect();
+                            self.main_menu.restore();
+                        }
+                    }
+                    CurrentScreen::SoundSelection | CurrentScreen::DeviceSelection => {
+                        self.switch_screen(CurrentScreen::Editing);
+                    }
+                    CurrentScreen::Calibration => {
+                        self.switch_screen(CurrentScreen::Editing);
+                        self.edit_menu.select(29);
+                    }
+                    CurrentScreen::Exiting | CurrentScreen::Help | CurrentScreen::Error => {}
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+impl Drop for App {
+    fn drop(&mut self) {
+        self.stop_metronome_thread();
+    }
+}
+
+// Returns which quickslot (counting from 0) a number key picks and whether it saves to the slot. Shift + 1 - 9 saves,
+// most terminals send the shifted symbol of a US layout for these instead of the digit
+fn quickslot_key(key: KeyEvent) -> Option<(usize, bool)> {
+    const SHIFTED_DIGITS: [char; 9] = ['!', '@', '#', '$', '%', '^', '&', '*', '('];
+    let key_char = match key.code {
+        KeyCode::Char(key_char) => key_char,
+        _ => return None,
+    };
+    if let Some(slot) = SHIFTED_DIGITS.iter().position(|digit| *digit == key_char) {
+        return Some((slot, true));
+    }
+    match key_char.to_digit(10) {
+        Some(digit @ 1..=9) => Some((
+            digit as usize - 1,
+            key.modifiers.contains(KeyModifiers::SHIFT),
+        )),
+        _ => None,
+    }
+}
+
+// Reads accent groups written like "2+2+3" (commas or spaces work too), None if any group isn't a number of beats.
+// An empty string gives no groups
+fn parse_accent_groups(text: &str) -> Option<Vec<u64>> {
+    text.split(|c: char| c == '+' || c == ',' || c.is_whitespace())
+        .filter(|group| !group.is_empty())
+        .map(|group| group.parse::<u64>().ok().filter(|beats| *beats > 0))
+        .collect()
+}
+
+// The bpm played at a time feel from the base tempo, kept within 20 - 500 bpm
+fn time_feel_bpm(base_bpm: f64, time_feel: TimeFeel) -> f64 {
+    let bpm = match time_feel {
+        TimeFeel::Normal => base_bpm,
+        TimeFeel::Double => base_bpm * 2.0,
+        TimeFeel::Half => base_bpm / 2.0,
+    };
+    round_bpm(bpm.clamp(20.0, 500.0))
+}
+
+// Reads accented beats counting from 1 written like "1 3" (commas work too) and returns them counting from 0, None if
+// any isn't a beat number. An empty string gives no beats
+fn parse_accent_beats(text: &str) -> Option<Vec<u64>> {
+    text.split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|beat| !beat.is_empty())
+        .map(|beat| {
+            beat.parse::<u64>()
+                .ok()
+                .filter(|beat| *beat > 0)
+                .map(|beat| beat - 1)
+        })
+        .collect()
+}
+
+// Reads feel offsets in milliseconds written like "0 10 0 -5" (commas work too), None if any offset isn't a whole
+// number within MAX_FEEL_OFFSET_MS. An empty string gives no offsets
+fn parse_feel_offsets(text: &str) -> Option<Vec<i64>> {
+    text.split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|offset| !offset.is_empty())
+        .map(|offset| {
+            offset
+                .parse::<i64>()
+                .ok()
+                .filter(|offset| offset.abs() <= MAX_FEEL_OFFSET_MS)
+        })
+        .collect()
+}
+
+// Returns which row of a bordered list was clicked, if the click landed inside of it
+fn clicked_row(area: Rect, column: u16, row: u16) -> Option<usize> {
+    let inside_x = column > area.x && column + 1 < area.x + area.width;
+    let inside_y = row > area.y && row + 1 < area.y + area.height;
+    if inside_x && inside_y {
+        Some((row - area.y - 1) as usize)
+    } else {
+        None
+    }
+}
+
+// Tests ---------------------------------------------------------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::metronome::DEFAULT_ACCENT_PITCH;
+    use crate::setlist::Section;
+    use crate::tempo_map::TempoPoint;
+    use std::sync::Arc;
+
+    const TEST_SETTINGS: InitMetronomeSettings = InitMetronomeSettings {
+        bpm: 120.0,
+        ts_note: 4,
+        ts_value: 4,
+        volume: 100.0,
+        is_running: false,
+        debug: false,
+    };
+
+    const TEST_TICK_RATE: u64 = 7;
+
+    // helper functions should return their values
+    #[test]
+    fn app_get_bpm() {
+        let mut test_app = App::new(TEST_SETTINGS, TEST_TICK_RATE);
+        assert_eq!(test_app.get_bpm(), 120.0);
+    }
+
+    #[test]
+    fn app_get_volume() {
+        let mut test_app = App::new(TEST_SETTINGS, TEST_TICK_RATE);
+        assert_eq!(test_app.get_volume(), 100.0);
+    }
+
+    #[test]
+    fn app_get_is_running() {
+        let mut test_app = App::new(TEST_SETTINGS, TEST_TICK_RATE);
+        assert_eq!(test_app.get_is_running(), false);
+    }
+
+    // change functions should change the internal state of app based on edit_string
+    #[test]
+    fn app_change_bpm_editor() {
+        let mut test_app = App::new(TEST_SETTINGS, TEST_TICK_RATE);
+        test_app.edit_string = "200".to_string();
+        test_app.change_bpm_editor();
+        assert_eq!(test_app.get_bpm(), 200.0);
+    }
+
+    // app::change_bpm should not change bpm with invalid input
+    #[test]
+    fn app_change_bpm_bad_input() {
+        let mut test_app = App::new(TEST_SETTINGS, TEST_TICK_RATE);
+        test_app.edit_string = "hey this isn't a number is it?".to_string();
+        assert_eq!(test_app.change_bpm_editor(), false);
+        assert_eq!(test_app.get_bpm(), 120.0);
+    }
+
+    #[test]
+    fn app_change_bpm_value_too_big() {
+        let mut test_app = App::new(TEST_SETTINGS, TEST_TICK_RATE);
+        test_app.edit_string = "500000".to_string();
+        assert_eq!(test_app.change_bpm_editor(), false);
+        assert_eq!(test_app.get_bpm(), 120.0);
+    }
+
+    #[test]
+    fn app_change_bpm_value_too_small() {
+        let mut test_app = App::new(TEST_SETTINGS, TEST_TICK_RATE);
+        test_app.edit_string = "19".to_string();
+        assert_eq!(test_app.change_bpm_editor(), false);
+        assert_eq!(test_app.get_bpm(), 120.0);
+    }
+
+    #[test]
+    fn app_change_bpm_value_negative() {
+        let mut test_app = App::new(TEST_SETTINGS, TEST_TICK_RATE);
+        test_app.edit_string = "-120".to_string();
+        assert_eq!(test_app.change_bpm_editor(), false);
+        assert_eq!(test_app.get_bpm(), 120.0);
+    }
+
+    // app::change_bpm_editor should take tenths of a bpm and work out the delay for them
+    #[test]
+    fn app_change_bpm_value_is_float() {
+        let mut test_app = App::new(TEST_SETTINGS, TEST_TICK_RATE);
+        test_app.edit_string = "120.5".to_string();
+        assert_eq!(test_app.change_bpm_editor(), true);
+        assert_eq!(test_app.get_bpm(), 120.5);
+        assert_eq!(
+            test_app.settings.ns_delay.load(Ordering::Relaxed),
+            497_925_311
+        );
+        test_app.refresh_edit_menu();
+        assert!(test_app.edit_menu.items[1].starts_with("bpm: 120.5 "));
+    }
+
+    // app::change_bpm_editor should reject more than one decimal place
+    #[test]
+    fn app_change_bpm_value_too_precise() {
+        let mut test_app = App::new(TEST_SETTINGS, TEST_TICK_RATE);
+        test_app.edit_string = "120.55".to_string();
+        assert_eq!(test_app.change_bpm_editor(), false);
+        test_app.edit_string = "120.5.5".to_string();
+        assert_eq!(test_app.change_bpm_editor(), false);
+        test_app.edit_string = ".5".to_string();
+        assert_eq!(test_app.change_bpm_editor(), false);
+        assert_eq!(test_app.get_bpm(), 120.0);
+    }
+
+    // app::change_bpm_editor should explain why a bpm was rejected
+    #[test]
+    fn app_change_bpm_editor_alert() {
+        let mut test_app = App::new(TEST_SETTINGS, TEST_TICK_RATE);
+        test_app.edit_string = "abc".to_string();
+        assert!(!test_app.change_bpm_editor());
+        assert_eq!(
+            test_app.alert_string,
+            "BPM must be a number between 20 and 500 with up to one decimal place"
+        );
+
+        test_app.edit_string = "99999999999999999999999".to_string();
+        assert!(!test_app.change_bpm_editor());
+        assert_eq!(test_app.alert_string, "BPM must be between 20 and 500");
+        assert_eq!(test_app.get_bpm(), 120.0);
+    }
+
+    // app::change_volume_editor should reject values rust parses that aren't plain numbers
+    #[test]
+    fn app_change_volume_editor_not_a_number() {
+        let mut test_app = App::new(TEST_SETTINGS, TEST_TICK_RATE);
+        for value in ["nan", "inf", "1e2"] {
+            test_app.edit_string = value.to_string();
+            assert!(!test_app.change_volume_editor());
+            assert_eq!(
+                test_app.alert_string,
+                "Volume must be a number between 0 and 100"
+            );
+        }
+        assert_eq!(test_app.get_volume(), 100.0);
+    }
+
+    // app::change_volume should not change volume with bad input
+    #[test]
+    fn app_change_volume_editor_bad_input() {
+        let mut test_app = App::new(TEST_SETTINGS, TEST_TICK_RATE);
+        test_app.edit_string = "hey this isn't a number is it?".to_string();
+        assert_eq!(test_app.change_volume_editor(), false);
+        assert_eq!(test_app.get_volume(), 100.0);
+    }
+
+    #[test]
+    fn app_change_volume_editor_value_too_big() {
+        let mut test_app = App::new(TEST_SETTINGS, TEST_TICK_RATE);
+        test_app.edit_string = "500000".to_string();
+        assert_eq!(test_app.change_volume_editor(), false);
+        assert_eq!(test_app.get_volume(), 100.0);
+        test_app.edit_string = "100.5".to_string();
+        assert_eq!(test_app.change_volume_editor(), false);
+        assert_eq!(test_app.alert_string, "Volume must be between 0 and 100");
+        assert_eq!(test_app.get_volume(), 100.0);
+    }
+
+    // a volume of 0 is the bottom of the range and silences the click
+    #[test]
+    fn app_change_volume_editor_value_zero() {
+        let mut test_app = App::new(TEST_SETTINGS, TEST_TICK_RATE);
+        test_app.edit_string = "0".to_string();
+        assert_eq!(test_app.change_volume_editor(), true);
+        assert_eq!(test_app.get_volume(), 0.0);
+    }
+
+    #[test]
+    fn app_change_volume_editor_value_negative() {
+        let mut test_app = App::new(TEST_SETTINGS, TEST_TICK_RATE);
+        test_app.edit_string = "-120".to_string();
+        assert_eq!(test_app.change_volume_editor(), false);
+        assert_eq!(test_app.get_volume(), 100.0);
+    }
+
+    // app::change_trainer_editor should only accept values in range for the setting being edited
+    #[test]
+    fn app_change_trainer_editor() {
+        let mut test_app = App::new(TEST_SETTINGS, TEST_TICK_RATE);
+        test_app.edit_string = "180".to_string();
+        assert!(test_app.change_trainer_editor(CurrentlyEditing::TrainerTargetBpm));
+        assert_eq!(
+            test_app.get_trainer_value(CurrentlyEditing::TrainerTargetBpm),
+            180
+        );
+
+        // a negative increment slows the tempo down
+        test_app.edit_string = "-5".to_string();
+        assert!(test_app.change_trainer_editor(CurrentlyEditing::TrainerIncrement));
+        assert_eq!(
+            test_app.get_trainer_value(CurrentlyEditing::TrainerIncrement),
+            -5
+        );
+        test_app.edit_string = "-101".to_string();
+        assert!(!test_app.change_trainer_editor(CurrentlyEditing::TrainerIncrement));
+
+        test_app.edit_string = "-1".to_string();
+        assert!(!test_app.change_trainer_editor(CurrentlyEditing::TrainerTargetBpm));
+
+        test_app.edit_string = "0".to_string();
+        assert!(!test_app.change_trainer_editor(CurrentlyEditing::TrainerBarsPerStep));
+        assert_eq!(
+            test_app.get_trainer_value(CurrentlyEditing::TrainerBarsPerStep),
+            4
+        );
+    }
+
+    #[test]
+    fn app_toggle_trainer() {
+        let mut test_app = App::new(TEST_SETTINGS, TEST_TICK_RATE);
+        assert!(!test_app.get_trainer_enabled());
+        test_app.toggle_trainer();
+        assert!(test_app.get_trainer_enabled());
+    }
+
+    // app::set_output_device should store the device name and flag the metronome to reopen its stream
+    #[test]
+    fn app_set_output_device() {
+        let mut test_app = App::new(TEST_SETTINGS, TEST_TICK_RATE);
+        assert!(test_app.get_output_device_string().is_empty());
+        test_app.set_output_device("Headphones".to_string());
+        assert_eq!(test_app.get_output_device_string(), "Headphones");
+        assert!(test_app
+            .settings
+            .output_device_changed
+            .load(Ordering::Relaxed));
+    }
+
+    // undo should put back the settings from before each of the last few changes, recomputing the timing
+    #[test]
+    fn app_undo() {
+        let mut test_app = App::new(TEST_SETTINGS, TEST_TICK_RATE);
+        let (ns_delay, _) = test_app.get_timing();
+        let _ = test_app.update(KeyEvent::from(KeyCode::Char(']')));
+        assert_eq!(test_app.get_bpm(), 130.0);
+        let _ = test_app.update(KeyEvent::from(KeyCode::Char('u')));
+        assert_eq!(test_app.get_bpm(), 120.0);
+        assert_eq!(test_app.settings.ns_delay.load(Ordering::Relaxed), ns_delay);
+        assert!(test_app.undo_history.is_empty());
+
+        // only the last UNDO_LEVELS changes are kept
+        for _ in 0..UNDO_LEVELS + 2 {
+            let _ = test_app.update(KeyEvent::from(KeyCode::Char('+')));
+        }
+        for _ in 0..UNDO_LEVELS + 2 {
+            let _ = test_app.update(KeyEvent::from(KeyCode::Char('u')));
+        }
+        assert_eq!(test_app.get_bpm(), 122.0);
+    }
+
+    // app::change_fade_in_bars_editor should only accept 0 to 16 bars
+    #[test]
+    fn app_change_fade_in_bars_editor() {
+        let mut test_app = App::new(TEST_SETTINGS, TEST_TICK_RATE);
+        assert_eq!(test_app.get_fade_in_string(), "off");
+        test_app.edit_string = "17".to_string();
+        assert!(!test_app.change_fade_in_bars_editor());
+        test_app.edit_string = "2".to_string();
+        assert!(test_app.change_fade_in_bars_editor());
+        assert_eq!(test_app.get_fade_in_string(), "2 bars");
+    }
+
+    // the output latency editor should only accept 0 - 1000 ms
+    #[test]
+    fn app_change_output_latency_editor() {
+        let mut test_app = App::new(TEST_SETTINGS, TEST_TICK_RATE);
+        test_app.edit_string = "1001".to_string();
+        assert!(!test_app.change_output_latency_editor());
+        test_app.edit_string = "120".to_string();
+        assert!(test_app.change_output_latency_editor());
+        assert_eq!(test_app.get_output_latency(), 120);
+
+        // an edit can be undone like the other settings
+        test_app.open_editor(CurrentlyEditing::OutputLatency);
+        test_app.edit_string = "40".to_string();
+        let _ = test_app.update(KeyEvent::from(KeyCode::Enter));
+        assert_eq!(test_app.get_output_latency(), 40);
+        let _ = test_app.update(KeyEvent::from(KeyCode::Char('u')));
+        assert_eq!(test_app.get_output_latency(), 120);
+    }
+
+    // calibration should suggest the current latency plus the average offset of the taps once there are enough
+    #[test]
+    fn app_suggested_latency() {
+        let mut test_app = App::new(TEST_SETTINGS, TEST_TICK_RATE);
+        test_app
+            .settings
+            .output_latency_ms
+            .swap(50, Ordering::Relaxed);
+        for _ in 0..CALIBRATION_TAPS - 1 {
+            test_app.calibration_stats.record(30.0);
+        }
+        assert_eq!(test_app.get_suggested_latency(), None);
+        assert!(!test_app.apply_suggested_latency());
+
+        test_app.calibration_stats.record(30.0);
+        assert_eq!(test_app.get_suggested_latency(), Some(80));
+        assert!(test_app.apply_suggested_latency());
+        assert_eq!(test_app.get_output_latency(), 80);
+
+        // early taps can't take the latency below 0
+        test_app.calibration_stats = TapStats::new();
+        for _ in 0..CALIBRATION_TAPS {
+            test_app.calibration_stats.record(-200.0);
+        }
+        assert_eq!(test_app.get_suggested_latency(), Some(0));
+    }
+
+    // number keys should jump to a quickslot, and shifted ones save to it
+    #[test]
+    fn app_quickslots() {
+        let mut test_app = App::new(TEST_SETTINGS, TEST_TICK_RATE);
+        assert_eq!(
+            quickslot_key(KeyEvent::from(KeyCode::Char('3'))),
+            Some((2, false))
+        );
+        assert_eq!(
+            quickslot_key(KeyEvent::from(KeyCode::Char('#'))),
+            Some((2, true))
+        );
+        assert_eq!(
+            quickslot_key(KeyEvent::new(KeyCode::Char('3'), KeyModifiers::SHIFT)),
+            Some((2, true))
+        );
+        assert_eq!(quickslot_key(KeyEvent::from(KeyCode::Char('0'))), None);
+
+        test_app.jump_to_quickslot(0);
+        assert_eq!(test_app.get_bpm(), test_app.config.bpm_quickslots[0] as f64);
+        test_app.nudge_bpm(5);
+        test_app.set_quickslot(8);
+        assert_eq!(test_app.config.bpm_quickslots[8] as f64, test_app.get_bpm());
+    }
+
+    // a live preview should change the volume as it is typed, be put back when discarded and undo as one change
+    #[test]
+    fn app_live_preview() {
+        let mut test_app = App::new(TEST_SETTINGS, TEST_TICK_RATE);
+        test_app.toggle_live_preview();
+        test_app.switch_screen(CurrentScreen::Editing);
+        test_app.edit_menu.select(2);
+        let _ = test_app.update(KeyEvent::from(KeyCode::Enter));
+        let _ = test_app.update(KeyEvent::from(KeyCode::Char('5')));
+        let _ = test_app.update(KeyEvent::from(KeyCode::Char('0')));
+        assert_eq!(test_app.get_volume(), 50.0);
+        let _ = test_app.update(KeyEvent::from(KeyCode::Esc));
+        assert_eq!(test_app.get_volume(), 100.0);
+        assert!(test_app.undo_history.is_empty());
+
+        test_app.edit_menu.select(2);
+        let _ = test_app.update(KeyEvent::from(KeyCode::Enter));
+        let _ = test_app.update(KeyEvent::from(KeyCode::Char('7')));
+        let _ = test_app.update(KeyEvent::from(KeyCode::Char('0')));
+        let _ = test_app.update(KeyEvent::from(KeyCode::Enter));
+        assert_eq!(test_app.get_volume(), 70.0);
+        assert_eq!(test_app.undo_history.len(), 1);
+        test_app.undo();
+        assert_eq!(test_app.get_volume(), 100.0);
+    }
+
+    // the spacebar should start and stop the metronome, but not while a value is being typed
+    #[test]
+    fn app_spacebar_toggles_metronome() {
+        let mut test_app = App::new(TEST_SETTINGS, TEST_TICK_RATE);
+        test_app.sound_list = vec!["EmeryBoardClick.wav".to_owned()];
+        let _ = test_app.update(KeyEvent::from(KeyCode::Char(' ')));
+        assert!(test_app.get_is_running());
+        test_app.switch_screen(CurrentScreen::Editing);
+        let _ = test_app.update(KeyEvent::from(KeyCode::Char(' ')));
+        assert!(!test_app.get_is_running());
+
+        test_app.edit_menu.select(1);
+        let _ = test_app.update(KeyEvent::from(KeyCode::Enter));
+        let _ = test_app.update(KeyEvent::from(KeyCode::Char(' ')));
+        assert!(!test_app.get_is_running());
+    }
+
+    // app::toggle_silent should switch visual only mode without touching mute or whether the metronome is running
+    #[test]
+    fn app_toggle_silent() {
+        let mut test_app = App::new(TEST_SETTINGS, TEST_TICK_RATE);
+        assert!(!test_app.get_is_silent());
+        test_app.toggle_silent();
+        assert!(test_app.get_is_silent());
+        assert!(!test_app.get_is_muted());
+        assert!(!test_app.get_is_running());
+        test_app.toggle_silent();
+        assert!(!test_app.get_is_silent());
+    }
+
+    // a missing audio output should be explained at the bottom of the status panel instead of stopping the app
+    #[test]
+    fn app_audio_error_status() {
+        let mut test_app = App::new(TEST_SETTINGS, TEST_TICK_RATE);
+        test_app.refresh_edit_menu();
+        let item_count = test_app.edit_menu.items.len();
+        *test_app.settings.audio_error.lock().unwrap() = "No audio output available".to_owned();
+        test_app.refresh_edit_menu();
+        assert_eq!(test_app.edit_menu.items.len(), item_count + 1);
+        assert_eq!(
+            test_app.edit_menu.items.last().unwrap(),
+            "No audio output available, retrying..."
+        );
+        assert!(test_app.current_screen != CurrentScreen::Error);
+    }
+
+    // app::toggle_mute should toggle the mute without touching whether the metronome is running
+    #[test]
+    fn app_toggle_mute() {
+        let mut test_app = App::new(TEST_SETTINGS, TEST_TICK_RATE);
+        test_app.sound_list = vec!["EmeryBoardClick.wav".to_owned()];
+        test_app.toggle_metronome();
+        assert!(!test_app.get_is_muted());
+        test_app.toggle_mute();
+        assert!(test_app.get_is_muted());
+        assert!(test_app.get_is_running());
+        test_app.toggle_mute();
+        assert!(!test_app.get_is_muted());
+    }
+
+    // app::update should route typed characters into the sound selection filter
+    #[test]
+    fn app_sound_selection_filter() {
+        let mut test_app = App::new(TEST_SETTINGS, TEST_TICK_RATE);
+        test_app.sound_list = vec![
+            "EmeryBoardClick.wav".to_string(),
+            "TronicClick1.wav".to_string(),
+        ];
+        test_app.current_screen = CurrentScreen::SoundSelection;
+        test_app.refresh_sound_selection_menu();
+
+        for value in "TRON".chars() {
+            let _ = test_app.update(KeyEvent::from(KeyCode::Char(value)));
+        }
+        assert_eq!(
+            test_app.sound_selection_menu.items,
+            vec!["TronicClick1.wav"]
+        );
+        assert!(!test_app.get_is_running());
+
+        let _ = test_app.update(KeyEvent::from(KeyCode::Esc));
+        assert!(test_app.sound_selection_menu.filter.is_empty());
+        assert_eq!(test_app.sound_selection_menu.items.len(), 2);
+    }
+
+    // ctrl + p should ask for the highlighted sound to be played without searching or touching the counters
+    #[test]
+    fn app_preview_current_sound() {
+        let mut test_app = App::new(TEST_SETTINGS, TEST_TICK_RATE);
+        test_app.sound_list = vec![
+            "EmeryBoardClick.wav".to_string(),
+            "TronicClick1.wav".to_string(),
+        ];
+        test_app.current_screen = CurrentScreen::SoundSelection;
+        test_app.refresh_sound_selection_menu();
+        test_app.sound_selection_menu.select(1);
+
+        let _ = test_app.update(KeyEvent::new(KeyCode::Char('p'), KeyModifiers::CONTROL));
+        assert_eq!(*test_app.settings.preview_sound.lock().unwrap(), Some(1));
+        assert!(test_app.sound_selection_menu.filter.is_empty());
+        assert!(!test_app.get_is_running());
+        assert_eq!(test_app.get_bar_count_string(), "1");
+
+        // a search that matches nothing has no sound to play
+        *test_app.settings.preview_sound.lock().unwrap() = None;
+        test_app.sound_selection_menu.set_filter("missing");
+        test_app.preview_current_sound();
+        assert_eq!(*test_app.settings.preview_sound.lock().unwrap(), None);
+    }
+
+    // ctrl + r should rename the highlighted sound through the pop up, the menu showing the new name and still picking
+    // the right file
+    #[test]
+    fn app_rename_sound() {
+        let mut test_app = App::new(TEST_SETTINGS, TEST_TICK_RATE);
+        test_app.config.sound_display_names.clear();
+        test_app.sound_list = vec![
+            "EmeryBoardClick.wav".to_string(),
+            "TronicClick1.wav".to_string(),
+        ];
+        test_app.current_screen = CurrentScreen::SoundSelection;
+        test_app.refresh_sound_selection_menu();
+        test_app.sound_selection_menu.select(1);
+
+        let _ = test_app.update(KeyEvent::new(KeyCode::Char('r'), KeyModifiers::CONTROL));
+        assert!(test_app.currently_editing == Some(CurrentlyEditing::SoundName));
+        assert_eq!(test_app.edit_string, "TronicClick1.wav");
+        for value in "Tronic".chars() {
+            let _ = test_app.update(KeyEvent::from(KeyCode::Char(value)));
+        }
+        assert!(test_app.sound_selection_menu.filter.is_empty());
+        assert_eq!(test_app.edit_string, "Tronic");
+        test_app.change_sound_name_editor();
+        assert!(test_app.currently_editing.is_none());
+        assert_eq!(
+            test_app.sound_selection_menu.items,
+            vec!["EmeryBoardClick.wav", "Tronic"]
+        );
+        assert_eq!(
+            test_app.sound_selection_menu.selected_item().unwrap(),
+            "Tronic"
+        );
+        assert_eq!(test_app.get_sound_name(1), "Tronic");
+
+        test_app.sound_target = SoundTarget::Main;
+        let _ = test_app.update(KeyEvent::from(KeyCode::Enter));
+        assert_eq!(test_app.settings.selected_sound.load(Ordering::Relaxed), 1);
+
+        // an empty name goes back to the file name
+        test_app.current_screen = CurrentScreen::SoundSelection;
+        test_app.refresh_sound_selection_menu();
+        test_app.start_sound_rename();
+        test_app.edit_string.clear();
+        test_app.change_sound_name_editor();
+        assert!(test_app.config.sound_display_names.is_empty());
+        assert_eq!(test_app.sound_selection_menu.items[1], "TronicClick1.wav");
+    }
+
+    // app::refresh_sound_list should pick up the sounds in /assets and keep each setting's sound by name
+    #[test]
+    fn app_refresh_sound_list() {
+        let mut test_app = App::new(TEST_SETTINGS, TEST_TICK_RATE);
+        test_app.sound_list = vec![
+            "TronicClick1.wav".to_string(),
+            "Removed.wav".to_string(),
+            "EmeryBoardClick.wav".to_string(),
+        ];
+        test_app.settings.selected_sound.swap(0, Ordering::Relaxed);
+        test_app.settings.poly_sound.swap(1, Ordering::Relaxed);
+        test_app
+            .settings
+            .subdivision_sound
+            .swap(2, Ordering::Relaxed);
+        *test_app.settings.beat_sounds.lock().unwrap() = vec![2, 0];
+
+        test_app.refresh_sound_list();
+        assert_eq!(
+            test_app.sound_list,
+            vec!["EmeryBoardClick.wav", "TronicClick1.wav"]
+        );
+        assert_eq!(
+            *test_app.settings.sound_list.lock().unwrap(),
+            test_app.sound_list
+        );
+        assert_eq!(test_app.get_selected_sound_string(), "TronicClick1.wav");
+        assert_eq!(test_app.get_poly_sound_string(), "EmeryBoardClick.wav");
+        assert_eq!(
+            test_app.get_subdivision_sound_string(),
+            "EmeryBoardClick.wav"
+        );
+        assert_eq!(test_app.get_beat_sounds(), vec![0, 1]);
+        assert_eq!(
+            test_app.alert_string,
+            "Removed.wav left the sound folder, using EmeryBoardClick.wav instead"
+        );
+    }
+
+    // app::toggle_metronome should put sounds that point past the end of the sound list back on the first sound
+    #[test]
+    fn app_check_sound_indices() {
+        let mut test_app = App::new(TEST_SETTINGS, TEST_TICK_RATE);
+        test_app.sound_list = vec!["EmeryBoardClick.wav".to_owned()];
+        test_app.settings.selected_sound.swap(3, Ordering::Relaxed);
+        *test_app.settings.beat_sounds.lock().unwrap() = vec![0, 5, 0, 0];
+        test_app.toggle_metronome();
+        assert!(test_app.get_is_running());
+        assert_eq!(test_app.settings.selected_sound.load(Ordering::Relaxed), 0);
+        assert_eq!(test_app.get_beat_sounds(), vec![0, 0, 0, 0]);
+        assert_eq!(
+            test_app.alert_string,
+            "A sound was missing from the sound list, using EmeryBoardClick.wav instead"
+        );
+    }
+
+    // app::export_click_track should write a wav file and report it in the status panel
+    #[test]
+    fn app_export_click_track() {
+        let mut test_app = App::new(TEST_SETTINGS, TEST_TICK_RATE);
+        test_app.populate_sounds().unwrap();
+        test_app.settings.beats_per_bar.swap(4, Ordering::Relaxed);
+        let path = std::env::temp_dir().join("readymetronome_test_export.wav");
+        let path = path.to_str().unwrap();
+
+        test_app.export_click_track(path);
+        assert!(test_app.export_string.starts_with("saved"));
+        assert!(hound::WavReader::open(path).unwrap().len() > 0);
+        let _ = fs::remove_file(path);
+    }
+
+    // app::change_poly_beats_editor should only accept between 1 and 16 beats
+    #[test]
+    fn app_change_poly_beats_editor() {
+        let mut test_app = App::new(TEST_SETTINGS, TEST_TICK_RATE);
+        test_app.edit_string = "17".to_string();
+        assert!(!test_app.change_poly_beats_editor());
+        assert_eq!(test_app.get_poly_beats(), 3);
+        test_app.edit_string = "5".to_string();
+        assert!(test_app.change_poly_beats_editor());
+        assert_eq!(test_app.get_poly_beats(), 5);
+    }
+
+    // app::update should use the keymap to decide what a key does
+    #[test]
+    fn app_update_uses_keymap() {
+        let mut test_app = App::new(TEST_SETTINGS, TEST_TICK_RATE);
+        test_app.sound_list = vec!["EmeryBoardClick.wav".to_owned()];
+        test_app.main_menu.select(0);
+        let _ = test_app.update(KeyEvent::from(KeyCode::Down));
+        assert_eq!(test_app.main_menu.state.selected(), Some(1));
+        let _ = test_app.update(KeyEvent::from(KeyCode::Char('t')));
+        assert!(test_app.get_is_running());
+    }
+
+    // sends the metronome's beat event for a tick heard now
+    fn send_beat(test_app: &App, bar: u64, beat: u64) {
+        test_app.settings.send_beat(BeatEvent {
+            bar,
+            beat,
+            is_accent: beat == 1,
+            instant: Instant::now(),
+            late: Duration::ZERO,
+        });
+    }
+
+    // app::refresh_flash should flash once per bar and never while stopped
+    #[test]
+    fn app_refresh_flash() {
+        let mut test_app = App::new(TEST_SETTINGS, TEST_TICK_RATE);
+        test_app.sound_list = vec!["EmeryBoardClick.wav".to_owned()];
+        send_beat(&test_app, 1, 1);
+        test_app.refresh_flash();
+        assert_eq!(test_app.flash_frames, 0);
+
+        test_app.toggle_metronome();
+        send_beat(&test_app, 1, 1);
+        test_app.refresh_flash();
+        assert_eq!(test_app.flash_frames, FLASH_FRAMES);
+        test_app.decay_flash();
+        test_app.refresh_flash();
+        assert_eq!(test_app.flash_frames, FLASH_FRAMES - 1);
+
+        // beats heard later because of output latency wait for their turn
+        test_app.flash_frames = 0;
+        test_app.settings.send_beat(BeatEvent {
+            bar: 2,
+            beat: 1,
+            is_accent: true,
+            instant: Instant::now() + Duration::from_secs(60),
+            late: Duration::ZERO,
+        });
+        test_app.refresh_flash();
+        assert_eq!(test_app.flash_frames, 0);
+        assert_eq!(test_app.pending_beats.len(), 1);
+
+        test_app.toggle_metronome();
+        test_app.refresh_flash();
+        assert_eq!(test_app.flash_frames, 0);
+    }
+
+    // the screen flash should only flash on main beats while it is on and the metronome is running
+    #[test]
+    fn app_screen_flash() {
+        let mut test_app = App::new(TEST_SETTINGS, TEST_TICK_RATE);
+        test_app.sound_list = vec!["EmeryBoardClick.wav".to_owned()];
+        test_app.settings.beats_per_bar.swap(8, Ordering::Relaxed);
+        test_app.toggle_screen_flash();
+        send_beat(&test_app, 1, 1);
+        test_app.refresh_flash();
+        assert_eq!(test_app.screen_flash_frames, 0);
+
+        test_app.toggle_metronome();
+        send_beat(&test_app, 1, 1);
+        test_app.refresh_flash();
+        assert_eq!(test_app.screen_flash_frames, SCREEN_FLASH_FRAMES);
+        assert!(test_app.screen_flash_downbeat);
+        test_app.decay_flash();
+        assert_eq!(test_app.screen_flash_frames, 0);
+
+        // the eighth note between the first two beats doesn't flash
+        send_beat(&test_app, 1, 2);
+        test_app.refresh_flash();
+        assert_eq!(test_app.screen_flash_frames, 0);
+        send_beat(&test_app, 1, 3);
+        test_app.refresh_flash();
+        assert_eq!(test_app.screen_flash_frames, SCREEN_FLASH_FRAMES);
+        assert!(!test_app.screen_flash_downbeat);
+
+        test_app.toggle_screen_flash();
+        assert_eq!(test_app.screen_flash_frames, 0);
+    }
+
+    // the pendulum should only swing while it is on and the metronome is running, reaching a side on every tick
+    #[test]
+    fn app_get_pendulum_position() {
+        let mut test_app = App::new(TEST_SETTINGS, TEST_TICK_RATE);
+        test_app.sound_list = vec!["EmeryBoardClick.wav".to_owned()];
+        test_app.toggle_metronome();
+        assert_eq!(test_app.get_pendulum_position(), None);
+        test_app.toggle_pendulum();
+        assert_eq!(test_app.get_pendulum_position(), Some(0.0));
+
+        test_app
+            .settings
+            .current_beat_count
+            .swap(1, Ordering::Relaxed);
+        assert_eq!(test_app.get_pendulum_position(), Some(0.0));
+        test_app
+            .settings
+            .current_beat_count
+            .swap(4, Ordering::Relaxed);
+        assert_eq!(test_app.get_pendulum_position(), Some(1.0));
+        // beat 1 of bar 2 is the fifth tick, so it swings from the left again
+        test_app.settings.bar_count.swap(2, Ordering::Relaxed);
+        test_app
+            .settings
+            .current_beat_count
+            .swap(1, Ordering::Relaxed);
+        assert_eq!(test_app.get_pendulum_position(), Some(0.0));
+        test_app.toggle_metronome();
+        assert_eq!(test_app.get_pendulum_position(), None);
+    }
+
+    // the status panel should warn once the ticks get too close together for the refresh rate
+    #[test]
+    fn app_timing_warning() {
+        let mut test_app = App::new(TEST_SETTINGS, 500_000);
+        test_app.refresh_edit_menu();
+        assert!(test_app.get_timing_warning().is_none());
+        let menu_length = test_app.edit_menu.items.len();
+
+        // sixty-fourths at 500 bpm tick every 7.5 ms, which is only 15 checks at this refresh rate
+        test_app.settings.ts_value.swap(64, Ordering::Relaxed);
+        test_app.change_bpm(500.0);
+        test_app.refresh_edit_menu();
+        assert!(test_app.get_timing_warning().is_some());
+        assert_eq!(test_app.edit_menu.items.len(), menu_length + 1);
+    }
+
+    // the edit pop up's list of settings should show every value and highlight the one being edited
+    #[test]
+    fn app_refresh_edit_field_menu() {
+        let mut test_app = App::new(TEST_SETTINGS, TEST_TICK_RATE);
+        test_app.refresh_edit_field_menu();
+        assert_eq!(
+            test_app.edit_field_menu.items.len(),
+            CurrentlyEditing::ALL.len()
+        );
+        assert_eq!(test_app.edit_field_menu.selected_item(), None);
+
+        test_app.currently_editing = Some(CurrentlyEditing::Volume);
+        test_app.refresh_edit_menu();
+        assert_eq!(
+            test_app.edit_field_menu.selected_item().unwrap(),
+            "Volume: 100"
+        );
+        assert_eq!(test_app.edit_field_menu.items[0], "Bpm: 120");
+    }
+
+    // (,) and (.) should move the volume by 5 within the valid range, and the slider should show it alongside mute
+    #[test]
+    fn app_nudge_volume() {
+        let mut test_app = App::new(TEST_SETTINGS, TEST_TICK_RATE);
+        assert_eq!(test_app.get_volume_slider(), "[##########] 100%");
+        let _ = test_app.update(KeyEvent::from(KeyCode::Char('.')));
+        assert_eq!(test_app.get_volume(), 100.0);
+        let _ = test_app.update(KeyEvent::from(KeyCode::Char(',')));
+        let _ = test_app.update(KeyEvent::from(KeyCode::Char(',')));
+        assert_eq!(test_app.get_volume(), 90.0);
+        assert_eq!(test_app.get_volume_slider(), "[#########-] 90%");
+
+        test_app.settings.volume.swap(3.0, Ordering::Relaxed);
+        test_app.nudge_volume(-VOLUME_STEP);
+        assert_eq!(test_app.get_volume_slider(), "[----------] 0%");
+        test_app.settings.volume.swap(98.0, Ordering::Relaxed);
+        test_app.nudge_volume(VOLUME_STEP);
+        assert_eq!(test_app.get_volume_slider(), "[##########] 100%");
+
+        // muting keeps the volume to come back to
+        test_app.toggle_mute();
+        test_app.nudge_volume(-VOLUME_STEP);
+        assert!(test_app.get_is_muted());
+        assert_eq!(test_app.get_volume_slider(), "[##########] 95% (muted)");
+    }
+
+    // in knob mode the arrows should change the bpm by 1, or 10 with shift, instead of moving through the menu
+    #[test]
+    fn app_knob_mode() {
+        let mut test_app = App::new(TEST_SETTINGS, TEST_TICK_RATE);
+        test_app.refresh_edit_menu();
+        let bpm = test_app.get_bpm();
+        let _ = test_app.update(KeyEvent::from(KeyCode::Down));
+        assert_eq!(test_app.get_bpm(), bpm);
+
+        let _ = test_app.update(KeyEvent::from(KeyCode::Char('n')));
+        assert!(test_app.knob_mode);
+        let selection = test_app.main_menu.state.selected();
+        let _ = test_app.update(KeyEvent::from(KeyCode::Up));
+        assert_eq!(test_app.get_bpm(), bpm + 1.0);
+        assert_eq!(test_app.knob_step, 1);
+        let _ = test_app.update(KeyEvent::new(KeyCode::Down, KeyModifiers::SHIFT));
+        assert_eq!(test_app.get_bpm(), bpm - 9.0);
+        assert_eq!(test_app.knob_step, 10);
+        assert_eq!(test_app.main_menu.state.selected(), selection);
+
+        // values being typed into the edit popup are left alone
+        test_app.currently_editing = Some(CurrentlyEditing::Bpm);
+        let _ = test_app.update(KeyEvent::from(KeyCode::Up));
+        assert_eq!(test_app.get_bpm(), bpm - 9.0);
+    }
+
+    // the beat indicator should mark subdivisions and only light the tick that just played
+    #[test]
+    fn app_get_beat_indicator() {
+        let mut test_app = App::new(TEST_SETTINGS, TEST_TICK_RATE);
+        test_app.sound_list = vec!["EmeryBoardClick.wav".to_owned()];
+        test_app.settings.beats_per_bar.swap(8, Ordering::Relaxed);
+        send_beat(&test_app, 1, 2);
+        test_app.refresh_flash();
+        let indicator = test_app.get_beat_indicator();
+        assert_eq!(indicator.len(), 8);
+        assert!(indicator.iter().all(|(_, lit)| !lit));
+
+        test_app.toggle_metronome();
+        send_beat(&test_app, 1, 2);
+        test_app.refresh_flash();
+        let indicator = test_app.get_beat_indicator();
+        assert_eq!(indicator[0], (true, false));
+        assert_eq!(indicator[1], (false, true));
+        for _ in 0..PULSE_FRAMES {
+            test_app.decay_flash();
+        }
+        assert!(test_app.get_beat_indicator().iter().all(|(_, lit)| !lit));
+        test_app.toggle_metronome();
+    }
+
+    // app::resize_beat_sounds should grow the sound map with the selected sound and set_selected_sound should
+    // only move beats and the subdivision sound that were using the old selected sound
+    #[test]
+    fn app_beat_sounds() {
+        let mut test_app = App::new(TEST_SETTINGS, TEST_TICK_RATE);
+        test_app.resize_beat_sounds();
+        assert_eq!(test_app.get_beat_sounds(), vec![0, 0, 0, 0]);
+
+        test_app.set_beat_sound(0, 2);
+        test_app.set_selected_sound(1);
+        assert_eq!(test_app.get_beat_sounds(), vec![2, 1, 1, 1]);
+        assert_eq!(
+            test_app.settings.subdivision_sound.load(Ordering::Relaxed),
+            1
+        );
+        test_app
+            .settings
+            .subdivision_sound
+            .swap(3, Ordering::Relaxed);
+        test_app.set_selected_sound(2);
+        assert_eq!(
+            test_app.settings.subdivision_sound.load(Ordering::Relaxed),
+            3
+        );
+        test_app.set_selected_sound(1);
+
+        test_app.settings.beats_per_bar.swap(6, Ordering::Relaxed);
+        test_app.resize_beat_sounds();
+        assert_eq!(test_app.get_beat_sounds(), vec![2, 1, 1, 1, 1, 1]);
+    }
+
+    // b, v and shift + t should open their editors from the main screen without typing the letter into them
+    #[test]
+    fn app_editor_hotkeys() {
+        let mut test_app = App::new(TEST_SETTINGS, TEST_TICK_RATE);
+        test_app.refresh_edit_menu();
+        let _ = test_app.update(KeyEvent::from(KeyCode::Char('b')));
+        assert!(test_app.current_screen == CurrentScreen::Editing);
+        assert!(test_app.currently_editing == Some(CurrentlyEditing::Bpm));
+        assert_eq!(test_app.edit_string, "120");
+
+        // letters typed into the pop up are left for the value
+        let _ = test_app.update(KeyEvent::from(KeyCode::Char('v')));
+        assert!(test_app.currently_editing == Some(CurrentlyEditing::Bpm));
+        assert_eq!(test_app.edit_string, "v");
+        let _ = test_app.update(KeyEvent::from(KeyCode::Esc));
+
+        let _ = test_app.update(KeyEvent::from(KeyCode::Char('v')));
+        assert!(test_app.currently_editing == Some(CurrentlyEditing::Volume));
+        assert_eq!(test_app.edit_string, "100");
+        let _ = test_app.update(KeyEvent::from(KeyCode::Esc));
+
+        let _ = test_app.update(KeyEvent::new(KeyCode::Char('T'), KeyModifiers::SHIFT));
+        assert!(test_app.currently_editing == Some(CurrentlyEditing::TimeSignature));
+        assert_eq!(test_app.edit_string, "4/4");
+        // a value that isn't a note length is reported and the time signature is kept
+        test_app.edit_string = "7/6".to_owned();
+        let _ = test_app.update(KeyEvent::from(KeyCode::Enter));
+        assert!(test_app.currently_editing == Some(CurrentlyEditing::TimeSignature));
+        assert_eq!(test_app.get_time_signature_label(), "4/4");
+        assert_eq!(
+            test_app.alert_string,
+            "Please input a time signature like 4/4 or 7/8"
+        );
+        test_app.edit_string = "7/8".to_owned();
+        let _ = test_app.update(KeyEvent::from(KeyCode::Enter));
+        assert!(test_app.currently_editing.is_none());
+        assert_eq!(test_app.get_time_signature_label(), "7/8");
+        assert_eq!(test_app.edit_menu.state.selected(), Some(4));
+    }
+
+    // the intro sound should be picked from the sound list and turned off by selecting it again
+    #[test]
+    fn app_intro_sound() {
+        let mut test_app = App::new(TEST_SETTINGS, TEST_TICK_RATE);
+        test_app.sound_list = vec![
+            "EmeryBoardClick.wav".to_owned(),
+            "TronicClick1.wav".to_owned(),
+        ];
+        test_app.switch_screen(CurrentScreen::Editing);
+        test_app.refresh_edit_menu();
+        assert_eq!(test_app.edit_menu.items[42], "Intro sound: off");
+
+        test_app.edit_menu.select(42);
+        let _ = test_app.update(KeyEvent::from(KeyCode::Enter));
+        assert!(test_app.current_screen == CurrentScreen::SoundSelection);
+        test_app.sound_selection_menu.select(1);
+        let _ = test_app.update(KeyEvent::from(KeyCode::Enter));
+        assert_eq!(*test_app.settings.intro_sound.lock().unwrap(), Some(1));
+        assert_eq!(test_app.edit_menu.state.selected(), Some(42));
+        test_app.refresh_edit_menu();
+        assert_eq!(
+            test_app.edit_menu.items[42],
+            "Intro sound: TronicClick1.wav"
+        );
+
+        let _ = test_app.update(KeyEvent::from(KeyCode::Enter));
+        assert_eq!(*test_app.settings.intro_sound.lock().unwrap(), None);
+        assert!(test_app.current_screen == CurrentScreen::Editing);
+    }
+
+    // the total bar count should only be reset by 'z', not by stopping the metronome
+    #[test]
+    fn app_reset_total_bars() {
+        let mut test_app = App::new(TEST_SETTINGS, TEST_TICK_RATE);
+        test_app.sound_list = vec!["EmeryBoardClick.wav".to_owned()];
+        test_app.settings.total_bars.swap(12, Ordering::Relaxed);
+        test_app.toggle_metronome();
+        test_app.toggle_metronome();
+        assert_eq!(test_app.get_total_bars_string(), "12");
+        let _ = test_app.update(KeyEvent::from(KeyCode::Char('z')));
+        assert_eq!(test_app.get_total_bars_string(), "0");
+    }
+
+    // a frozen display should keep showing the counters from when it was frozen while the metronome carries on
+    #[test]
+    fn app_toggle_display_frozen() {
+        let mut test_app = App::new(TEST_SETTINGS, TEST_TICK_RATE);
+        test_app.settings.bar_count.swap(3, Ordering::Relaxed);
+        test_app
+            .settings
+            .current_beat_count
+            .swap(2, Ordering::Relaxed);
+        let _ = test_app.update(KeyEvent::new(KeyCode::Char('F'), KeyModifiers::SHIFT));
+        assert!(test_app.display_frozen.is_some());
+
+        test_app.settings.bar_count.swap(4, Ordering::Relaxed);
+        test_app
+            .settings
+            .current_beat_count
+            .swap(1, Ordering::Relaxed);
+        test_app.flash_frames = 5;
+        test_app.decay_flash();
+        assert_eq!(test_app.flash_frames, 5);
+        assert_eq!(test_app.get_bar_count_string(), "3");
+        assert_eq!(test_app.get_beat_count_string(), "2");
+
+        let _ = test_app.update(KeyEvent::new(KeyCode::Char('F'), KeyModifiers::SHIFT));
+        assert!(test_app.display_frozen.is_none());
+        assert_eq!(test_app.get_bar_count_string(), "4");
+        assert_eq!(test_app.get_beat_count_string(), "1");
+    }
+
+    // app::reset_beat_customization should undo the per beat sounds and accent groups without stopping the metronome
+    #[test]
+    fn app_reset_beat_customization() {
+        let mut test_app = App::new(TEST_SETTINGS, TEST_TICK_RATE);
+        test_app.sound_list = vec![
+            "EmeryBoardClick.wav".to_owned(),
+            "TronicClick1.wav".to_owned(),
+        ];
+        test_app.resize_beat_sounds();
+        test_app.set_beat_sound(2, 1);
+        test_app
+            .settings
+            .subdivision_sound
+            .swap(1, Ordering::Relaxed);
+        *test_app.settings.accent_groups.lock().unwrap() = vec![2, 2];
+        *test_app.settings.beat_offsets.lock().unwrap() = vec![0, 10];
+        test_app.settings.beat_enabled.lock().unwrap()[1] = false;
+        test_app.toggle_metronome();
+
+        let _ = test_app.update(KeyEvent::from(KeyCode::Char('c')));
+        assert!(test_app.get_is_running());
+        assert_eq!(test_app.get_beat_sounds(), vec![0, 0, 0, 0]);
+        assert_eq!(
+            test_app.settings.subdivision_sound.load(Ordering::Relaxed),
+            0
+        );
+        assert_eq!(test_app.get_accent_groups_string(), "beat 1");
+        assert_eq!(test_app.get_feel_offsets_string(), "off");
+        assert_eq!(
+            *test_app.settings.beat_enabled.lock().unwrap(),
+            vec![true; 4]
+        );
+
+        // the reset can be undone like any other setting change
+        let _ = test_app.update(KeyEvent::from(KeyCode::Char('u')));
+        assert_eq!(test_app.get_beat_sounds(), vec![0, 0, 1, 0]);
+        assert_eq!(test_app.get_accent_groups_string(), "2+2");
+        assert_eq!(test_app.get_feel_offsets_string(), "0 10");
+        test_app.toggle_metronome();
+    }
+
+    // app::change_silent_bar_editor should store the percentage as a probability
+    #[test]
+    fn app_change_silent_bar_editor() {
+        let mut test_app = App::new(TEST_SETTINGS, TEST_TICK_RATE);
+        test_app.edit_string = "101".to_string();
+        assert!(!test_app.change_silent_bar_editor());
+        assert_eq!(test_app.get_silent_bar_chance(), 0.0);
+        test_app.edit_string = "25".to_string();
+        assert!(test_app.change_silent_bar_editor());
+        assert_eq!(
+            test_app.settings.mute_probability.load(Ordering::Relaxed),
+            0.25
+        );
+    }
+
+    // app::change_pitch_editor should only accept speeds between 0.25 and 4.0
+    #[test]
+    fn app_change_pitch_editor() {
+        let mut test_app = App::new(TEST_SETTINGS, TEST_TICK_RATE);
+        test_app.edit_string = "5".to_string();
+        assert!(!test_app.change_pitch_editor());
+        assert_eq!(test_app.get_pitch(), 1.0);
+        test_app.edit_string = "1.5".to_string();
+        assert!(test_app.change_pitch_editor());
+        assert_eq!(test_app.get_pitch(), 1.5);
+    }
+
+    // app::get_stop_after_bars_string should count down the bars left while running
+    #[test]
+    fn app_get_stop_after_bars_string() {
+        let mut test_app = App::new(TEST_SETTINGS, TEST_TICK_RATE);
+        test_app.sound_list = vec!["EmeryBoardClick.wav".to_owned()];
+        assert_eq!(test_app.get_stop_after_bars_string(), "never");
+        test_app.edit_string = "8".to_string();
+        assert!(test_app.change_stop_after_bars_editor());
+        assert_eq!(test_app.get_stop_after_bars_string(), "8 bars");
+        test_app.toggle_metronome();
+        test_app.settings.bar_count.swap(3, Ordering::Relaxed);
+        assert_eq!(test_app.get_stop_after_bars_string(), "8 bars (6 left)");
+    }
+
+    // app::update_mouse should select the clicked menu item and ignore clicks outside of the menu
+    #[test]
+    fn app_update_mouse() {
+        let mut test_app = App::new(TEST_SETTINGS, TEST_TICK_RATE);
+        test_app.main_menu.select(0);
+        test_app.main_menu_area = Rect::new(0, 3, 20, 10);
+        let click = |column, row| MouseEvent {
+            kind: MouseEventKind::Down(MouseButton::Left),
+            column,
+            row,
+            modifiers: crossterm::event::KeyModifiers::NONE,
+        };
+        // the border and the empty space below the items do nothing
+        test_app.update_mouse(click(5, 3)).unwrap();
+        test_app.update_mouse(click(5, 10)).unwrap();
+        test_app.update_mouse(click(30, 5)).unwrap();
+        assert!(test_app.current_screen == CurrentScreen::Main);
+        assert_eq!(test_app.main_menu.state.selected(), Some(0));
+        // the second item opens the edit menu
+        test_app.update_mouse(click(5, 5)).unwrap();
+        assert!(test_app.current_screen == CurrentScreen::Editing);
+    }
+
+    // the quit dialog should go back to the screen it was opened from, or quit with y / enter
+    #[test]
+    fn app_exit_dialog() {
+        let mut test_app = App::new(TEST_SETTINGS, TEST_TICK_RATE);
+        test_app.refresh_edit_menu();
+        test_app.switch_screen(CurrentScreen::Editing);
+        test_app.edit_menu.select(5);
+        let _ = test_app.update(KeyEvent::from(KeyCode::Char('q')));
+        assert!(test_app.current_screen == CurrentScreen::Exiting);
+        assert_eq!(test_app.edit_menu.state.selected(), None);
+        let _ = test_app.update(KeyEvent::from(KeyCode::Char('n')));
+        assert!(test_app.current_screen == CurrentScreen::Editing);
+        assert_eq!(test_app.edit_menu.state.selected(), Some(5));
+        assert!(!test_app.knob_mode);
+
+        let _ = test_app.update(KeyEvent::from(KeyCode::Char('q')));
+        let _ = test_app.update(KeyEvent::from(KeyCode::Esc));
+        assert!(test_app.current_screen == CurrentScreen::Editing);
+        assert!(!test_app.should_quit);
+
+        // the quit item on the main menu opens the same dialog
+        let _ = test_app.update(KeyEvent::from(KeyCode::Esc));
+        test_app.main_menu.select(3);
+        let _ = test_app.update(KeyEvent::from(KeyCode::Enter));
+        assert!(test_app.current_screen == CurrentScreen::Exiting);
+        let _ = test_app.update(KeyEvent::from(KeyCode::Enter));
+        assert!(test_app.should_quit);
+    }
+
+    // app::update should open the help screen with ?, scroll it with the navigation keys and return to the same screen
+    // on esc or ?
+    #[test]
+    fn app_help_screen() {
+        let mut test_app = App::new(TEST_SETTINGS, TEST_TICK_RATE);
+        test_app.switch_screen(CurrentScreen::Editing);
+        let _ = test_app.update(KeyEvent::from(KeyCode::Char('?')));
+        assert!(test_app.current_screen == CurrentScreen::Help);
+        let _ = test_app.update(KeyEvent::from(KeyCode::PageDown));
+        let _ = test_app.update(KeyEvent::from(KeyCode::Down));
+        let _ = test_app.update(KeyEvent::from(KeyCode::Up));
+        assert_eq!(test_app.help_scroll, MENU_PAGE_SIZE as u16);
+        let _ = test_app.update(KeyEvent::from(KeyCode::Char('s')));
+        assert!(test_app.current_screen == CurrentScreen::Help);
+        let _ = test_app.update(KeyEvent::from(KeyCode::Esc));
+        assert!(test_app.current_screen == CurrentScreen::Editing);
+
+        // opening it again starts from the top, and ? closes it too
+        let _ = test_app.update(KeyEvent::from(KeyCode::Char('?')));
+        assert_eq!(test_app.help_scroll, 0);
+        let _ = test_app.update(KeyEvent::from(KeyCode::Char('?')));
+        assert!(test_app.current_screen == CurrentScreen::Editing);
+    }
+
+    // app::switch_screen should return to the item each menu had selected when it was left
+    #[test]
+    fn app_switch_screen_keeps_selection() {
+        let mut test_app = App::new(TEST_SETTINGS, TEST_TICK_RATE);
+        test_app.refresh_edit_menu();
+        test_app.main_menu.select(1);
+        test_app.switch_screen(CurrentScreen::Editing);
+        assert_eq!(test_app.edit_menu.state.selected(), Some(0));
+        test_app.edit_menu.select(10);
+        let _ = test_app.update(KeyEvent::from(KeyCode::Esc));
+        assert!(test_app.current_screen == CurrentScreen::Main);
+        assert_eq!(test_app.main_menu.state.selected(), Some(1));
+        test_app.switch_screen(CurrentScreen::Editing);
+        assert_eq!(test_app.edit_menu.state.selected(), Some(10));
 
-        Ok("App updated".to_string())
+        // leaving a value's pop up goes back to the row it was opened from
+        test_app.edit_menu.select(2);
+        let _ = test_app.update(KeyEvent::from(KeyCode::Enter));
+        assert!(test_app.currently_editing.is_some());
+        let _ = test_app.update(KeyEvent::from(KeyCode::Esc));
+        assert!(test_app.currently_editing.is_none());
+        assert_eq!(test_app.edit_menu.state.selected(), Some(2));
     }
 
-    fn switch_screen(&mut self, new_screen: CurrentScreen) {
-        match new_screen {
-            CurrentScreen::Main => {
-                self.edit_menu.deselect();
-                self.sound_selection_menu.deselect();
-                self.first_edit = true;
-                if self.current_screen == CurrentScreen::Editing {
-                    self.main_menu.select(1);
-                } else {
-                    self.main_menu.select(0);
-                }
-            }
-            CurrentScreen::Editing => {
-                self.main_menu.deselect();
-                self.sound_selection_menu.deselect();
-                self.edit_menu.select(0);
-            }
-            CurrentScreen::SoundSelection => {
-                self.main_menu.deselect();
-                self.edit_menu.deselect();
-                self.refresh_sound_selection_menu();
-            }
-            CurrentScreen::Exiting => {
-                self.main_menu.deselect();
-                self.edit_menu.deselect();
-                self.sound_selection_menu.deselect();
-                self.currently_editing = None;
-                self.clear_strings();
-            }
-            CurrentScreen::Error => {
-                // Probably unnecessary but might as well while I'm here?
-                self.main_menu.deselect();
-                self.edit_menu.deselect();
-                self.sound_selection_menu.deselect();
-            }
-        }
-        self.current_screen = new_screen;
+    // app::cycle_theme should step through every theme and remember the choice in the config
+    #[test]
+    fn app_cycle_theme() {
+        let mut test_app = App::new(TEST_SETTINGS, TEST_TICK_RATE);
+        assert_eq!(test_app.theme().name, "dark");
+        test_app.cycle_theme();
+        assert_eq!(test_app.theme().name, "light");
+        assert_eq!(test_app.config.theme, "light");
+        test_app.cycle_theme();
+        test_app.cycle_theme();
+        assert_eq!(test_app.theme().name, "dark");
     }
 
-    fn menu_navigate(&mut self, key: KeyEvent) {
-        match key.code {
-            KeyCode::Up | KeyCode::Left | KeyCode::BackTab => match self.current_screen {
-                CurrentScreen::Main => {
-                    self.main_menu.previous();
-                }
-                CurrentScreen::Editing => {
-                    if self.currently_editing.is_none() {
-                        self.edit_menu.previous();
-                    }
-                }
-                CurrentScreen::SoundSelection => {
-                    self.sound_selection_menu.previous();
-                }
-                CurrentScreen::Exiting => {}
-                CurrentScreen::Error => {}
-            },
-            KeyCode::Down | KeyCode::Right | KeyCode::Tab => match self.current_screen {
-                CurrentScreen::Main => {
-                    self.main_menu.next();
-                }
-                CurrentScreen::Editing => {
-                    if self.currently_editing.is_none() {
-                        self.edit_menu.next();
-                    }
-                }
-                CurrentScreen::SoundSelection => {
-                    self.sound_selection_menu.next();
-                }
-                CurrentScreen::Exiting => {}
-                CurrentScreen::Error => {}
-            },
-            KeyCode::Esc => {
-                match self.current_screen {
-                    CurrentScreen::Main => {}
-                    CurrentScreen::Editing => {
-                        // if in EditMode return to EditScreen, if in EditScreen return to MainScreen
-                        if self.currently_editing.is_some() {
-                            self.edit_menu.select(0);
-                            self.currently_editing = None;
-                            self.clear_strings();
-                        } else {
-                            self.current_screen = CurrentScreen::Main;
-                            self.edit_menu.deselect();
-                            self.main_menu.select(1);
-                        }
-                    }
-                    CurrentScreen::SoundSelection => {
-                        self.switch_screen(CurrentScreen::Editing);
-                    }
-                    CurrentScreen::Exiting => {}
-                    CurrentScreen::Error => {}
-                }
-            }
-            _ => {}
-        }
+    // app::record_tap should only score taps while tap practice is on and the metronome is running
+    #[test]
+    fn app_record_tap() {
+        let mut test_app = App::new(TEST_SETTINGS, TEST_TICK_RATE);
+        test_app.settings.is_running.swap(true, Ordering::Relaxed);
+        *test_app.settings.last_tick_time.lock().unwrap() =
+            Some(Instant::now() - std::time::Duration::from_millis(20));
+        test_app.record_tap();
+        assert_eq!(test_app.tap_stats.taps(), 0);
+
+        test_app.toggle_tap_practice();
+        test_app.record_tap();
+        assert_eq!(test_app.tap_stats.taps(), 1);
+        assert!(test_app.tap_stats.last_offset_ms > 0.0);
+
+        // turning it back on starts over
+        test_app.toggle_tap_practice();
+        test_app.toggle_tap_practice();
+        assert_eq!(test_app.tap_stats.taps(), 0);
     }
-}
 
-// Tests ---------------------------------------------------------------------------------------------------------------
-#[cfg(test)]
-mod tests {
-    use super::*;
+    // the final bar cue should only show on the last bar before stopping, and only when it is on
+    #[test]
+    fn app_final_bar_cue() {
+        let mut test_app = App::new(TEST_SETTINGS, TEST_TICK_RATE);
+        test_app.settings.is_running.swap(true, Ordering::Relaxed);
+        test_app.settings.stop_after_bars.swap(4, Ordering::Relaxed);
+        test_app.settings.bar_count.swap(4, Ordering::Relaxed);
+        assert!(!test_app.get_is_final_bar());
+        test_app.toggle_final_bar_cue();
+        assert!(test_app.get_final_bar_cue());
+        assert!(test_app.get_is_final_bar());
+        test_app.settings.bar_count.swap(3, Ordering::Relaxed);
+        assert!(!test_app.get_is_final_bar());
+    }
 
-    const TEST_SETTINGS: InitMetronomeSettings = InitMetronomeSettings {
-        bpm: 120,
-        ts_note: 4,
-        ts_value: 4,
-        volume: 100.0,
-        is_running: false,
-        debug: false,
-    };
+    // accent groups must add up to the bar and are reset when the bar changes length
+    #[test]
+    fn app_change_accent_groups_editor() {
+        let mut test_app = App::new(TEST_SETTINGS, TEST_TICK_RATE);
+        test_app.settings.ts_note.swap(7, Ordering::Relaxed);
+        test_app.settings.ts_value.swap(8, Ordering::Relaxed);
+        let (_, beats_per_bar) = test_app.get_timing();
+        test_app
+            .settings
+            .beats_per_bar
+            .swap(beats_per_bar, Ordering::Relaxed);
+        assert_eq!(test_app.get_accent_groups_string(), "beat 1");
 
-    const TEST_TICK_RATE: u64 = 7;
+        test_app.edit_string = "2+2+2".to_owned();
+        assert!(!test_app.change_accent_groups_editor());
+        test_app.edit_string = "2+2+3".to_owned();
+        assert!(test_app.change_accent_groups_editor());
+        assert_eq!(test_app.get_accent_groups_string(), "2+2+3");
+        assert_eq!(parse_accent_groups("3, 2 2"), Some(vec![3, 2, 2]));
+        assert_eq!(parse_accent_groups("2+0"), None);
 
-    // helper functions should return their values
+        test_app.settings.beats_per_bar.swap(4, Ordering::Relaxed);
+        test_app.resize_beat_sounds();
+        assert_eq!(test_app.get_accent_groups_string(), "beat 1");
+    }
+
+    // app::change_pan_editor should save each voice's pan as a fraction and describe it by side
     #[test]
-    fn app_get_bpm() {
+    fn app_change_pan_editor() {
         let mut test_app = App::new(TEST_SETTINGS, TEST_TICK_RATE);
-        assert_eq!(test_app.get_bpm(), 120);
+        test_app.refresh_edit_menu();
+        assert_eq!(test_app.edit_menu.items[45], "Click pan: center");
+
+        test_app.switch_screen(CurrentScreen::Editing);
+        test_app.edit_menu.select(47);
+        let _ = test_app.update(KeyEvent::from(KeyCode::Enter));
+        assert!(test_app.currently_editing == Some(CurrentlyEditing::PolyPan));
+        assert_eq!(test_app.edit_string, "0");
+        test_app.edit_string = "150".to_owned();
+        let _ = test_app.update(KeyEvent::from(KeyCode::Enter));
+        assert_eq!(test_app.get_pan(CurrentlyEditing::PolyPan), 0);
+        assert_eq!(
+            test_app.alert_string,
+            "Please input a pan between -100 (left) and 100 (right)"
+        );
+        test_app.edit_string = "40".to_owned();
+        let _ = test_app.update(KeyEvent::from(KeyCode::Enter));
+        assert!(test_app.currently_editing.is_none());
+        assert_eq!(test_app.edit_menu.state.selected(), Some(47));
+        assert_eq!(test_app.settings.poly_pan.load(Ordering::Relaxed), 0.4);
+
+        test_app.edit_string = "-25".to_owned();
+        assert!(test_app.change_pan_editor(CurrentlyEditing::SubdivisionPan));
+        test_app.refresh_edit_menu();
+        assert_eq!(test_app.edit_menu.items[46], "Subdivision pan: 25% left");
+        assert_eq!(test_app.edit_menu.items[47], "Polyrhythm pan: 40% right");
+        assert_eq!(test_app.edit_menu.items[53], "Back to main menu");
+
+        // the last pan edited through the menu can be undone
+        let _ = test_app.update(KeyEvent::from(KeyCode::Char('u')));
+        assert_eq!(test_app.get_pan(CurrentlyEditing::PolyPan), 0);
     }
 
+    // app::change_accent_every_editor should take 0 to 64 beats between accents, 0 turning them off
     #[test]
-    fn app_get_volume() {
+    fn app_change_accent_every_editor() {
         let mut test_app = App::new(TEST_SETTINGS, TEST_TICK_RATE);
-        assert_eq!(test_app.get_volume(), 100.0);
+        test_app.refresh_edit_menu();
+        assert_eq!(test_app.edit_menu.items[48], "Accent every: off");
+
+        test_app.switch_screen(CurrentScreen::Editing);
+        test_app.edit_menu.select(48);
+        let _ = test_app.update(KeyEvent::from(KeyCode::Enter));
+        assert!(test_app.currently_editing == Some(CurrentlyEditing::AccentEvery));
+        assert_eq!(test_app.edit_string, "0");
+        test_app.edit_string = "65".to_owned();
+        let _ = test_app.update(KeyEvent::from(KeyCode::Enter));
+        assert_eq!(test_app.get_accent_every(), 0);
+        assert_eq!(
+            test_app.alert_string,
+            "Please input a value between 0 and 64"
+        );
+        test_app.edit_string = "3".to_owned();
+        let _ = test_app.update(KeyEvent::from(KeyCode::Enter));
+        assert!(test_app.currently_editing.is_none());
+        assert_eq!(test_app.edit_menu.state.selected(), Some(48));
+        assert_eq!(test_app.get_accent_every_string(), "every 3 beats");
+        let _ = test_app.update(KeyEvent::from(KeyCode::Char('u')));
+        assert_eq!(test_app.get_accent_every_string(), "off");
+
+        test_app.edit_string = "3".to_owned();
+        assert!(test_app.change_accent_every_editor());
+        test_app.edit_string = "0".to_owned();
+        assert!(test_app.change_accent_every_editor());
+        assert_eq!(test_app.get_accent_every_string(), "off");
     }
 
+    // app::change_accent_beats_editor should take beats in the bar counting from 1, an empty list going back to the groups
     #[test]
-    fn app_get_is_running() {
+    fn app_change_accent_beats_editor() {
         let mut test_app = App::new(TEST_SETTINGS, TEST_TICK_RATE);
-        assert_eq!(test_app.get_is_running(), false);
+        test_app.refresh_edit_menu();
+        assert_eq!(test_app.edit_menu.items[50], "Accented beats: off");
+
+        test_app.switch_screen(CurrentScreen::Editing);
+        test_app.edit_menu.select(50);
+        let _ = test_app.update(KeyEvent::from(KeyCode::Enter));
+        assert!(test_app.currently_editing == Some(CurrentlyEditing::AccentBeats));
+        assert!(test_app.edit_string.is_empty());
+        test_app.edit_string = "5".to_owned();
+        let _ = test_app.update(KeyEvent::from(KeyCode::Enter));
+        assert!(test_app.settings.accent_beats.lock().unwrap().is_empty());
+        assert_eq!(
+            test_app.alert_string,
+            "Please input beats between 1 and 4, ie. 4 or 1 3"
+        );
+        test_app.edit_string = "3, 1 3".to_owned();
+        let _ = test_app.update(KeyEvent::from(KeyCode::Enter));
+        assert!(test_app.currently_editing.is_none());
+        assert_eq!(test_app.edit_menu.state.selected(), Some(50));
+        assert_eq!(*test_app.settings.accent_beats.lock().unwrap(), vec![0, 2]);
+        assert_eq!(test_app.get_accent_beats_string(), "1 3");
+
+        assert_eq!(parse_accent_beats("0"), None);
+        assert_eq!(parse_accent_beats("four"), None);
+        test_app.edit_string.clear();
+        assert!(test_app.change_accent_beats_editor());
+        assert_eq!(test_app.get_accent_beats_string(), "off");
     }
 
-    // change functions should change the internal state of app based on edit_string
+    // app::change_feel_offsets_editor should save an offset per beat and drop the zeros left at the end
     #[test]
-    fn app_change_bpm_editor() {
+    fn app_change_feel_offsets_editor() {
         let mut test_app = App::new(TEST_SETTINGS, TEST_TICK_RATE);
-        test_app.edit_string = "200".to_string();
-        test_app.change_bpm_editor();
-        assert_eq!(test_app.get_bpm(), 200);
+        test_app.refresh_edit_menu();
+        assert_eq!(test_app.edit_menu.items[44], "Feel offsets: off");
+
+        test_app.edit_string = "0 10 500".to_owned();
+        assert!(!test_app.change_feel_offsets_editor());
+        test_app.edit_string = "0, +10 0 -5 0".to_owned();
+        assert!(test_app.change_feel_offsets_editor());
+        assert_eq!(test_app.get_feel_offsets_string(), "0 10 0 -5");
+        assert_eq!(parse_feel_offsets("ten"), None);
+        assert_eq!(parse_feel_offsets(""), Some(vec![]));
+
+        test_app.edit_string = "0 0".to_owned();
+        assert!(test_app.change_feel_offsets_editor());
+        assert_eq!(test_app.get_feel_offsets_string(), "off");
     }
 
-    // app::change_bpm should not change bpm with invalid input
+    // (d) should show the debug panel with the engine's timing measurements
     #[test]
-    fn app_change_bpm_bad_input() {
+    fn app_debug_panel() {
         let mut test_app = App::new(TEST_SETTINGS, TEST_TICK_RATE);
-        test_app.edit_string = "hey this isn't a number is it?".to_string();
-        assert_eq!(test_app.change_bpm_editor(), false);
-        assert_eq!(test_app.get_bpm(), 120);
+        assert!(!test_app.get_debug());
+        let _ = test_app.update(KeyEvent::from(KeyCode::Char('d')));
+        assert!(test_app.get_debug());
+        test_app
+            .settings
+            .last_jitter_ns
+            .swap(1_250_000, Ordering::Relaxed);
+        test_app.settings.missed_beats.swap(2, Ordering::Relaxed);
+        let lines = test_app.get_debug_lines();
+        assert_eq!(lines[1], "Last beat late by: 1.250 ms");
+        assert_eq!(lines[3], "Missed beats: 2");
     }
 
+    // the reference tone should accept note names and frequencies, and (f) should start and stop it
     #[test]
-    fn app_change_bpm_value_too_big() {
+    fn app_reference_tone() {
         let mut test_app = App::new(TEST_SETTINGS, TEST_TICK_RATE);
-        test_app.edit_string = "500000".to_string();
-        assert_eq!(test_app.change_bpm_editor(), false);
-        assert_eq!(test_app.get_bpm(), 120);
+        assert_eq!(test_app.get_tone_string(), "A4 (440.0 Hz)");
+        test_app.edit_string = "Bb".to_owned();
+        assert!(test_app.change_tone_editor());
+        assert!((test_app.get_tone_hz() - 466.164).abs() < 0.001);
+        test_app.edit_string = "10".to_owned();
+        assert!(!test_app.change_tone_editor());
+
+        let _ = test_app.update(KeyEvent::from(KeyCode::Char('f')));
+        assert!(test_app.get_tone_playing());
+        assert!(test_app.get_tone_string().ends_with(", playing"));
+        let _ = test_app.update(KeyEvent::from(KeyCode::Char('f')));
+        assert!(!test_app.get_tone_playing());
     }
 
+    // the accent pitch should start out audible, only accept the same range as the pitch and be undoable
     #[test]
-    fn app_change_bpm_value_too_small() {
+    fn app_change_accent_pitch_editor() {
         let mut test_app = App::new(TEST_SETTINGS, TEST_TICK_RATE);
-        test_app.edit_string = "19".to_string();
-        assert_eq!(test_app.change_bpm_editor(), false);
-        assert_eq!(test_app.get_bpm(), 120);
+        assert_eq!(test_app.get_accent_pitch_mult(), DEFAULT_ACCENT_PITCH);
+        test_app.edit_string = "1.5".to_owned();
+        assert!(test_app.change_accent_pitch_editor());
+        assert_eq!(test_app.get_accent_pitch_mult(), 1.5);
+        test_app.edit_string = "5".to_owned();
+        assert!(!test_app.change_accent_pitch_editor());
+        assert_eq!(test_app.get_accent_pitch_mult(), 1.5);
+
+        test_app.open_editor(CurrentlyEditing::AccentPitch);
+        test_app.edit_string = "2".to_owned();
+        let _ = test_app.update(KeyEvent::from(KeyCode::Enter));
+        assert_eq!(test_app.get_accent_pitch_mult(), 2.0);
+        let _ = test_app.update(KeyEvent::from(KeyCode::Char('u')));
+        assert_eq!(test_app.get_accent_pitch_mult(), 1.5);
     }
 
+    // smooth click edges should toggle on and off from the edit menu
     #[test]
-    fn app_change_bpm_value_negative() {
+    fn app_toggle_smooth_clicks() {
         let mut test_app = App::new(TEST_SETTINGS, TEST_TICK_RATE);
-        test_app.edit_string = "-120".to_string();
-        assert_eq!(test_app.change_bpm_editor(), false);
-        assert_eq!(test_app.get_bpm(), 120);
+        test_app.switch_screen(CurrentScreen::Editing);
+        test_app.edit_menu.select(38);
+        assert!(!test_app.get_smooth_clicks());
+        let _ = test_app.update(KeyEvent::from(KeyCode::Enter));
+        assert!(test_app.get_smooth_clicks());
+        test_app.refresh_edit_menu();
+        assert_eq!(test_app.edit_menu.items[38], "Smooth click edges: on");
     }
 
+    // normalizing should toggle from the edit menu, leaving the sounds for the metronome's thread to measure
     #[test]
-    fn app_change_bpm_value_is_float() {
+    fn app_toggle_normalize_sounds() {
         let mut test_app = App::new(TEST_SETTINGS, TEST_TICK_RATE);
-        test_app.edit_string = "120.5".to_string();
-        assert_eq!(test_app.change_bpm_editor(), false);
-        assert_eq!(test_app.get_bpm(), 120);
+        test_app.sound_list = vec!["EmeryBoardClick.wav".to_owned()];
+        *test_app.settings.sound_list.lock().unwrap() = test_app.sound_list.clone();
+        test_app.switch_screen(CurrentScreen::Editing);
+        test_app.edit_menu.select(49);
+        assert!(!test_app.get_normalize_sounds());
+        let _ = test_app.update(KeyEvent::from(KeyCode::Enter));
+        assert!(test_app.get_normalize_sounds());
+        assert!(test_app.settings.sound_gains.lock().unwrap().is_empty());
+        test_app.refresh_edit_menu();
+        assert_eq!(test_app.edit_menu.items[49], "Normalize sound levels: on");
     }
 
-    // app::change_volume should not change volume with bad input
+    // downbeat only should toggle on and off
     #[test]
-    fn app_change_volume_editor_bad_input() {
+    fn app_toggle_downbeat_only() {
         let mut test_app = App::new(TEST_SETTINGS, TEST_TICK_RATE);
-        test_app.edit_string = "hey this isn't a number is it?".to_string();
-        assert_eq!(test_app.change_volume_editor(), false);
-        assert_eq!(test_app.get_volume(), 100.0);
+        assert!(!test_app.get_downbeat_only());
+        test_app.toggle_downbeat_only();
+        assert!(test_app.get_downbeat_only());
+        test_app.toggle_downbeat_only();
+        assert!(!test_app.get_downbeat_only());
     }
 
+    // the bar progress should only be shown while the metronome is running
     #[test]
-    fn app_change_volume_editor_value_too_big() {
+    fn app_get_bar_progress() {
         let mut test_app = App::new(TEST_SETTINGS, TEST_TICK_RATE);
-        test_app.edit_string = "500000".to_string();
-        assert_eq!(test_app.change_volume_editor(), false);
-        assert_eq!(test_app.get_volume(), 100.0);
+        assert_eq!(test_app.get_bar_progress(), None);
+        test_app.settings.is_running.swap(true, Ordering::Relaxed);
+        test_app
+            .settings
+            .current_beat_count
+            .swap(3, Ordering::Relaxed);
+        *test_app.settings.last_tick_time.lock().unwrap() = Some(Instant::now());
+        let progress = test_app.get_bar_progress().unwrap();
+        assert!((0.5..0.75).contains(&progress));
+    }
+
+    // the output meter should pulse with the click while running and stay dark while muted
+    #[test]
+    fn app_get_output_level() {
+        let mut test_app = App::new(TEST_SETTINGS, TEST_TICK_RATE);
+        assert_eq!(test_app.get_output_level(), None);
+        test_app.settings.is_running.swap(true, Ordering::Relaxed);
+        assert_eq!(test_app.get_output_level(), Some(0.0));
+        *test_app.settings.last_tick_time.lock().unwrap() = Some(Instant::now());
+        assert!(test_app.get_output_level().unwrap() > 0.0);
+        test_app.toggle_mute();
+        assert_eq!(test_app.get_output_level(), Some(0.0));
+    }
+
+    // (s) should step through the subdivisions, changing the ticks per bar to match
+    #[test]
+    fn app_cycle_subdivision() {
+        let mut test_app = App::new(TEST_SETTINGS, TEST_TICK_RATE);
+        assert_eq!(test_app.get_subdivision(), Subdivision::Straight);
+        let _ = test_app.update(KeyEvent::from(KeyCode::Char('s')));
+        assert_eq!(test_app.get_subdivision(), Subdivision::Eighths);
+        assert_eq!(test_app.settings.beats_per_bar.load(Ordering::Relaxed), 8);
+        assert_eq!(test_app.get_beat_sounds().len(), 8);
+        test_app.cycle_subdivision();
+        test_app.cycle_subdivision();
+        assert_eq!(test_app.get_subdivision(), Subdivision::Triplets);
+        assert!(!test_app.settings.sub_sixteens.load(Ordering::Relaxed));
+        assert_eq!(test_app.settings.beats_per_bar.load(Ordering::Relaxed), 6);
+    }
+
+    // (shift + d) and (shift + h) should double and halve the bpm from the base tempo, and the same key again should go
+    // back to it
+    #[test]
+    fn app_toggle_time_feel() {
+        let mut test_app = App::new(TEST_SETTINGS, TEST_TICK_RATE);
+        test_app.change_bpm(120.0);
+        // the keys work on the main screen without touching the quickslots
+        assert!(test_app.current_screen == CurrentScreen::Main);
+        let quickslots = test_app.config.bpm_quickslots;
+        let _ = test_app.update(KeyEvent::new(KeyCode::Char('D'), KeyModifiers::SHIFT));
+        assert_eq!(test_app.get_bpm(), 240.0);
+        assert_eq!(test_app.get_time_feel_string(), "    Double time of 120");
+        let _ = test_app.update(KeyEvent::new(KeyCode::Char('H'), KeyModifiers::SHIFT));
+        assert_eq!(test_app.get_bpm(), 60.0);
+        assert_eq!(test_app.get_time_feel(), TimeFeel::Half);
+        let _ = test_app.update(KeyEvent::new(KeyCode::Char('H'), KeyModifiers::SHIFT));
+        assert_eq!(test_app.get_bpm(), 120.0);
+        assert_eq!(test_app.get_time_feel_string(), "");
+        assert_eq!(test_app.config.bpm_quickslots, quickslots);
+
+        // doubling is kept in range, and nudging the tempo makes it the new normal
+        test_app.change_bpm(300.0);
+        test_app.toggle_time_feel(TimeFeel::Double);
+        assert_eq!(test_app.get_bpm(), 500.0);
+        test_app.nudge_bpm(-1);
+        assert_eq!(test_app.get_time_feel(), TimeFeel::Normal);
+        test_app.toggle_time_feel(TimeFeel::Half);
+        assert_eq!(test_app.get_bpm(), 249.5);
+    }
+
+    // (x) should stop every sound without quitting
+    #[test]
+    fn app_stop_all_sound() {
+        let mut test_app = App::new(TEST_SETTINGS, TEST_TICK_RATE);
+        test_app.settings.is_running.swap(true, Ordering::Relaxed);
+        test_app.settings.tone_playing.swap(true, Ordering::Relaxed);
+        *test_app.settings.preview_sound.lock().unwrap() = Some(1);
+        let _ = test_app.update(KeyEvent::from(KeyCode::Char('x')));
+        assert!(!test_app.settings.is_running.load(Ordering::Relaxed));
+        assert!(!test_app.settings.tone_playing.load(Ordering::Relaxed));
+        assert_eq!(*test_app.settings.preview_sound.lock().unwrap(), None);
+        assert!(test_app.settings.stop_all_sound.load(Ordering::Relaxed));
+        assert_eq!(test_app.get_status_message().unwrap(), "All sound stopped");
+        assert!(!test_app.should_quit);
+    }
+
+    // (shift + s) should switch triplets on and off, putting back the subdivision they replaced
+    #[test]
+    fn app_toggle_triplets() {
+        let mut test_app = App::new(TEST_SETTINGS, TEST_TICK_RATE);
+        test_app.cycle_subdivision();
+        test_app.cycle_subdivision();
+        assert_eq!(test_app.get_subdivision(), Subdivision::Sixteenths);
+        let _ = test_app.update(KeyEvent::new(KeyCode::Char('S'), KeyModifiers::SHIFT));
+        assert_eq!(test_app.get_subdivision(), Subdivision::Triplets);
+        assert!(!test_app.settings.sub_sixteens.load(Ordering::Relaxed));
+        assert_eq!(test_app.settings.beats_per_bar.load(Ordering::Relaxed), 6);
+        assert_eq!(test_app.get_status_message().unwrap(), "Triplets ON");
+
+        let _ = test_app.update(KeyEvent::new(KeyCode::Char('S'), KeyModifiers::SHIFT));
+        assert_eq!(test_app.get_subdivision(), Subdivision::Sixteenths);
+        assert_eq!(test_app.settings.beats_per_bar.load(Ordering::Relaxed), 16);
+        assert_eq!(test_app.get_status_message().unwrap(), "Triplets OFF");
+
+        // triplets reached by cycling have nothing to go back to but straight
+        test_app.cycle_subdivision();
+        test_app.subdivision_before_triplets = Subdivision::Straight;
+        test_app.toggle_triplets();
+        assert_eq!(test_app.get_subdivision(), Subdivision::Straight);
+
+        test_app.status_message = Some((
+            "Triplets ON".to_owned(),
+            Instant::now() - STATUS_MESSAGE_DURATION,
+        ));
+        assert!(test_app.get_status_message().is_none());
+    }
+
+    // while running with changes held for the next bar, (s) should step on from the held subdivision and stopping
+    // should apply it
+    #[test]
+    fn app_time_sig_on_downbeat() {
+        let mut test_app = App::new(TEST_SETTINGS, TEST_TICK_RATE);
+        test_app.toggle_time_sig_on_downbeat();
+        test_app.settings.is_running.swap(true, Ordering::Relaxed);
+        test_app.cycle_subdivision();
+        test_app.cycle_subdivision();
+        assert_eq!(test_app.get_subdivision(), Subdivision::Straight);
+        assert_eq!(test_app.settings.beats_per_bar.load(Ordering::Relaxed), 4);
+        test_app.refresh_edit_menu();
+        assert!(test_app.edit_menu.items[4].contains("changing at the next bar"));
+
+        test_app.toggle_metronome();
+        assert_eq!(test_app.get_subdivision(), Subdivision::Sixteenths);
+        assert_eq!(test_app.settings.beats_per_bar.load(Ordering::Relaxed), 16);
+    }
+
+    // (r) should only ask for a restart while the metronome is running
+    #[test]
+    fn app_restart_bar() {
+        let mut test_app = App::new(TEST_SETTINGS, TEST_TICK_RATE);
+        let _ = test_app.update(KeyEvent::from(KeyCode::Char('r')));
+        assert!(!test_app.settings.restart.load(Ordering::Relaxed));
+        test_app.settings.is_running.swap(true, Ordering::Relaxed);
+        let _ = test_app.update(KeyEvent::from(KeyCode::Char('r')));
+        assert!(test_app.settings.restart.load(Ordering::Relaxed));
+    }
+
+    // without any sounds the metronome shouldn't start, and the status panel should still show
+    #[test]
+    fn app_empty_sound_list() {
+        let mut test_app = App::new(TEST_SETTINGS, TEST_TICK_RATE);
+        assert!(test_app.sound_list.is_empty());
+        test_app.refresh_edit_menu();
+        assert_eq!(test_app.get_selected_sound_string(), "none");
+        test_app.toggle_metronome();
+        assert!(!test_app.get_is_running());
+        assert!(test_app.alert_string.starts_with("No sounds found in "));
+
+        // visual only mode has nothing to play anyway
+        test_app.settings.silent.swap(true, Ordering::Relaxed);
+        test_app.toggle_metronome();
+        assert!(test_app.get_is_running());
+        test_app.toggle_metronome();
+
+        test_app.settings.silent.swap(false, Ordering::Relaxed);
+        test_app.sound_list = vec!["EmeryBoardClick.wav".to_owned()];
+        test_app.toggle_metronome();
+        assert!(test_app.get_is_running());
+    }
+
+    // app::adjust_panel_width should stay within the allowed widths and be kept in the config
+    #[test]
+    fn app_adjust_panel_width() {
+        let mut test_app = App::new(TEST_SETTINGS, TEST_TICK_RATE);
+        assert_eq!(test_app.panel_width, 25);
+        test_app.adjust_panel_width(PANEL_WIDTH_STEP);
+        assert_eq!(test_app.panel_width, 30);
+        assert_eq!(test_app.config.panel_width, 30);
+        for _ in 0..20 {
+            test_app.adjust_panel_width(-PANEL_WIDTH_STEP);
+        }
+        assert_eq!(test_app.panel_width, MIN_PANEL_WIDTH);
+        for _ in 0..20 {
+            test_app.adjust_panel_width(PANEL_WIDTH_STEP);
+        }
+        assert_eq!(test_app.panel_width, MAX_PANEL_WIDTH);
+    }
+
+    // the setlist can't be turned on without sections, and shows the current section while playing
+    #[test]
+    fn app_setlist() {
+        let mut test_app = App::new(TEST_SETTINGS, TEST_TICK_RATE);
+        test_app.toggle_setlist();
+        assert!(!test_app.get_setlist_enabled());
+
+        test_app.settings.setlist.lock().unwrap().sections = vec![
+            Section {
+                name: "Intro".to_owned(),
+                bpm: 90,
+                ts_note: 4,
+                ts_value: 4,
+                bars: 2,
+                note: "Count in".to_owned(),
+            },
+            Section {
+                name: "Verse".to_owned(),
+                bpm: 120,
+                ts_note: 3,
+                ts_value: 4,
+                bars: 4,
+                note: String::new(),
+            },
+        ];
+        test_app.toggle_setlist();
+        assert_eq!(test_app.get_setlist_string(), "on (2 sections)");
+        assert_eq!(test_app.get_setlist_position(), None);
+        test_app.settings.is_running.swap(true, Ordering::Relaxed);
+        test_app.settings.bar_count.swap(2, Ordering::Relaxed);
+        assert_eq!(
+            test_app.get_setlist_position().unwrap(),
+            "Section: Intro, bar 2 of 2    Next: Verse    Note: Count in"
+        );
+        test_app.settings.bar_count.swap(6, Ordering::Relaxed);
+        assert_eq!(
+            test_app.get_setlist_position().unwrap(),
+            "Section: Verse, bar 4 of 4    Next: end"
+        );
+    }
+
+    // the tempo map should only turn on with tempo changes loaded, and its changes switch between stepped and gradual
+    #[test]
+    fn app_tempo_map() {
+        let mut test_app = App::new(TEST_SETTINGS, TEST_TICK_RATE);
+        test_app.toggle_tempo_map();
+        assert!(!test_app.get_tempo_map_enabled());
+        assert_eq!(
+            test_app.alert_string,
+            "No tempo changes found in tempo_map.csv"
+        );
+
+        test_app.settings.tempo_map.lock().unwrap().points = vec![
+            TempoPoint { bar: 1, bpm: 90.0 },
+            TempoPoint { bar: 9, bpm: 130.0 },
+        ];
+        test_app.toggle_tempo_map();
+        assert!(test_app.get_tempo_map_enabled());
+        test_app.refresh_edit_menu();
+        assert_eq!(test_app.edit_menu.items[51], "Tempo map: on (2 changes)");
+        assert_eq!(test_app.edit_menu.items[52], "Tempo map changes: stepped");
+        test_app.toggle_tempo_map_interpolate();
+        test_app.refresh_edit_menu();
+        assert_eq!(test_app.edit_menu.items[52], "Tempo map changes: gradual");
+        assert_eq!(
+            test_app.settings.tempo_map.lock().unwrap().bpm_at(5),
+            Some(110.0)
+        );
+        test_app.toggle_tempo_map();
+        assert_eq!(test_app.get_tempo_map_string(), "off (2 changes)");
     }
 
+    // overriding the beats per bar should change the bar length without touching the time signature
     #[test]
-    fn app_change_volume_editor_value_too_small() {
+    fn app_change_beats_per_bar_editor() {
         let mut test_app = App::new(TEST_SETTINGS, TEST_TICK_RATE);
+        test_app.edit_string = "65".to_string();
+        assert!(!test_app.change_beats_per_bar_editor());
+        test_app.edit_string = "2".to_string();
+        assert!(test_app.change_beats_per_bar_editor());
+        assert_eq!(test_app.settings.beats_per_bar.load(Ordering::Relaxed), 2);
+        assert_eq!(test_app.get_beat_sounds().len(), 2);
+        assert_eq!(test_app.get_time_sig_string(), "4/4");
+        assert_eq!(test_app.get_timing(), (500_000_000, 2));
+
+        // 0 follows the time signature again
         test_app.edit_string = "0".to_string();
-        assert_eq!(test_app.change_volume_editor(), false);
-        assert_eq!(test_app.get_volume(), 100.0);
+        assert!(test_app.change_beats_per_bar_editor());
+        assert_eq!(
+            test_app.get_beats_per_bar_string(),
+            "4 (from time signature)"
+        );
+
+        // undoing an edit puts the bar length back as well as the beat sounds
+        test_app.open_editor(CurrentlyEditing::BeatsPerBar);
+        test_app.edit_string = "3".to_string();
+        let _ = test_app.update(KeyEvent::from(KeyCode::Enter));
+        assert_eq!(test_app.get_beats_per_bar_override(), 3);
+        let _ = test_app.update(KeyEvent::from(KeyCode::Char('u')));
+        assert_eq!(test_app.get_beats_per_bar_override(), 0);
+        assert_eq!(test_app.settings.beats_per_bar.load(Ordering::Relaxed), 4);
+        assert_eq!(test_app.get_beat_sounds().len(), 4);
     }
 
+    // app::get_link_string should show the peer count while Link is on
     #[test]
-    fn app_change_volume_editor_value_negative() {
+    fn app_get_link_string() {
         let mut test_app = App::new(TEST_SETTINGS, TEST_TICK_RATE);
-        test_app.edit_string = "-120".to_string();
-        assert_eq!(test_app.change_volume_editor(), false);
-        assert_eq!(test_app.get_volume(), 100.0);
+        assert_eq!(test_app.get_link_string(), "off");
+        test_app.toggle_link();
+        test_app.settings.link_peers.swap(1, Ordering::Relaxed);
+        assert_eq!(test_app.get_link_string(), "on (1 peer)");
+        test_app.settings.link_peers.swap(3, Ordering::Relaxed);
+        assert_eq!(test_app.get_link_string(), "on (3 peers)");
+    }
+
+    // app::cycle_midi_port should step through the ports and back to the virtual port
+    #[test]
+    fn app_cycle_midi_port() {
+        let mut test_app = App::new(TEST_SETTINGS, TEST_TICK_RATE);
+        let port_names = vec!["Drum Machine".to_string(), "Synth".to_string()];
+        test_app.cycle_midi_port(&port_names);
+        assert_eq!(test_app.get_midi_port_string(), "Drum Machine");
+        test_app.cycle_midi_port(&port_names);
+        assert_eq!(test_app.get_midi_port_string(), "Synth");
+        test_app.cycle_midi_port(&port_names);
+        assert_eq!(test_app.get_midi_port_string(), "");
+        // a port that was unplugged goes back to the virtual port
+        *test_app.settings.midi_port.lock().unwrap() = "Unplugged".to_string();
+        test_app.cycle_midi_port(&port_names);
+        assert_eq!(test_app.get_midi_port_string(), "");
+    }
+
+    // app::stop_metronome_thread should tell the metronome thread to return and wait for it
+    #[test]
+    fn app_stop_metronome_thread() {
+        let mut test_app = App::new(TEST_SETTINGS, TEST_TICK_RATE);
+        let shutdown = Arc::clone(&test_app.settings.shutdown);
+        test_app.metronome_handle = Some(thread::spawn(move || {
+            while !shutdown.load(Ordering::Relaxed) {
+                thread::sleep(std::time::Duration::from_millis(1));
+            }
+        }));
+        test_app.stop_metronome_thread();
+        assert!(test_app.metronome_handle.is_none());
+        assert!(test_app.settings.shutdown.load(Ordering::Relaxed));
+    }
+
+    // app::refresh_practice_timer should only time while the metronome is running
+    #[test]
+    fn app_refresh_practice_timer() {
+        let mut test_app = App::new(TEST_SETTINGS, TEST_TICK_RATE);
+        test_app.sound_list = vec!["EmeryBoardClick.wav".to_owned()];
+        test_app.refresh_practice_timer();
+        assert!(test_app.practice_start.is_none());
+        test_app.toggle_metronome();
+        test_app.refresh_practice_timer();
+        assert!(test_app.practice_start.is_some());
+        assert_eq!(test_app.get_elapsed_string(), "00:00");
+        test_app.toggle_metronome();
+        test_app.refresh_practice_timer();
+        assert!(test_app.practice_start.is_none());
+    }
+
+    // a session should follow the metronome and be logged once it stops, adding to the totals
+    #[test]
+    fn app_practice_log() {
+        let path = std::env::temp_dir().join("readymetronome_test_app_practice_log.jsonl");
+        let path = path.to_str().unwrap().to_owned();
+        let _ = fs::remove_file(&path);
+        let mut test_app = App::new(TEST_SETTINGS, TEST_TICK_RATE);
+        test_app.sound_list = vec!["EmeryBoardClick.wav".to_owned()];
+        test_app.practice_log_path = Some(path.clone());
+        assert_eq!(test_app.get_practice_totals_string(), None);
+
+        test_app.toggle_metronome();
+        test_app.refresh_practice_timer();
+        assert!(test_app.session.is_some());
+        // too short to count as practice
+        test_app.toggle_metronome();
+        test_app.refresh_practice_timer();
+        assert!(test_app.session.is_none());
+        assert_eq!(practice_totals(&path).0, 0);
+
+        test_app.toggle_metronome();
+        test_app.refresh_practice_timer();
+        thread::sleep(MIN_SESSION_LENGTH);
+        test_app.toggle_metronome();
+        test_app.refresh_practice_timer();
+        assert_eq!(test_app.practice_totals.0, 1);
+        assert_eq!(
+            test_app.get_practice_totals_string().unwrap(),
+            "1 session, 0h 00m"
+        );
+        let _ = fs::remove_file(&path);
+    }
+
+    // app::nudge_bpm should change the bpm and delay together and stay within 20 - 500
+    #[test]
+    fn app_nudge_bpm() {
+        let mut test_app = App::new(TEST_SETTINGS, TEST_TICK_RATE);
+        let _ = test_app.update(KeyEvent::from(KeyCode::Char('+')));
+        assert_eq!(test_app.get_bpm(), 121.0);
+        let _ = test_app.update(KeyEvent::from(KeyCode::Char('[')));
+        assert_eq!(test_app.get_bpm(), 111.0);
+        assert_eq!(
+            test_app.settings.ns_delay.load(Ordering::Relaxed),
+            test_app.get_timing().0
+        );
+        test_app.change_bpm(25.0);
+        test_app.nudge_bpm(-10);
+        assert_eq!(test_app.get_bpm(), 20.0);
+        test_app.change_bpm(495.0);
+        test_app.nudge_bpm(10);
+        assert_eq!(test_app.get_bpm(), 500.0);
     }
 
     // app::toggle_metronome should toggle metronome
     #[test]
     fn app_toggle_metronome() {
         let mut test_app = App::new(TEST_SETTINGS, TEST_TICK_RATE);
+        test_app.sound_list = vec!["EmeryBoardClick.wav".to_owned()];
         assert_eq!(test_app.get_is_running(), false);
         test_app.toggle_metronome();
         assert_eq!(test_app.get_is_running(), true);
@@ -760,11 +6074,11 @@ mod tests {
         assert_eq!(test_app.get_is_running(), false);
     }
 
-    // app::get_ns_from_bpm should correctly calculate the nanosecond offset from bpm
+    // app::get_timing should correctly calculate the nanosecond offset from bpm
     #[test]
-    fn app_get_ns_from_bpm() {
+    fn app_get_timing() {
         let mut test_app = App::new(TEST_SETTINGS, TEST_TICK_RATE);
-        assert_eq!(test_app.get_ns_from_bpm(), 500_000_000);
+        assert_eq!(test_app.get_timing(), (500_000_000, 4));
     }
 
     // app::clear_strings should clear it's edit and notification strings when told to
@@ -787,21 +6101,21 @@ mod tests {
     #[test]
     fn app_verify_bpm() {
         let mut test_app = App::new(TEST_SETTINGS, TEST_TICK_RATE);
-        assert_eq!(test_app.verify_bpm(19), false);
-        assert_eq!(test_app.verify_bpm(501), false);
-        assert_eq!(test_app.verify_bpm(120), true);
-        assert_eq!(test_app.verify_bpm(500), true);
-        assert_eq!(test_app.verify_bpm(20), true);
+        assert_eq!(test_app.verify_bpm(19.0), false);
+        assert_eq!(test_app.verify_bpm(501.0), false);
+        assert_eq!(test_app.verify_bpm(120.0), true);
+        assert_eq!(test_app.verify_bpm(500.0), true);
+        assert_eq!(test_app.verify_bpm(20.0), true);
     }
 
     // app::verify_volume should correctly determine which values are in range
     #[test]
     fn app_verify_volume() {
         let mut test_app = App::new(TEST_SETTINGS, TEST_TICK_RATE);
-        assert_eq!(test_app.verify_volume(0.0), false);
-        assert_eq!(test_app.verify_volume(201.0), false);
-        assert_eq!(test_app.verify_volume(120.0), true);
-        assert_eq!(test_app.verify_volume(200.0), true);
-        assert_eq!(test_app.verify_volume(1.0), true);
+        assert_eq!(test_app.verify_volume(-1.0), false);
+        assert_eq!(test_app.verify_volume(101.0), false);
+        assert_eq!(test_app.verify_volume(50.0), true);
+        assert_eq!(test_app.verify_volume(100.0), true);
+        assert_eq!(test_app.verify_volume(0.0), true);
     }
 }
@@ -0,0 +1,146 @@
+/// Beat_log.rs writes the timing of every beat to a CSV file, for checking how steady the metronome is on different
+/// hardware. It runs on its own thread following the metronome's beat events, so the metronome's thread never touches
+/// the file. Rows are buffered and flushed every BEAT_LOG_FLUSH rather than once per beat, ie.
+///
+/// bar,beat,intended_ns,actual_ns,drift_ns
+/// 1,1,0,0,0
+/// 1,2,500000000,500003210,3210
+use crate::metronome::{BeatEvent, MetronomeSettings};
+use color_eyre::Report;
+use std::{
+    fs::{File, OpenOptions},
+    io::{BufWriter, Write},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::{Receiver, RecvTimeoutError},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+
+// The first line of a new log
+const BEAT_LOG_HEADER: &str = "bar,beat,intended_ns,actual_ns,drift_ns";
+
+// How often the buffered rows are written out to the file
+const BEAT_LOG_FLUSH: Duration = Duration::from_secs(1);
+
+// How long the thread waits for a beat before checking for shutdown
+const BEAT_LOG_POLL: Duration = Duration::from_millis(100);
+
+pub struct BeatLog {
+    writer: BufWriter<File>,
+    beat_events: Receiver<BeatEvent>,
+    shutdown: Arc<AtomicBool>,
+    first_beat: Option<Instant>, // when the first logged beat was due, the times in every row count from here
+}
+
+impl BeatLog {
+    // Opens the file straight away so a bad path can be reported before the thread starts. Rows are added to the end
+    // of an existing log, and the header is only written to a new one
+    pub fn new(settings: &MetronomeSettings, path: &str) -> Result<BeatLog, Report> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        let is_new = file.metadata()?.len() == 0;
+        let mut writer = BufWriter::new(file);
+        if is_new {
+            writeln!(writer, "{}", BEAT_LOG_HEADER)?;
+        }
+        Ok(BeatLog {
+            writer,
+            beat_events: settings.subscribe_beats(),
+            shutdown: Arc::clone(&settings.shutdown),
+            first_beat: None,
+        })
+    }
+
+    pub fn start(&mut self) {
+        let mut last_flush = Instant::now();
+        loop {
+            match self.beat_events.recv_timeout(BEAT_LOG_POLL) {
+                Ok(event) => {
+                    let row = self.row(&event);
+                    // a full disk shouldn't stop the metronome, the rows are just lost
+                    let _ = writeln!(self.writer, "{}", row);
+                }
+                Err(RecvTimeoutError::Timeout) => {}
+                Err(RecvTimeoutError::Disconnected) => break,
+            }
+            if self.shutdown.load(Ordering::Relaxed) {
+                break;
+            }
+            if last_flush.elapsed() >= BEAT_LOG_FLUSH {
+                let _ = self.writer.flush();
+                last_flush = Instant::now();
+            }
+        }
+        let _ = self.writer.flush();
+    }
+
+    // Describes a beat as a row of the log. The intended time is when the beat was due and the actual time is when it
+    // was played, both in nanoseconds from when the first logged beat was due. Drift is how late the beat was
+    fn row(&mut self, event: &BeatEvent) -> String {
+        let intended = event
+            .instant
+            .checked_sub(event.late)
+            .unwrap_or(event.instant);
+        let first_beat = *self.first_beat.get_or_insert(intended);
+        format!(
+            "{},{},{},{},{}",
+            event.bar,
+            event.beat,
+            intended.saturating_duration_since(first_beat).as_nanos(),
+            event
+                .instant
+                .saturating_duration_since(first_beat)
+                .as_nanos(),
+            event.late.as_nanos()
+        )
+    }
+}
+
+// Tests ---------------------------------------------------------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::metronome::InitMetronomeSettings;
+    use std::fs;
+
+    // a new log should start with the header and each row should count from the first beat
+    #[test]
+    fn beat_log_row() {
+        let settings = MetronomeSettings::new(InitMetronomeSettings {
+            bpm: 120.0,
+            ts_note: 4,
+            ts_value: 4,
+            volume: 100.0,
+            debug: false,
+            is_running: false,
+        });
+        let path = std::env::temp_dir().join("readymetronome_test_beat_log.csv");
+        let _ = fs::remove_file(&path);
+        let mut beat_log = BeatLog::new(&settings, path.to_str().unwrap()).unwrap();
+
+        let start = Instant::now();
+        let first = BeatEvent {
+            bar: 1,
+            beat: 1,
+            is_accent: true,
+            instant: start,
+            late: Duration::ZERO,
+        };
+        assert_eq!(beat_log.row(&first), "1,1,0,0,0");
+        let second = BeatEvent {
+            bar: 1,
+            beat: 2,
+            is_accent: false,
+            instant: start + Duration::from_nanos(500_003_210),
+            late: Duration::from_nanos(3_210),
+        };
+        assert_eq!(beat_log.row(&second), "1,2,500000000,500003210,3210");
+
+        settings.shutdown.swap(true, Ordering::Relaxed);
+        beat_log.start();
+        let contents = fs::read_to_string(&path).unwrap();
+        assert_eq!(contents, BEAT_LOG_HEADER.to_owned() + "\n");
+        let _ = fs::remove_file(&path);
+    }
+}
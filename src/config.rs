@@ -0,0 +1,213 @@
+/// Config.rs loads and saves the preferences Ready Metronome remembers between runs. They are kept in a config.toml
+/// file in the directory Ready Metronome is run from, ie.
+///
+/// theme = "light"
+/// bpm_quickslots = [60, 80, 100, 120, 140, 160, 180, 200, 220]
+/// panel_width = 25
+///
+/// [sound_display_names]
+/// "click_hi_02.wav" = "Wood Block (High)"
+use color_eyre::{Report, Result};
+use std::{collections::HashMap, fs};
+
+pub const CONFIG_PATH: &str = "./config.toml";
+
+// Range of the control panel's share of the screen width as a percentage, the status panel gets the rest
+pub const MIN_PANEL_WIDTH: u16 = 15;
+pub const MAX_PANEL_WIDTH: u16 = 60;
+
+pub struct Config {
+    pub theme: String,
+    pub bpm_quickslots: [u64; 9], // bpm jumped to by the 1 - 9 keys on the main screen
+    pub panel_width: u16,         // percentage of the screen width used by the control panel
+    pub sound_display_names: HashMap<String, String>, // names shown instead of sound file names, by file name
+}
+
+impl Config {
+    // The preferences used when there is no config.toml
+    pub fn new() -> Config {
+        Config {
+            theme: "dark".to_owned(),
+            bpm_quickslots: [60, 80, 100, 120, 140, 160, 180, 200, 220],
+            panel_width: 25,
+            sound_display_names: HashMap::new(),
+        }
+    }
+
+    // Loads the config from a toml file, returning the defaults if it is absent. Invalid entries are skipped and
+    // described in the returned warnings
+    pub fn load(path: &str) -> (Config, Vec<String>) {
+        let mut config = Config::new();
+        let contents = match fs::read_to_string(path) {
+            Ok(contents) => contents,
+            Err(_) => return (config, Vec::new()),
+        };
+        let warnings = config.apply(&contents);
+        (config, warnings)
+    }
+
+    // Applies the preferences found in the contents of a config.toml file
+    fn apply(&mut self, contents: &str) -> Vec<String> {
+        let table = match contents.parse::<toml::Table>() {
+            Ok(table) => table,
+            Err(_) => return vec!["config.toml could not be read, using the defaults".to_owned()],
+        };
+
+        let mut warnings = Vec::new();
+        for (name, value) in table {
+            match (name.as_str(), value) {
+                ("theme", toml::Value::String(theme)) => self.theme = theme,
+                ("bpm_quickslots", toml::Value::Array(values)) => match parse_quickslots(&values) {
+                    Some(quickslots) => self.bpm_quickslots = quickslots,
+                    None => warnings.push(
+                        "bpm_quickslots in config.toml must be 9 bpm between 20 and 500".to_owned(),
+                    ),
+                },
+                ("panel_width", toml::Value::Integer(width))
+                    if (MIN_PANEL_WIDTH as i64..=MAX_PANEL_WIDTH as i64).contains(&width) =>
+                {
+                    self.panel_width = width as u16
+                }
+                ("sound_display_names", toml::Value::Table(names)) => {
+                    for (file_name, display_name) in names {
+                        match display_name {
+                            toml::Value::String(display_name) => {
+                                self.sound_display_names.insert(file_name, display_name);
+                            }
+                            _ => warnings.push(
+                                "The display name for '".to_owned()
+                                    + &file_name
+                                    + "' in config.toml must be text",
+                            ),
+                        }
+                    }
+                }
+                _ => warnings
+                    .push("Unknown or invalid setting '".to_owned() + &name + "' in config.toml"),
+            }
+        }
+        warnings
+    }
+
+    // Writes the preferences to a toml file
+    pub fn save(&self, path: &str) -> Result<(), Report> {
+        let mut table = toml::Table::new();
+        table.insert("theme".to_owned(), toml::Value::String(self.theme.clone()));
+        table.insert(
+            "bpm_quickslots".to_owned(),
+            toml::Value::Array(
+                self.bpm_quickslots
+                    .iter()
+                    .map(|bpm| toml::Value::Integer(*bpm as i64))
+                    .collect(),
+            ),
+        );
+        table.insert(
+            "panel_width".to_owned(),
+            toml::Value::Integer(self.panel_width as i64),
+        );
+        table.insert(
+            "sound_display_names".to_owned(),
+            toml::Value::Table(
+                self.sound_display_names
+                    .iter()
+                    .map(|(file_name, display_name)| {
+                        (file_name.clone(), toml::Value::String(display_name.clone()))
+                    })
+                    .collect(),
+            ),
+        );
+        fs::write(path, table.to_string())?;
+        Ok(())
+    }
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config::new()
+    }
+}
+
+// Reads the 9 quickslot bpm from a toml array, None if there aren't exactly 9 valid bpm
+fn parse_quickslots(values: &[toml::Value]) -> Option<[u64; 9]> {
+    let bpm: Vec<u64> = values
+        .iter()
+        .filter_map(|value| value.as_integer())
+        .filter(|bpm| (20..=500).contains(bpm))
+        .map(|bpm| bpm as u64)
+        .collect();
+    bpm.try_into().ok()
+}
+
+// Tests ---------------------------------------------------------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // settings in the file should replace the defaults and unknown ones should be reported
+    #[test]
+    fn config_apply() {
+        let mut config = Config::new();
+        let warnings = config.apply("theme = \"light\"\nvolume = 3");
+        assert_eq!(config.theme, "light");
+        assert_eq!(warnings.len(), 1);
+
+        // the panel width must leave room for both panels
+        let warnings = config.apply("panel_width = 90");
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(config.panel_width, 25);
+        let warnings = config.apply("panel_width = 40");
+        assert!(warnings.is_empty());
+        assert_eq!(config.panel_width, 40);
+    }
+
+    // quickslots should only be replaced by a full set of valid bpm
+    #[test]
+    fn config_apply_quickslots() {
+        let mut config = Config::new();
+        let warnings = config.apply("bpm_quickslots = [60, 70, 80, 90, 100, 110, 120, 130, 600]");
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(config.bpm_quickslots, Config::new().bpm_quickslots);
+
+        let warnings = config.apply("bpm_quickslots = [60, 70, 80, 90, 100, 110, 120, 130, 140]");
+        assert!(warnings.is_empty());
+        assert_eq!(config.bpm_quickslots[8], 140);
+    }
+
+    // display names should be read by file name, skipping any that aren't text
+    #[test]
+    fn config_apply_sound_display_names() {
+        let mut config = Config::new();
+        let warnings = config.apply(
+            "[sound_display_names]\n\"click_hi_02.wav\" = \"Wood Block (High)\"\n\"beep.wav\" = 3",
+        );
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(
+            config.sound_display_names,
+            HashMap::from([("click_hi_02.wav".to_owned(), "Wood Block (High)".to_owned())])
+        );
+    }
+
+    // a saved config should load back the same
+    #[test]
+    fn config_save_and_load() {
+        let path = std::env::temp_dir().join("readymetronome_test_config.toml");
+        let path = path.to_str().unwrap();
+        let mut config = Config::new();
+        config.theme = "high-contrast".to_owned();
+        config.bpm_quickslots[0] = 72;
+        config.panel_width = 35;
+        config
+            .sound_display_names
+            .insert("click_hi_02.wav".to_owned(), "Wood Block (High)".to_owned());
+        config.save(path).unwrap();
+
+        let (loaded, warnings) = Config::load(path);
+        assert!(warnings.is_empty());
+        assert_eq!(loaded.theme, "high-contrast");
+        assert_eq!(loaded.bpm_quickslots, config.bpm_quickslots);
+        assert_eq!(loaded.panel_width, 35);
+        assert_eq!(loaded.sound_display_names, config.sound_display_names);
+        let _ = fs::remove_file(path);
+    }
+}
@@ -5,8 +5,12 @@ use crate::{
     event_handler::{Event, EventHandler},
     ui::ui,
 };
-use color_eyre::Result;
+use color_eyre::{eyre::eyre, Result};
 use ratatui::{backend::Backend, Terminal};
+use std::{sync::atomic::Ordering, time::Duration};
+
+// How long the headless loop waits for a beat before checking whether the metronome has stopped
+const HEADLESS_POLL: Duration = Duration::from_millis(100);
 
 // This function controls the application in Ratatui mode, It polls for user input and updates the various menus /
 // app.state appropriately. the generic Backend parameter is to allow for support for more backends than just Crossterm.
@@ -18,7 +22,9 @@ pub fn run_app<B: Backend>(
     let events = EventHandler::new(ui_refresh_rate);
     loop {
         app.check_error_status();
+        app.refresh_practice_timer();
         app.refresh_edit_menu();
+        app.refresh_flash();
         if app.should_quit {
             break;
         }
@@ -26,12 +32,15 @@ pub fn run_app<B: Backend>(
         terminal.draw(|f| ui(f, app))?;
 
         match events.next()? {
-            Event::Tick => {}
+            Event::Tick => app.decay_flash(),
             Event::Key(key_event) => match app.update(key_event) {
                 Ok(_) => {}
                 Err(e) => return Err(e),
             },
-            Event::Mouse(_) => {}
+            Event::Mouse(mouse_event) => match app.update_mouse(mouse_event) {
+                Ok(_) => {}
+                Err(e) => return Err(e),
+            },
             Event::Resize(_, _) => {}
             Event::FocusChange(_) => {}
         }
@@ -39,3 +48,33 @@ pub fn run_app<B: Backend>(
 
     Ok("exited successfully".to_string())
 }
+
+// This function runs the metronome without the ui for --no-ui. It prints each bar as it starts and returns once the
+// metronome stops by itself, ie. at the end of a setlist or after an error, otherwise it plays until Ctrl-C
+pub fn run_headless(app: &mut App) -> Result<String> {
+    if !app.alert_string.is_empty() {
+        println!("{}", app.alert_string);
+    }
+    println!(
+        "Playing {} bpm in {}, press Ctrl-C to stop",
+        app.get_bpm(),
+        app.get_time_signature_label()
+    );
+    loop {
+        app.refresh_practice_timer();
+        // Nothing is flashing without the ui, so the app's own beat events are printed instead
+        if let Ok(event) = app.beat_events.recv_timeout(HEADLESS_POLL) {
+            if event.beat == 1 {
+                println!("Bar {}", event.bar);
+            }
+        }
+        if app.settings.error.load(Ordering::Relaxed) {
+            return Err(eyre!(app.get_error_message()));
+        }
+        // Ctrl-C sets shutdown, main then stops the threads and logs the session
+        if !app.get_is_running() || app.settings.shutdown.load(Ordering::Relaxed) {
+            break;
+        }
+    }
+    Ok("metronome stopped".to_string())
+}
@@ -0,0 +1,130 @@
+/// Export.rs renders a click track to a wav file offline instead of playing it live. It loads the sound the same way
+/// the metronome does and places each click using the same nanosecond delay and beats per bar that App calculates for
+/// the live metronome, so the exported file matches what you hear
+use crate::metronome::{
+    accents_every, beat_offset_ms, beat_plays, feel_offset_ns, get_beat_sound, is_accented,
+    load_sound, sound_gain, MetronomeSettings, CLICK_ATTACK,
+};
+use color_eyre::{eyre::eyre, Report, Result};
+use hound::{SampleFormat, WavSpec, WavWriter};
+use rodio::source::{Source, UniformSourceIterator};
+use std::{
+    collections::{hash_map::Entry, HashMap},
+    sync::atomic::Ordering,
+};
+
+// Renders the given number of bars with the current settings and writes them to a 16 bit wav file at path
+pub fn export_wav(settings: &MetronomeSettings, path: &str, bars: u64) -> Result<(), Report> {
+    let sound_list = settings.sound_list.lock().unwrap().clone();
+    let selected_sound_name = match sound_list.get(settings.selected_sound.load(Ordering::Relaxed))
+    {
+        Some(value) => value.clone(),
+        None => return Err(eyre!("Error: No sound selected to export")),
+    };
+    let volume = settings.volume.load(Ordering::Relaxed);
+    let pitch = settings.pitch.load(Ordering::Relaxed);
+    let smooth = settings.smooth_clicks.load(Ordering::Relaxed);
+    let accent_pitch_mult = settings.accent_pitch_mult.load(Ordering::Relaxed);
+    let sound_dir = settings.sound_dir.lock().unwrap().clone();
+
+    // The selected sound decides the format of the file, other sounds are converted to match it
+    let source = load_sound(&sound_dir, &selected_sound_name)?;
+    let channels = source.channels();
+    let sample_rate = source.sample_rate();
+
+    // Work out where every beat lands in frames (one sample per channel)
+    let channel_count = channels as usize;
+    let ns_delay = settings.ns_delay.load(Ordering::Relaxed);
+    let frames_per_beat = ns_delay as f64 * sample_rate as f64 / 1_000_000_000.0;
+    let beats_per_bar = settings.beats_per_bar.load(Ordering::Relaxed).max(1);
+    let total_beats = bars * beats_per_bar;
+    let beat_start_frame = |beat: u64| (beat as f64 * frames_per_beat).round() as usize;
+    // each click is moved by its beat's feel offset, one played early at the very start begins the file instead
+    let offset_frames: Vec<f64> = (0..beats_per_bar)
+        .map(|beat_index| {
+            let offset = feel_offset_ns(beat_offset_ms(settings, beat_index as usize), ns_delay);
+            offset as f64 * sample_rate as f64 / 1_000_000_000.0
+        })
+        .collect();
+    let click_start_frame = |beat: u64| {
+        (beat as f64 * frames_per_beat + offset_frames[(beat % beats_per_bar) as usize])
+            .round()
+            .max(0.0) as usize
+    };
+    let downbeat_only = settings.downbeat_only.load(Ordering::Relaxed);
+    let beat_enabled: Vec<bool> = (0..beats_per_bar)
+        .map(|beat_index| beat_plays(settings, beat_index as usize))
+        .collect();
+
+    // Decode each sound used in the track once, accented beats get their own higher pitched copy. Every beat mixes in a
+    // copy of its interleaved samples. Accenting every few beats runs across the bars, so each beat of the track is
+    // worked out rather than just the first bar
+    let accent_beats = settings.accent_beats.lock().unwrap().clone();
+    let accent_groups = settings.accent_groups.lock().unwrap().clone();
+    let accent_every = settings.accent_every.load(Ordering::Relaxed);
+    let beat_sounds: Vec<(usize, bool)> = (0..total_beats)
+        .map(|beat| {
+            let beat_index = beat % beats_per_bar;
+            (
+                get_beat_sound(settings, beat_index as usize),
+                is_accented(beat_index, &accent_beats, &accent_groups, beats_per_bar)
+                    || accents_every(beat, accent_every),
+            )
+        })
+        .collect();
+    let mut clicks: HashMap<(usize, bool), Vec<f32>> = HashMap::new();
+    for (sound, accented) in &beat_sounds {
+        if let Entry::Vacant(entry) = clicks.entry((*sound, *accented)) {
+            let sound_name = match sound_list.get(*sound) {
+                Some(value) => value,
+                None => return Err(eyre!("Error: Beat sound is missing from the sound list")),
+            };
+            let click_pitch = if *accented {
+                pitch * accent_pitch_mult
+            } else {
+                pitch
+            };
+            let source = load_sound(&sound_dir, sound_name)?
+                .speed(click_pitch as f32)
+                .amplify((volume / 100.0) as f32 * sound_gain(settings, sound_name));
+            let samples = if smooth {
+                UniformSourceIterator::new(source.fade_in(CLICK_ATTACK), channels, sample_rate)
+                    .collect()
+            } else {
+                UniformSourceIterator::new(source, channels, sample_rate).collect()
+            };
+            entry.insert(samples);
+        }
+    }
+
+    // Make room for the full length of the bars plus the tail of the last click
+    let mut samples = vec![0.0_f32; beat_start_frame(total_beats) * channel_count];
+    for beat in 0..total_beats {
+        if (downbeat_only && beat % beats_per_bar != 0)
+            || !beat_enabled[(beat % beats_per_bar) as usize]
+        {
+            continue;
+        }
+        let click = &clicks[&beat_sounds[beat as usize]];
+        let start = click_start_frame(beat) * channel_count;
+        if samples.len() < start + click.len() {
+            samples.resize(start + click.len(), 0.0);
+        }
+        for (offset, sample) in click.iter().enumerate() {
+            samples[start + offset] += sample;
+        }
+    }
+
+    let spec = WavSpec {
+        channels,
+        sample_rate,
+        bits_per_sample: 16,
+        sample_format: SampleFormat::Int,
+    };
+    let mut writer = WavWriter::create(path, spec)?;
+    for sample in samples {
+        writer.write_sample((sample.clamp(-1.0, 1.0) * i16::MAX as f32) as i16)?;
+    }
+    writer.finalize()?;
+    Ok(())
+}
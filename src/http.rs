@@ -0,0 +1,265 @@
+/// Http.rs serves the metronome's state over HTTP for stage displays and other tools on the network. It is only built
+/// with the "http" feature and runs on its own thread next to the metronome, reading and writing the shared atomics.
+///
+/// GET  /status : the bpm, beat, bar, time signature and whether the metronome is running as JSON
+/// POST /bpm    : sets the bpm from the request body, ie. 140 or {"bpm": 140}
+use crate::metronome::{compute_timing, round_bpm, time_signature_label, MetronomeSettings};
+use atomic_float::AtomicF64;
+use color_eyre::Report;
+use std::{
+    io::{ErrorKind, Read, Write},
+    net::{TcpListener, TcpStream},
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc,
+    },
+    thread,
+    time::Duration,
+};
+
+// How long the thread sleeps when there is no connection waiting, short enough that requests still feel instant
+const ACCEPT_POLL: Duration = Duration::from_millis(20);
+
+// How long a connection gets to send its request before it is dropped
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(2);
+
+// Largest request read, anything the endpoints accept is far smaller
+const MAX_REQUEST_BYTES: usize = 8 * 1024;
+
+pub struct StatusServer {
+    listener: TcpListener,
+    bpm: Arc<AtomicF64>,
+    ns_delay: Arc<AtomicU64>,
+    ts_note: Arc<AtomicU64>,
+    ts_value: Arc<AtomicU64>,
+    ts_triplets: Arc<AtomicBool>,
+    sub_eights: Arc<AtomicBool>,
+    sub_sixteens: Arc<AtomicBool>,
+    current_beat_count: Arc<AtomicU64>,
+    bar_count: Arc<AtomicU64>,
+    is_running: Arc<AtomicBool>,
+    shutdown: Arc<AtomicBool>,
+}
+
+impl StatusServer {
+    // Binds to the address (ie. 127.0.0.1:8080) straight away so a bad address can be reported before the thread starts
+    pub fn new(settings: &MetronomeSettings, address: &str) -> Result<StatusServer, Report> {
+        let listener = TcpListener::bind(address)?;
+        // accepting without blocking lets the loop notice shutdown
+        listener.set_nonblocking(true)?;
+        Ok(StatusServer {
+            listener,
+            bpm: Arc::clone(&settings.bpm),
+            ns_delay: Arc::clone(&settings.ns_delay),
+            ts_note: Arc::clone(&settings.ts_note),
+            ts_value: Arc::clone(&settings.ts_value),
+            ts_triplets: Arc::clone(&settings.ts_triplets),
+            sub_eights: Arc::clone(&settings.sub_eights),
+            sub_sixteens: Arc::clone(&settings.sub_sixteens),
+            current_beat_count: Arc::clone(&settings.current_beat_count),
+            bar_count: Arc::clone(&settings.bar_count),
+            is_running: Arc::clone(&settings.is_running),
+            shutdown: Arc::clone(&settings.shutdown),
+        })
+    }
+
+    pub fn start(&mut self) {
+        loop {
+            if self.shutdown.load(Ordering::Relaxed) {
+                return;
+            }
+            match self.listener.accept() {
+                Ok((stream, _)) => self.handle_connection(stream),
+                Err(error) if error.kind() == ErrorKind::WouldBlock => thread::sleep(ACCEPT_POLL),
+                // a failed connection only affects that client
+                Err(_) => {}
+            }
+        }
+    }
+
+    // Reads one request from the connection, answers it and closes the connection
+    fn handle_connection(&mut self, mut stream: TcpStream) {
+        if stream.set_nonblocking(false).is_err()
+            || stream.set_read_timeout(Some(REQUEST_TIMEOUT)).is_err()
+        {
+            return;
+        }
+        let (status, body) = match read_request(&mut stream) {
+            Some((method, path, request_body)) => self.route(&method, &path, &request_body),
+            None => (400, error_json("Bad request")),
+        };
+        let response = format!(
+            "HTTP/1.1 {} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            status,
+            status_reason(status),
+            body.len(),
+            body
+        );
+        let _ = stream.write_all(response.as_bytes());
+    }
+
+    // Picks the endpoint for a request, returning the status code and JSON body to answer with
+    fn route(&mut self, method: &str, path: &str, body: &str) -> (u16, String) {
+        match (method, path) {
+            ("GET", "/status") => (200, self.status_json()),
+            ("POST", "/bpm") => match parse_bpm(body) {
+                Some(bpm) => {
+                    self.set_bpm(bpm);
+                    (200, self.status_json())
+                }
+                None => (400, error_json("bpm must be a number between 20 and 500")),
+            },
+            (_, "/status" | "/bpm") => (405, error_json("Method not allowed")),
+            _ => (404, error_json("Not found")),
+        }
+    }
+
+    fn status_json(&self) -> String {
+        let ts_note = self.ts_note.load(Ordering::Relaxed);
+        let ts_value = self.ts_value.load(Ordering::Relaxed);
+        format!(
+            "{{\"bpm\":{},\"current_beat_count\":{},\"bar_count\":{},\"is_running\":{},\"ts_note\":{},\"ts_value\":{},\"time_signature\":\"{}\"}}",
+            self.bpm.load(Ordering::Relaxed),
+            self.current_beat_count.load(Ordering::Relaxed),
+            self.bar_count.load(Ordering::Relaxed),
+            self.is_running.load(Ordering::Relaxed),
+            ts_note,
+            ts_value,
+            time_signature_label(
+                ts_note,
+                ts_value,
+                self.ts_triplets.load(Ordering::Relaxed),
+                self.sub_eights.load(Ordering::Relaxed),
+                self.sub_sixteens.load(Ordering::Relaxed),
+            )
+        )
+    }
+
+    // Updates the bpm and the nanosecond delay to match, keeping the current time signature and subdivision
+    fn set_bpm(&mut self, new_bpm: f64) {
+        let (new_ns_delay, _) = compute_timing(
+            new_bpm,
+            self.ts_note.load(Ordering::Relaxed),
+            self.ts_value.load(Ordering::Relaxed),
+            self.ts_triplets.load(Ordering::Relaxed),
+            self.sub_eights.load(Ordering::Relaxed),
+            self.sub_sixteens.load(Ordering::Relaxed),
+        );
+        self.bpm.swap(new_bpm, Ordering::Relaxed);
+        self.ns_delay.swap(new_ns_delay, Ordering::Relaxed);
+    }
+}
+
+// Reads the method, path and body of a request, None if it couldn't be read or isn't HTTP
+fn read_request(stream: &mut TcpStream) -> Option<(String, String, String)> {
+    let mut request = Vec::new();
+    let mut buffer = [0; 1024];
+    // read until the end of the headers and then the rest of the body they describe
+    loop {
+        let read = stream.read(&mut buffer).ok()?;
+        if read == 0 {
+            break;
+        }
+        request.extend_from_slice(&buffer[..read]);
+        if request.len() > MAX_REQUEST_BYTES {
+            return None;
+        }
+        if let Some(header_end) = find_header_end(&request) {
+            let headers = String::from_utf8_lossy(&request[..header_end]).to_string();
+            if request.len() >= header_end + 4 + content_length(&headers) {
+                break;
+            }
+        }
+    }
+    parse_request(&String::from_utf8_lossy(&request))
+}
+
+fn find_header_end(request: &[u8]) -> Option<usize> {
+    request.windows(4).position(|window| window == b"\r\n\r\n")
+}
+
+fn content_length(headers: &str) -> usize {
+    headers
+        .lines()
+        .filter_map(|line| line.split_once(':'))
+        .find(|(name, _)| name.trim().eq_ignore_ascii_case("content-length"))
+        .and_then(|(_, value)| value.trim().parse().ok())
+        .unwrap_or(0)
+}
+
+// Splits a raw request into its method, path and body
+fn parse_request(request: &str) -> Option<(String, String, String)> {
+    let (head, body) = request.split_once("\r\n\r\n")?;
+    let mut request_line = head.lines().next()?.split_whitespace();
+    let method = request_line.next()?.to_owned();
+    let path = request_line.next()?.to_owned();
+    if !request_line.next()?.starts_with("HTTP/") {
+        return None;
+    }
+    Some((method, path, body.to_owned()))
+}
+
+// Reads the bpm from a POST /bpm body, either a bare number or a JSON object with a "bpm" field. Fractional tempos are
+// rounded to the nearest tenth like the bpm editor
+fn parse_bpm(body: &str) -> Option<f64> {
+    let body = body.trim();
+    let value = if body.starts_with('{') {
+        let (_, after_key) = body.split_once("\"bpm\"")?;
+        let (_, value) = after_key.split_once(':')?;
+        value
+            .trim_start()
+            .split(|c: char| !(c.is_ascii_digit() || c == '.'))
+            .next()?
+    } else {
+        body
+    };
+    let bpm = round_bpm(value.parse().ok()?);
+    if (20.0..=500.0).contains(&bpm) {
+        Some(bpm)
+    } else {
+        None
+    }
+}
+
+fn error_json(message: &str) -> String {
+    format!("{{\"error\":\"{}\"}}", message)
+}
+
+fn status_reason(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        400 => "Bad Request",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        _ => "",
+    }
+}
+
+// Tests ---------------------------------------------------------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // requests should be split into their method, path and body
+    #[test]
+    fn http_parse_request() {
+        assert_eq!(
+            parse_request("POST /bpm HTTP/1.1\r\nContent-Length: 3\r\n\r\n140"),
+            Some(("POST".to_owned(), "/bpm".to_owned(), "140".to_owned()))
+        );
+        assert_eq!(parse_request("GET /status\r\n\r\n"), None);
+        assert_eq!(content_length("Host: x\r\ncontent-length: 12"), 12);
+    }
+
+    // the bpm can be posted as a bare number or JSON, and must be a valid bpm
+    #[test]
+    fn http_parse_bpm() {
+        assert_eq!(parse_bpm("140\n"), Some(140.0));
+        assert_eq!(parse_bpm("{\"bpm\": 96}"), Some(96.0));
+        assert_eq!(parse_bpm("120.54"), Some(120.5));
+        assert_eq!(parse_bpm("{\"bpm\": 98.25}"), Some(98.3));
+        assert_eq!(parse_bpm("19.9"), None);
+        assert_eq!(parse_bpm("{\"bpm\":501}"), None);
+        assert_eq!(parse_bpm("fast"), None);
+    }
+}
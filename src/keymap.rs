@@ -0,0 +1,324 @@
+/// Keymap.rs maps keyboard keys to the actions used to control Ready Metronome. The default bindings can be
+/// overridden per action with a keybindings.toml file in the directory Ready Metronome is run from, ie.
+///
+/// NavigateUp = ["Up", "k"]
+/// NavigateDown = ["Down", "j"]
+/// Quit = "Q"
+use crossterm::event::KeyCode;
+use std::{collections::HashMap, fs};
+
+pub const KEYMAP_PATH: &str = "./keybindings.toml";
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Action {
+    NavigateUp,
+    NavigateDown,
+    Select,
+    Back,
+    Quit,
+    ToggleRun,
+    PageUp,
+    PageDown,
+    First,
+    Last,
+    Help,
+    BpmUp,
+    BpmDown,
+    BpmUpTen,
+    BpmDownTen,
+    Tap,
+    Undo,
+    CycleSubdivision,
+    RestartBar,
+    NarrowPanel,
+    WidenPanel,
+    ToggleTone,
+    ToggleDebug,
+    ToggleKnobMode,
+    VolumeUp,
+    VolumeDown,
+    ResetBeats,
+    EditBpm,
+    EditVolume,
+    EditTimeSignature,
+    ResetTotalBars,
+    FreezeDisplay,
+    ToggleTriplets,
+    DoubleTime,
+    HalfTime,
+    ToggleMute,
+    StopAllSound,
+}
+
+impl Action {
+    // Looks up an action by the name used for it in keybindings.toml
+    fn from_name(name: &str) -> Option<Action> {
+        match name {
+            "NavigateUp" => Some(Action::NavigateUp),
+            "NavigateDown" => Some(Action::NavigateDown),
+            "Select" => Some(Action::Select),
+            "Back" => Some(Action::Back),
+            "Quit" => Some(Action::Quit),
+            "ToggleRun" => Some(Action::ToggleRun),
+            "PageUp" => Some(Action::PageUp),
+            "PageDown" => Some(Action::PageDown),
+            "First" => Some(Action::First),
+            "Last" => Some(Action::Last),
+            "Help" => Some(Action::Help),
+            "BpmUp" => Some(Action::BpmUp),
+            "BpmDown" => Some(Action::BpmDown),
+            "BpmUpTen" => Some(Action::BpmUpTen),
+            "BpmDownTen" => Some(Action::BpmDownTen),
+            "Tap" => Some(Action::Tap),
+            "Undo" => Some(Action::Undo),
+            "CycleSubdivision" => Some(Action::CycleSubdivision),
+            "RestartBar" => Some(Action::RestartBar),
+            "NarrowPanel" => Some(Action::NarrowPanel),
+            "WidenPanel" => Some(Action::WidenPanel),
+            "ToggleTone" => Some(Action::ToggleTone),
+            "ToggleDebug" => Some(Action::ToggleDebug),
+            "ToggleKnobMode" => Some(Action::ToggleKnobMode),
+            "VolumeUp" => Some(Action::VolumeUp),
+            "VolumeDown" => Some(Action::VolumeDown),
+            "ResetBeats" => Some(Action::ResetBeats),
+            "EditBpm" => Some(Action::EditBpm),
+            "EditVolume" => Some(Action::EditVolume),
+            "EditTimeSignature" => Some(Action::EditTimeSignature),
+            "ResetTotalBars" => Some(Action::ResetTotalBars),
+            "FreezeDisplay" => Some(Action::FreezeDisplay),
+            "ToggleTriplets" => Some(Action::ToggleTriplets),
+            "DoubleTime" => Some(Action::DoubleTime),
+            "HalfTime" => Some(Action::HalfTime),
+            "ToggleMute" => Some(Action::ToggleMute),
+            "StopAllSound" => Some(Action::StopAllSound),
+            _ => None,
+        }
+    }
+}
+
+pub struct Keymap {
+    bindings: HashMap<KeyCode, Action>,
+}
+
+impl Keymap {
+    // The bindings used when there is no keybindings.toml
+    pub fn new() -> Keymap {
+        let mut bindings = HashMap::new();
+        for key in [KeyCode::Up, KeyCode::Left, KeyCode::BackTab] {
+            bindings.insert(key, Action::NavigateUp);
+        }
+        for key in [KeyCode::Down, KeyCode::Right, KeyCode::Tab] {
+            bindings.insert(key, Action::NavigateDown);
+        }
+        bindings.insert(KeyCode::Enter, Action::Select);
+        bindings.insert(KeyCode::Esc, Action::Back);
+        bindings.insert(KeyCode::Char('q'), Action::Quit);
+        bindings.insert(KeyCode::Char('t'), Action::ToggleRun);
+        bindings.insert(KeyCode::Char(' '), Action::ToggleRun);
+        bindings.insert(KeyCode::PageUp, Action::PageUp);
+        bindings.insert(KeyCode::PageDown, Action::PageDown);
+        bindings.insert(KeyCode::Home, Action::First);
+        bindings.insert(KeyCode::End, Action::Last);
+        // shortcuts
+        bindings.insert(KeyCode::Char('?'), Action::Help);
+        bindings.insert(KeyCode::Char('+'), Action::BpmUp);
+        bindings.insert(KeyCode::Char('-'), Action::BpmDown);
+        bindings.insert(KeyCode::Char(']'), Action::BpmUpTen);
+        bindings.insert(KeyCode::Char('['), Action::BpmDownTen);
+        bindings.insert(KeyCode::Char('a'), Action::Tap);
+        bindings.insert(KeyCode::Char('u'), Action::Undo);
+        bindings.insert(KeyCode::Char('s'), Action::CycleSubdivision);
+        bindings.insert(KeyCode::Char('r'), Action::RestartBar);
+        bindings.insert(KeyCode::Char('<'), Action::NarrowPanel);
+        bindings.insert(KeyCode::Char('>'), Action::WidenPanel);
+        bindings.insert(KeyCode::Char('f'), Action::ToggleTone);
+        bindings.insert(KeyCode::Char('d'), Action::ToggleDebug);
+        bindings.insert(KeyCode::Char('n'), Action::ToggleKnobMode);
+        bindings.insert(KeyCode::Char('.'), Action::VolumeUp);
+        bindings.insert(KeyCode::Char(','), Action::VolumeDown);
+        bindings.insert(KeyCode::Char('c'), Action::ResetBeats);
+        bindings.insert(KeyCode::Char('b'), Action::EditBpm);
+        bindings.insert(KeyCode::Char('v'), Action::EditVolume);
+        bindings.insert(KeyCode::Char('T'), Action::EditTimeSignature);
+        bindings.insert(KeyCode::Char('z'), Action::ResetTotalBars);
+        bindings.insert(KeyCode::Char('F'), Action::FreezeDisplay);
+        bindings.insert(KeyCode::Char('S'), Action::ToggleTriplets);
+        bindings.insert(KeyCode::Char('D'), Action::DoubleTime);
+        bindings.insert(KeyCode::Char('H'), Action::HalfTime);
+        bindings.insert(KeyCode::Char('m'), Action::ToggleMute);
+        bindings.insert(KeyCode::Char('x'), Action::StopAllSound);
+        Keymap { bindings }
+    }
+
+    // Loads the keymap from a toml file, returning the default bindings if it is absent. Any action found in the file
+    // replaces all of its default keys. Invalid entries are skipped and described in the returned warnings
+    pub fn load(path: &str) -> (Keymap, Vec<String>) {
+        let mut keymap = Keymap::new();
+        let contents = match fs::read_to_string(path) {
+            Ok(contents) => contents,
+            Err(_) => return (keymap, Vec::new()),
+        };
+        let warnings = keymap.apply(&contents);
+        (keymap, warnings)
+    }
+
+    // Applies the bindings found in the contents of a keybindings.toml file
+    fn apply(&mut self, contents: &str) -> Vec<String> {
+        let table = match contents.parse::<toml::Table>() {
+            Ok(table) => table,
+            Err(_) => {
+                return vec!["keybindings.toml could not be read, using default keys".to_owned()]
+            }
+        };
+
+        let mut warnings = Vec::new();
+        for (name, value) in table {
+            let action = match Action::from_name(&name) {
+                Some(action) => action,
+                None => {
+                    warnings.push("Unknown action '".to_owned() + &name + "' in keybindings.toml");
+                    continue;
+                }
+            };
+            let key_names = match value {
+                toml::Value::String(key_name) => vec![key_name],
+                toml::Value::Array(values) => values
+                    .into_iter()
+                    .filter_map(|value| value.as_str().map(str::to_owned))
+                    .collect(),
+                _ => Vec::new(),
+            };
+            let keys: Vec<KeyCode> = key_names
+                .iter()
+                .filter_map(|key_name| {
+                    let key = parse_key(key_name);
+                    if key.is_none() {
+                        warnings.push("Unknown key '".to_owned() + key_name + "' for " + &name);
+                    }
+                    key
+                })
+                .collect();
+            if keys.is_empty() {
+                warnings.push("No valid keys for ".to_owned() + &name + ", keeping the default");
+                continue;
+            }
+            self.bindings
+                .retain(|_, bound_action| *bound_action != action);
+            for key in keys {
+                self.bindings.insert(key, action);
+            }
+        }
+        warnings
+    }
+
+    // Returns the action bound to a key, if there is one
+    pub fn action(&self, key: KeyCode) -> Option<Action> {
+        self.bindings.get(&key).copied()
+    }
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        Keymap::new()
+    }
+}
+
+// Converts a key name from keybindings.toml into a KeyCode, single characters are used as is
+fn parse_key(name: &str) -> Option<KeyCode> {
+    let mut chars = name.chars();
+    if let (Some(value), None) = (chars.next(), chars.next()) {
+        return Some(KeyCode::Char(value));
+    }
+    match name {
+        "Up" => Some(KeyCode::Up),
+        "Down" => Some(KeyCode::Down),
+        "Left" => Some(KeyCode::Left),
+        "Right" => Some(KeyCode::Right),
+        "Enter" => Some(KeyCode::Enter),
+        "Esc" => Some(KeyCode::Esc),
+        "Tab" => Some(KeyCode::Tab),
+        "BackTab" => Some(KeyCode::BackTab),
+        "Backspace" => Some(KeyCode::Backspace),
+        "Space" => Some(KeyCode::Char(' ')),
+        "Home" => Some(KeyCode::Home),
+        "End" => Some(KeyCode::End),
+        "PageUp" => Some(KeyCode::PageUp),
+        "PageDown" => Some(KeyCode::PageDown),
+        _ => None,
+    }
+}
+
+// Tests ---------------------------------------------------------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // an action in the file should replace its default keys and leave the others alone
+    #[test]
+    fn keymap_apply_overrides_action() {
+        let mut keymap = Keymap::new();
+        let warnings = keymap.apply("NavigateDown = [\"j\", \"Down\"]\nQuit = \"Q\"");
+        assert!(warnings.is_empty());
+        assert_eq!(
+            keymap.action(KeyCode::Char('j')),
+            Some(Action::NavigateDown)
+        );
+        assert_eq!(keymap.action(KeyCode::Tab), None);
+        assert_eq!(keymap.action(KeyCode::Char('Q')), Some(Action::Quit));
+        assert_eq!(keymap.action(KeyCode::Char('q')), None);
+        assert_eq!(
+            keymap.action(KeyCode::Char('x')),
+            Some(Action::StopAllSound)
+        );
+        assert_eq!(keymap.action(KeyCode::Enter), Some(Action::Select));
+    }
+
+    // every shortcut should have a default key
+    #[test]
+    fn keymap_new_shortcuts() {
+        let keymap = Keymap::new();
+        assert_eq!(keymap.action(KeyCode::Char('?')), Some(Action::Help));
+        assert_eq!(keymap.action(KeyCode::Char('+')), Some(Action::BpmUp));
+        assert_eq!(keymap.action(KeyCode::Char('[')), Some(Action::BpmDownTen));
+        assert_eq!(keymap.action(KeyCode::Char('a')), Some(Action::Tap));
+        assert_eq!(keymap.action(KeyCode::Char('u')), Some(Action::Undo));
+        assert_eq!(
+            keymap.action(KeyCode::Char('s')),
+            Some(Action::CycleSubdivision)
+        );
+        assert_eq!(keymap.action(KeyCode::Char('r')), Some(Action::RestartBar));
+        assert_eq!(keymap.action(KeyCode::Char('<')), Some(Action::NarrowPanel));
+        assert_eq!(keymap.action(KeyCode::Char('f')), Some(Action::ToggleTone));
+        assert_eq!(keymap.action(KeyCode::Char('d')), Some(Action::ToggleDebug));
+        assert_eq!(
+            keymap.action(KeyCode::Char('n')),
+            Some(Action::ToggleKnobMode)
+        );
+        assert_eq!(keymap.action(KeyCode::Char('.')), Some(Action::VolumeUp));
+        assert_eq!(keymap.action(KeyCode::Char('c')), Some(Action::ResetBeats));
+        assert_eq!(keymap.action(KeyCode::Char('b')), Some(Action::EditBpm));
+        assert_eq!(
+            keymap.action(KeyCode::Char('z')),
+            Some(Action::ResetTotalBars)
+        );
+        assert_eq!(
+            keymap.action(KeyCode::Char('F')),
+            Some(Action::FreezeDisplay)
+        );
+        assert_eq!(
+            keymap.action(KeyCode::Char('S')),
+            Some(Action::ToggleTriplets)
+        );
+        assert_eq!(keymap.action(KeyCode::Char('D')), Some(Action::DoubleTime));
+        assert_eq!(keymap.action(KeyCode::Char('m')), Some(Action::ToggleMute));
+    }
+
+    // invalid entries should be ignored with a warning
+    #[test]
+    fn keymap_apply_invalid_entries() {
+        let mut keymap = Keymap::new();
+        let warnings = keymap.apply("Dance = \"d\"\nQuit = \"NotAKey\"");
+        assert_eq!(warnings.len(), 3);
+        assert_eq!(keymap.action(KeyCode::Char('q')), Some(Action::Quit));
+    }
+}
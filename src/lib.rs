@@ -0,0 +1,8 @@
+/// Lib.rs is the timing engine of Ready Metronome on its own, without the terminal ui. The binary in main.rs drives the
+/// ui on top of it, and other programs can depend on the crate and set up a metronome with Metronome::builder(). The
+/// setlist, tempo_map and tone modules come along since the engine plays setlists, tempo maps and the reference tone
+/// itself
+pub mod metronome;
+pub mod setlist;
+pub mod tempo_map;
+pub mod tone;
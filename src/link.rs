@@ -0,0 +1,154 @@
+/// Link.rs keeps the metronome in time with other apps on the network through Ableton Link. It runs on its own thread
+/// next to the metronome. Tempo changes from peers are copied into the bpm and ns_delay atomics, local bpm changes
+/// are proposed to the session, and the session's beat phase is shared so the metronome can start on a beat. The phase
+/// is looked up output_latency_ms ahead, so the click is heard on the session's beat rather than sent on it
+use crate::metronome::{compute_timing, round_bpm, MetronomeSettings};
+use atomic_float::AtomicF64;
+use rusty_link::{AblLink, SessionState};
+use std::{
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc,
+    },
+    thread,
+    time::Duration,
+};
+
+// How long the thread sleeps between checks while Link is off, and between updates of the session while it is on. The
+// metronome starts within LINK_POLL of the session's beat
+const IDLE_POLL: Duration = Duration::from_millis(10);
+const LINK_POLL: Duration = Duration::from_millis(1);
+
+pub struct LinkSync {
+    bpm: Arc<AtomicF64>,
+    ns_delay: Arc<AtomicU64>,
+    ts_note: Arc<AtomicU64>,
+    ts_value: Arc<AtomicU64>,
+    ts_triplets: Arc<AtomicBool>,
+    sub_eights: Arc<AtomicBool>,
+    sub_sixteens: Arc<AtomicBool>,
+    enabled: Arc<AtomicBool>,
+    peers: Arc<AtomicU64>,
+    phase: Arc<AtomicF64>,
+    output_latency_ms: Arc<AtomicU64>,
+    shutdown: Arc<AtomicBool>,
+}
+
+impl LinkSync {
+    pub fn new(settings: &MetronomeSettings) -> LinkSync {
+        LinkSync {
+            bpm: Arc::clone(&settings.bpm),
+            ns_delay: Arc::clone(&settings.ns_delay),
+            ts_note: Arc::clone(&settings.ts_note),
+            ts_value: Arc::clone(&settings.ts_value),
+            ts_triplets: Arc::clone(&settings.ts_triplets),
+            sub_eights: Arc::clone(&settings.sub_eights),
+            sub_sixteens: Arc::clone(&settings.sub_sixteens),
+            enabled: Arc::clone(&settings.link_enabled),
+            peers: Arc::clone(&settings.link_peers),
+            phase: Arc::clone(&settings.link_phase),
+            output_latency_ms: Arc::clone(&settings.output_latency_ms),
+            shutdown: Arc::clone(&settings.shutdown),
+        }
+    }
+
+    pub fn start(&mut self) {
+        // the Link session isn't joined until Link is first turned on
+        let mut link: Option<AblLink> = None;
+        let mut session_state = SessionState::new();
+        let mut link_enabled = false;
+        // the bpm both sides last agreed on, whichever side moves away from it changed the tempo
+        let mut synced_bpm = self.bpm.load(Ordering::Relaxed);
+
+        loop {
+            if self.shutdown.load(Ordering::Relaxed) {
+                if let Some(link) = &link {
+                    link.enable(false);
+                }
+                return;
+            }
+
+            let enabled = self.enabled.load(Ordering::Relaxed);
+            if enabled != link_enabled {
+                link_enabled = enabled;
+                if enabled {
+                    let link =
+                        link.get_or_insert_with(|| AblLink::new(self.bpm.load(Ordering::Relaxed)));
+                    link.enable(true);
+                    // bring the session up to date with any bpm changes made while Link was off
+                    synced_bpm = self.bpm.load(Ordering::Relaxed);
+                    link.capture_app_session_state(&mut session_state);
+                    session_state.set_tempo(synced_bpm, link.clock_micros());
+                    link.commit_app_session_state(&session_state);
+                } else {
+                    if let Some(link) = &link {
+                        link.enable(false);
+                    }
+                    self.peers.store(0, Ordering::Relaxed);
+                }
+            }
+
+            let link = match link.as_ref().filter(|_| link_enabled) {
+                Some(link) => link,
+                None => {
+                    thread::sleep(IDLE_POLL);
+                    continue;
+                }
+            };
+            link.capture_app_session_state(&mut session_state);
+            let time = link.clock_micros();
+            let local_bpm = self.bpm.load(Ordering::Relaxed);
+            let session_bpm = round_bpm(session_state.tempo()).clamp(20.0, 500.0);
+            if local_bpm != synced_bpm {
+                // the bpm was changed here, propose it to the session
+                session_state.set_tempo(local_bpm, time);
+                link.commit_app_session_state(&session_state);
+                synced_bpm = local_bpm;
+            } else if session_bpm != synced_bpm {
+                // a peer changed the tempo, follow it
+                self.set_bpm(session_bpm);
+                synced_bpm = session_bpm;
+            }
+            let heard_time = heard_time(time, self.output_latency_ms.load(Ordering::Relaxed));
+            self.phase.store(
+                session_state.phase_at_time(heard_time, 1.0),
+                Ordering::Relaxed,
+            );
+            self.peers.store(link.num_peers(), Ordering::Relaxed);
+
+            thread::sleep(LINK_POLL);
+        }
+    }
+
+    // Updates the bpm and the nanosecond delay to match, keeping the current time signature and subdivision
+    fn set_bpm(&mut self, new_bpm: f64) {
+        let (new_ns_delay, _) = compute_timing(
+            new_bpm,
+            self.ts_note.load(Ordering::Relaxed),
+            self.ts_value.load(Ordering::Relaxed),
+            self.ts_triplets.load(Ordering::Relaxed),
+            self.sub_eights.load(Ordering::Relaxed),
+            self.sub_sixteens.load(Ordering::Relaxed),
+        );
+        self.bpm.swap(new_bpm, Ordering::Relaxed);
+        self.ns_delay.swap(new_ns_delay, Ordering::Relaxed);
+    }
+}
+
+// The Link clock time (in microseconds) a sound played at time is heard, the output latency later
+fn heard_time(time: i64, output_latency_ms: u64) -> i64 {
+    time + output_latency_ms as i64 * 1000
+}
+
+// Tests ---------------------------------------------------------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // the session's phase should be read for when the click is heard, not when it is played
+    #[test]
+    fn link_heard_time() {
+        assert_eq!(heard_time(5_000_000, 0), 5_000_000);
+        assert_eq!(heard_time(5_000_000, 150), 5_150_000);
+    }
+}
@@ -4,57 +4,158 @@ use clap::Parser;
 use crossterm::{
     event::{DisableMouseCapture, EnableMouseCapture},
     execute,
-    terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
+    terminal::{
+        disable_raw_mode, enable_raw_mode, size, EnterAlternateScreen, LeaveAlternateScreen,
+    },
 };
-use ratatui::{backend::CrosstermBackend, Terminal};
-use std::{error::Error, io};
+use ratatui::{backend::CrosstermBackend, style::Color, Terminal, TerminalOptions, Viewport};
+// the engine lives in the library half of the crate, src/lib.rs, and the ui modules reach it through crate::metronome
+use readymetronome::{metronome, setlist, tempo_map, tone};
+use signal_hook::consts::SIGINT;
+use std::{error::Error, io, sync::Arc};
 
+mod accuracy;
 mod app;
+mod config;
 mod ui;
-use crate::{app::App, events::run_app, metronome::InitMetronomeSettings};
+use crate::{
+    app::App,
+    events::{run_app, run_headless},
+    metronome::{check_refresh_rate, round_bpm, InitMetronomeSettings},
+    setlist::parse_time_signature,
+};
 
+mod beat_log;
 mod event_handler;
 mod events;
+mod export;
+#[cfg(feature = "http")]
+mod http;
+mod keymap;
+mod link;
 mod menu;
-mod metronome;
+mod midi;
+mod practice_log;
+mod theme;
 
 fn main() -> Result<(), Box<dyn Error>> {
     let args = Args::parse();
-
-    // This is neccessary Ratatui boilerplate, enables Ratatui to have control over the keyboard inputs as well as mouse
-    enable_raw_mode()?;
-    execute!(io::stdout(), EnterAlternateScreen, EnableMouseCapture)?;
-
-    // This sets up Crossterm for our backend and gives it a terminal
-    let backend = CrosstermBackend::new(io::stdout());
-    let mut terminal = Terminal::new(backend)?;
+    // a bad refresh rate is reported before the terminal is taken over
+    let app_refresh_rate_ns = check_refresh_rate(args.refresh_rate.saturating_mul(1_000))
+        .map_err(|error| error.to_string())?;
+    // so are a bad starting tempo, time signature or flash color
+    let bpm = match args.bpm {
+        Some(bpm) if (20.0..=500.0).contains(&bpm) => round_bpm(bpm),
+        Some(_) => return Err("--bpm must be between 20 and 500".into()),
+        None => 120.0,
+    };
+    let (ts_note, ts_value) = match args.sig.as_deref() {
+        Some(sig) => parse_time_signature(sig).ok_or(
+            "--sig must be a time signature like 4/4 or 6/8 (up to 32 beats of 2, 4, 8, 16, 32 or 64)",
+        )?,
+        None => (4, 4),
+    };
+    let flash_color: Color = args.flash_color.parse().map_err(|_| {
+        format!(
+            "--flash-color '{}' is not a color, use a name like lightgreen or a hex code like #ff8800",
+            args.flash_color
+        )
+    })?;
 
     // Initialize the app
-    const APP_REFRESH_RATE_NS: u64 = 1_000;
     const UI_REFRESH_RATE_MS: u64 = 7;
     let init_settings: InitMetronomeSettings = InitMetronomeSettings {
-        bpm: 120,
-        ts_note: 4,
-        ts_value: 4,
+        bpm,
+        ts_note,
+        ts_value,
         volume: 100.0,
-        is_running: false,
+        is_running: args.start || args.no_ui,
         debug: args.debug,
     };
 
-    let mut app = App::new(init_settings, APP_REFRESH_RATE_NS);
+    let mut app = App::new(init_settings, app_refresh_rate_ns);
+    if let Some(device) = args.device {
+        app.set_output_device(device);
+    }
+    app.flash_color = flash_color;
+    if let Some(sound_dir) = args.sounds {
+        app.set_sound_dir(&sound_dir);
+    }
+    if args.screen_flash {
+        app.toggle_screen_flash();
+    }
+    if args.silent {
+        app.toggle_silent();
+    }
+
+    // without the ui the metronome just plays until Ctrl-C, there is no terminal to set up or restore
+    if args.no_ui {
+        // Ctrl-C asks the threads to finish so the teardown below still runs and the session is logged, a second
+        // Ctrl-C while that happens quits straight away
+        let shutdown = Arc::clone(&app.settings.shutdown);
+        signal_hook::flag::register_conditional_shutdown(SIGINT, 1, Arc::clone(&shutdown))?;
+        signal_hook::flag::register(SIGINT, shutdown)?;
+        app.init();
+        #[cfg(feature = "http")]
+        if let Some(address) = args.http {
+            app.spawn_http_thread(&address);
+        }
+        if let Some(path) = &args.beat_log {
+            app.spawn_beat_log_thread(path);
+        }
+        let res = run_headless(&mut app);
+        app.stop_metronome_thread();
+        return match res {
+            Ok(_) => Ok(()),
+            Err(err) => Err(err.to_string().into()),
+        };
+    }
+
+    // This is neccessary Ratatui boilerplate, enables Ratatui to have control over the keyboard inputs as well as mouse
+    enable_raw_mode()?;
+    // inline mode draws in the normal buffer and leaves the mouse alone, so anything printed stays in the scrollback
+    if !args.inline {
+        execute!(io::stdout(), EnterAlternateScreen, EnableMouseCapture)?;
+    }
+
+    // This sets up Crossterm for our backend and gives it a terminal
+    let backend = CrosstermBackend::new(io::stdout());
+    let mut terminal = if args.inline {
+        let (_, rows) = size()?;
+        Terminal::with_options(
+            backend,
+            TerminalOptions {
+                viewport: Viewport::Inline(rows),
+            },
+        )?
+    } else {
+        Terminal::new(backend)?
+    };
+
     app.init();
+    #[cfg(feature = "http")]
+    if let Some(address) = args.http {
+        app.spawn_http_thread(&address);
+    }
+    if let Some(path) = &args.beat_log {
+        app.spawn_beat_log_thread(path);
+    }
 
     let res = run_app(&mut terminal, &mut app, UI_REFRESH_RATE_MS);
     // This begins the clean up phase after the app quits
     // Restores the terminal to its original state after exiting the program
     disable_raw_mode()?;
 
-    // Leave the alternate screen created by ratatui
-    execute!(
-        terminal.backend_mut(),
-        LeaveAlternateScreen,
-        DisableMouseCapture
-    )?;
+    // Leave the alternate screen created by ratatui, in inline mode the last frame is kept and the prompt goes below it
+    if args.inline {
+        println!();
+    } else {
+        execute!(
+            terminal.backend_mut(),
+            LeaveAlternateScreen,
+            DisableMouseCapture
+        )?;
+    }
     terminal.show_cursor()?;
 
     // Check to see if the app errored out and print that to terminal
@@ -68,7 +169,49 @@ fn main() -> Result<(), Box<dyn Error>> {
 #[derive(Parser, Debug)]
 #[command(version, about, long_about = None)]
 struct Args {
+    /// Starting bpm (20 - 500, up to one decimal place)
+    #[arg(long)]
+    bpm: Option<f64>,
+    /// Starting time signature (ie. 4/4, 6/8 or 7/8)
+    #[arg(long)]
+    sig: Option<String>,
+    /// Start the metronome straight away instead of waiting on the main menu
+    #[arg(long)]
+    start: bool,
+    /// Play without the terminal ui until stopped with Ctrl-C, printing each bar as it starts
+    #[arg(long)]
+    no_ui: bool,
     /// Whether or not we are in debug mode
     #[arg(short, long)]
     debug: bool,
+    /// Draw the ui in the normal terminal buffer instead of the alternate screen, so output printed while it runs
+    /// stays in the scrollback. The mouse is left to the terminal
+    #[arg(long)]
+    inline: bool,
+    /// Name of the audio output device to play through, uses the default device if not found
+    #[arg(long)]
+    device: Option<String>,
+    /// Color the title bar flashes on the first beat of each bar (ie. lightgreen, yellow or #ff8800)
+    #[arg(long, default_value = "lightgreen")]
+    flash_color: String,
+    /// Flash the whole screen on every beat, for when the click can't be heard
+    #[arg(long)]
+    screen_flash: bool,
+    /// Folder to load sounds from. Defaults to an assets folder next to the executable, then
+    /// ~/.local/share/readymetronome/assets, then ./assets
+    #[arg(long)]
+    sounds: Option<String>,
+    /// Start in visual only mode, nothing is played and no audio device is needed
+    #[arg(long)]
+    silent: bool,
+    /// Append the timing of every beat to this CSV file (bar, beat, intended_ns, actual_ns, drift_ns)
+    #[arg(long)]
+    beat_log: Option<String>,
+    /// Microseconds between the metronome's timing checks (1 - 750), higher uses less CPU but ticks can land later
+    #[arg(long, default_value_t = 1)]
+    refresh_rate: u64,
+    /// Serve the metronome's state over HTTP on this address (ie. 127.0.0.1:8080)
+    #[cfg(feature = "http")]
+    #[arg(long)]
+    http: Option<String>,
 }
@@ -8,22 +8,53 @@ use ratatui::widgets::ListState;
 pub struct Menu {
     pub items: Vec<String>,
     pub state: ListState,
+    pub filter: String,
+    all_items: Vec<String>, // the unfiltered items, items holds only those matching the filter
+    last_selected: Option<usize>, // the item selected before the menu was last deselected
 }
 
 impl Menu {
     pub fn new(items: Vec<String>) -> Menu {
         Menu {
+            all_items: items.clone(),
             items,
             state: ListState::default(),
+            filter: String::new(),
+            last_selected: None,
         }
     }
-    // Resets the menu items and selects the first on the list
+    // Resets the menu items, clears the filter and selects the first on the list
     pub fn set_items(&mut self, items: Vec<String>) {
+        self.all_items = items.clone();
         self.items = items;
+        self.filter.clear();
         self.state = ListState::default();
     }
+    // Only show items containing the filter (case-insensitive) and select the first match
+    pub fn set_filter(&mut self, filter: &str) {
+        self.filter = filter.to_owned();
+        let filter = filter.to_lowercase();
+        self.items = self
+            .all_items
+            .iter()
+            .filter(|item| item.to_lowercase().contains(&filter))
+            .cloned()
+            .collect();
+        if self.items.is_empty() {
+            self.deselect();
+        } else {
+            self.select(0);
+        }
+    }
+    // Returns the currently selected item
+    pub fn selected_item(&self) -> Option<&String> {
+        self.state.selected().and_then(|i| self.items.get(i))
+    }
     // Select the next item in the list
     pub fn next(&mut self) {
+        if self.items.is_empty() {
+            return;
+        }
         let i = match self.state.selected() {
             Some(i) => {
                 if i >= self.items.len() - 1 {
@@ -38,6 +69,9 @@ impl Menu {
     }
     // Select the previous item in the list
     pub fn previous(&mut self) {
+        if self.items.is_empty() {
+            return;
+        }
         let i = match self.state.selected() {
             Some(i) => {
                 if i == 0 {
@@ -50,12 +84,134 @@ impl Menu {
         };
         self.state.select(Some(i));
     }
-    // Deselect an item
+    // Move the selection down by n items, stopping at the last item
+    pub fn page_down(&mut self, n: usize) {
+        if self.items.is_empty() {
+            return;
+        }
+        let i = match self.state.selected() {
+            Some(i) => (i + n).min(self.items.len() - 1),
+            None => 0,
+        };
+        self.state.select(Some(i));
+    }
+    // Move the selection up by n items, stopping at the first item
+    pub fn page_up(&mut self, n: usize) {
+        if self.items.is_empty() {
+            return;
+        }
+        let i = match self.state.selected() {
+            Some(i) => i.saturating_sub(n),
+            None => 0,
+        };
+        self.state.select(Some(i));
+    }
+    // Select the first item in the list
+    pub fn first(&mut self) {
+        if !self.items.is_empty() {
+            self.state.select(Some(0));
+        }
+    }
+    // Select the last item in the list
+    pub fn last(&mut self) {
+        if !self.items.is_empty() {
+            self.state.select(Some(self.items.len() - 1));
+        }
+    }
+    // Deselect an item, remembering it so the menu can be returned to later with restore
     pub fn deselect(&mut self) {
+        if let Some(i) = self.state.selected() {
+            self.last_selected = Some(i);
+        }
         self.state.select(None);
     }
+    // Select the item that was selected before the menu was last deselected, or the first item if it's gone
+    pub fn restore(&mut self) {
+        match self.last_selected {
+            Some(i) if i < self.items.len() => self.select(i),
+            _ => self.select(0),
+        }
+    }
     // Select an item by index
     pub fn select(&mut self, index: usize) {
         self.state.select(Some(index));
     }
+    // The selected item's position counting from 1 and the number of items, None when nothing is selected
+    pub fn position(&self) -> Option<(usize, usize)> {
+        match self.state.selected() {
+            Some(i) if i < self.items.len() => Some((i + 1, self.items.len())),
+            _ => None,
+        }
+    }
+}
+
+// Tests ---------------------------------------------------------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_menu() -> Menu {
+        Menu::new((0..25).map(|i| i.to_string()).collect())
+    }
+
+    // menu::page_down and menu::page_up should move by a page and clamp to the ends of the list
+    #[test]
+    fn menu_page_navigation() {
+        let mut menu = test_menu();
+        menu.select(0);
+        menu.page_down(10);
+        assert_eq!(menu.state.selected(), Some(10));
+        menu.page_down(20);
+        assert_eq!(menu.state.selected(), Some(24));
+        menu.page_up(10);
+        assert_eq!(menu.state.selected(), Some(14));
+        menu.page_up(20);
+        assert_eq!(menu.state.selected(), Some(0));
+    }
+
+    // menu::first and menu::last should jump to the ends of the list
+    #[test]
+    fn menu_first_last() {
+        let mut menu = test_menu();
+        menu.last();
+        assert_eq!(menu.state.selected(), Some(24));
+        menu.first();
+        assert_eq!(menu.state.selected(), Some(0));
+
+        let mut empty_menu = Menu::new(vec![]);
+        empty_menu.last();
+        empty_menu.page_down(10);
+        assert_eq!(empty_menu.state.selected(), None);
+    }
+
+    // menu::position should count from 1 and follow the filtered items
+    #[test]
+    fn menu_position() {
+        let mut menu = test_menu();
+        assert_eq!(menu.position(), None);
+        menu.select(2);
+        assert_eq!(menu.position(), Some((3, 25)));
+        menu.set_filter("1");
+        assert_eq!(menu.position(), Some((1, 12)));
+        menu.set_filter("none");
+        assert_eq!(menu.position(), None);
+    }
+
+    // menu::restore should return to the item selected before deselecting, or the first item if it no longer exists
+    #[test]
+    fn menu_restore() {
+        let mut menu = test_menu();
+        menu.restore();
+        assert_eq!(menu.state.selected(), Some(0));
+        menu.select(7);
+        menu.deselect();
+        menu.deselect();
+        assert_eq!(menu.state.selected(), None);
+        menu.restore();
+        assert_eq!(menu.state.selected(), Some(7));
+        menu.deselect();
+        menu.set_items(vec!["Only".to_owned()]);
+        menu.restore();
+        assert_eq!(menu.state.selected(), Some(0));
+    }
 }
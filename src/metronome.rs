@@ -1,22 +1,123 @@
 /// This file houses the Metronome code which has the audio event loop for running the click
-/// It is started on a new thread by App and also shares state with it via Arc variables
+/// It is started on a new thread by App and also shares state with it via Arc variables. Other programs can build one
+/// with Metronome::builder() to use the engine without the terminal ui
+use crate::setlist::Setlist;
+use crate::tempo_map::TempoMap;
+use crate::tone::DEFAULT_TONE_HZ;
 use atomic_float::AtomicF64;
 use color_eyre::{eyre::eyre, Report, Result};
-use rodio::source::Source;
-use rodio::{Decoder, OutputStream, OutputStreamHandle};
+use rodio::cpal::{
+    self,
+    traits::{DeviceTrait, HostTrait},
+};
+use rodio::source::{ChannelVolume, SineWave, Source};
+use rodio::{Decoder, OutputStream, OutputStreamHandle, PlayError, Sink};
 use std::{
+    collections::HashMap,
+    env,
     fs::File,
     io,
+    path::{Path, PathBuf},
     sync::{
-        atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering},
-        Arc,
+        atomic::{AtomicBool, AtomicI64, AtomicU64, AtomicUsize, Ordering},
+        mpsc::{self, Receiver, Sender},
+        Arc, Mutex,
     },
-    thread,
     time::{Duration, Instant},
 };
 
+// File extensions of the sound formats rodio is built to decode, other files in the sound folder are skipped
+pub const SOUND_EXTENSIONS: [&str; 4] = ["flac", "mp3", "ogg", "wav"];
+
+// Name of the folder sounds are loaded from unless another one is given
+const SOUND_DIR_NAME: &str = "assets";
+
+// How often the metronome tries to open an audio output again while it has none
+const AUDIO_RETRY_DELAY: Duration = Duration::from_secs(2);
+
+// How much higher the click is pitched on the final bar before stopping, when the final bar cue is on
+const FINAL_BAR_PITCH: f64 = 1.5;
+
+// How long the reference tone plays for unless it is stopped sooner, and how loud it is next to the click at the same
+// volume setting since a pure sine wave sounds much louder
+const TONE_DURATION: Duration = Duration::from_secs(10);
+const TONE_LEVEL: f64 = 0.3;
+
+// How quickly the output meter dies away after a click, it falls to about a third in this time
+const CLICK_LEVEL_DECAY: Duration = Duration::from_millis(120);
+
+// Length of the fade in applied to the start of every sound when smooth_clicks is on, short enough to keep the attack
+// crisp while stopping samples that start at full amplitude from popping
+pub const CLICK_ATTACK: Duration = Duration::from_millis(2);
+
+// How much higher the accented beats are pitched until accent_pitch_mult is changed, enough to be heard out of the box
+pub const DEFAULT_ACCENT_PITCH: f64 = 1.25;
+
+// Level the loudest sample of every sound is brought to when normalize_sounds is on, and the most a quiet sound is
+// boosted by so a near silent file isn't turned into loud noise
+const NORMALIZE_PEAK: f32 = 0.9;
+const MAX_NORMALIZE_GAIN: f32 = 8.0;
+
 pub struct Metronome {
     pub settings: MetronomeSettings,
+    silent_bar: bool, // whether the current bar was picked to be silent by mute_probability
+    fade_scale: f64,  // how far the fade in has raised the volume of the current beat (0.0 - 1.0)
+    pitch_scale: f64, // raises the pitch of the final bar's beats when the final bar cue is on
+    setlist_section: Option<usize>, // the setlist section whose settings were last loaded
+    beats_played: u64, // ticks played since the metronome started, accent_every counts through these
+    click_voice: Voice, // the main click
+    poly_voice: Voice, // the polyrhythm voice
+    tone_voice: Voice, // the reference tone
+    preview_voice: Voice, // a sound played once from the sound selection screen
+    intro_voice: Voice, // the intro played once before the first tick
+    tone_hz: f64,      // frequency of the reference tone being played
+    output_lost: bool, // a sound couldn't be played through the output stream, so it is reopened
+    on_beat: Option<BeatCallback>, // told about every tick as it plays by programs embedding the engine
+}
+
+// Called with every tick's BeatEvent, see MetronomeBuilder::on_beat
+pub type BeatCallback = Box<dyn FnMut(BeatEvent) + Send>;
+
+// Sent for every tick the metronome plays, to each receiver from MetronomeSettings::subscribe_beats. The ui flashes
+// from these and other integrations (MIDI, logging) can follow the beat the same way without polling the atomics
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct BeatEvent {
+    pub bar: u64,         // the bar being played, counting from 1
+    pub beat: u64,        // the tick within the bar, counting from 1
+    pub is_accent: bool,  // whether the tick starts an accent group or lands on accent_every
+    pub instant: Instant, // when the tick is heard, a little after it is sent when there is output latency
+    pub late: Duration, // how much later than it was due the tick was played, 0 for the first tick after starting
+}
+
+// A voice plays one sound at a time. Starting a new sound cuts off the one before it, so slow loading or a high bpm
+// can never pile up overlapping clicks
+#[derive(Default)]
+struct Voice {
+    sink: Option<Sink>,
+}
+
+impl Voice {
+    fn play(&mut self, sink: Sink) {
+        if let Some(last_sink) = self.sink.replace(sink) {
+            last_sink.stop();
+        }
+    }
+
+    fn stop(&mut self) {
+        if let Some(sink) = self.sink.take() {
+            sink.stop();
+        }
+    }
+}
+
+// How metronome_tick plays a sound, gathered from the settings by each voice as it starts one
+#[derive(Clone, Copy)]
+struct Playback {
+    volume: f64,  // the volume setting (0 - 100), already scaled by any fade in
+    gain: f32,    // normalizes the sound, 1.0 unless normalize_sounds is on
+    pitch: f64,   // the speed the sound is played at, 1.0 is as recorded
+    pan: f64,     // from -1.0 (left) through 0.0 (center) to 1.0 (right)
+    smooth: bool, // fade in over CLICK_ATTACK
 }
 
 // These settings are also shared with an instance of App to update the metronome after it has been
@@ -31,17 +132,96 @@ pub struct Metronome {
 // sub_sixteens         : subdivide the click into sixteenth notes
 // current_beat_count   : the current beat being played within the bar
 // beats_per_bar        : number of beats played by the metronome per bar (ie. 6 beats in a 4/4 triplets bar)
+// beats_per_bar_override: beats counted per bar regardless of the time signature (ie. 2 to count 4/4 in halves), 0
+//                         works it out from the time signature
+// time_sig_on_downbeat : hold time signature changes made while running until the next bar starts, rather than
+//                        changing the length of the bar being played
+// pending_time_sig     : a time signature change waiting for the next bar, see time_sig_on_downbeat
 // bar_count            : the number of bars elapsed since starting the metronome
+// total_bars           : the number of bars started across every run, it is only reset by App::reset_total_bars
+// stop_after_bars      : stop the metronome once this many bars have been played, 0 runs indefinitely
+// final_bar_cue        : play the last bar before stop_after_bars stops the metronome at a higher pitch
 // is_running           : whether or not the metronome is running
+// restart              : set by App to start the bar again from beat 1 straight away without stopping
+// muted                : silence the click while still counting beats and bars
+// downbeat_only        : only play the first beat of each bar, the other beats are silent but still counted
+// silent               : visual only mode, the audio device is closed and nothing is played but everything still counts
+// mute_probability     : chance (0.0 - 1.0) that a whole bar is silent while still counting, 0 disables it
 // volume               : volume of the metronome sound
-// sound_list           : vector of strings of selectable sounds (from the /assets folder)
+// smooth_clicks        : fade every sound in over CLICK_ATTACK so it doesn't pop as it starts
+// fade_in_bars         : number of bars the volume ramps up over after starting, 0 starts at full volume
+// output_latency_ms    : how long the output device takes to play a sound, clicks are played this early to be heard on time
+// pitch                : playback speed multiplier of every sound, raising it raises the pitch (1.0 = unchanged)
+// accent_pitch_mult    : extra playback speed multiplier for the accented beats, so the downbeat can stand out using
+//                        the same sound (1.0 = no accent, DEFAULT_ACCENT_PITCH to start with)
+// tone_playing         : set by App to play the reference tone, cleared by the metronome once it has finished
+// preview_sound        : index in the sound_list of a sound App wants played once, taken by the metronome as it plays it
+// stop_all_sound       : set by App to cut off every sound still playing or queued straight away, cleared by the
+//                        metronome once they are stopped
+// tone_hz              : frequency of the reference tone in Hz
+// sound_dir            : absolute path of the folder sounds are loaded from, see default_sound_dir
+// sound_list           : vector of strings of selectable sounds (from the sound_dir folder), rescanned by App
+// normalize_sounds     : bring every sound's loudest sample to NORMALIZE_PEAK so samples recorded at different levels
+//                        sound about as loud as each other, the volume is applied on top
+// sound_gains          : the gain that normalizes each sound, by its path. Measured by the metronome as normalizing is
+//                        turned on, or the first time it plays a sound added since
 // selected_sound       : index in the sound_list of the selected sound
+// beat_sounds          : index in the sound_list of the sound played on each beat of the bar (length = beats_per_bar)
+// subdivision_sound    : index in the sound_list of the sound played on the subdivisions between beats, ignored when
+//                        no subdivision is played
+// intro_sound          : index in the sound_list of a sound played once when the metronome starts, the first tick
+//                        waits for it to finish (None for no intro)
+// beat_enabled         : whether each tick of the bar is played (length = beats_per_bar), ticks that are off are still
+//                        counted. New ticks added to the bar are on
+// beat_offsets         : milliseconds each tick of the bar is played late (positive) or early (negative) to give the click
+//                        a human feel. Ticks missing from it are on the click, so an empty list is the mechanical click
+// accent_groups        : how the beats of the bar are grouped (ie. [2, 2, 3] for 7/8), the first beat of each group is
+//                        accented. Empty, or groups that don't add up to beats_per_bar, accent beat 1 only
+// accent_beats         : the ticks of the bar (counting from 0) that are accented instead of beat 1 or the accent groups
+//                        (ie. [3] to accent beat 4). Empty, or none of them in the bar, leaves it to accent_groups
+// accent_every         : also accent every this many ticks counting from the first tick played, however the bars fall
+//                        (ie. 3 to group 4/4 in threes), 0 disables it
+// poly_enabled         : play a secondary polyrhythm voice alongside the main click
+// poly_beats           : number of evenly spaced secondary beats played across each bar (ie. 3 for 3 against 4)
+// poly_sound           : index in the sound_list of the secondary voice's sound
+// click_pan            : where the beats are placed between the speakers, from -1.0 (left) through 0.0 (center) to 1.0
+//                        (right)
+// subdivision_pan      : where the subdivisions between the beats are placed, like click_pan
+// poly_pan             : where the secondary polyrhythm voice is placed, like click_pan
+// output_device        : name of the audio output device to play through (empty for the system default)
+// output_device_changed: set when output_device changes so the metronome reopens its output stream
+// audio_error          : why no audio output could be opened, the metronome runs silently until one opens (empty when fine)
 // tick_count           : the current tick count for the refresh rate
+// last_jitter_ns       : how late the last beat was played compared to when it was due, in nanoseconds
+// max_jitter_ns        : the latest any beat has been played since the metronome started, in nanoseconds
+// missed_beats         : beats skipped since the metronome started because a tick was played a whole beat late
+// last_tick_time       : when the main click last ticked, used to score taps in the tap accuracy mode (None when stopped)
+// beat_phase           : how far through the current beat the metronome is (0.0 - 1.0), updated every refresh so
+//                        visualisers can move smoothly between beats. 0.0 on each tick and while stopped
+// link_enabled         : join an Ableton Link session to share the tempo and beat phase with other apps
+// link_peers           : number of other apps connected to the Link session
+// link_phase           : how far through the current beat the Link session is (0.0 - 1.0), output_latency_ms ahead
+// midi_enabled         : send MIDI clock and start / stop messages so external gear follows the metronome
+// midi_port            : name of the MIDI output port clock is sent to (empty for a virtual port)
+// setlist_enabled      : play through the setlist's sections, loading each one's bpm and time signature in turn
+// setlist              : the sections played while setlist_enabled is set, loaded from setlist.toml by App
+// tempo_map_enabled    : follow the tempo map, setting the bpm of each bar from its tempo changes
+// tempo_map            : the tempo changes followed while tempo_map_enabled is set, loaded from tempo_map.csv by App
+// trainer_enabled      : whether the tempo trainer should ramp the bpm while running
+// trainer_start_bpm    : bpm the trainer starts at when the metronome is started
+// trainer_target_bpm   : bpm the trainer ramps towards and then holds
+// trainer_increment    : amount of bpm added at each trainer step, negative to slow down and 0 to hold the tempo
+// trainer_bars_per_step: number of bars played before each trainer step
+// trainer_stop_at_target: stop the metronome once a full step has been played at the target bpm
 // debug                : enable debugging mode
 // error                : used to report errors to the front end
+// error_message        : description of the error shown on the error screen
+// shutdown             : tells the metronome thread to return so its output stream is dropped
+// beat_subscribers     : senders of the BeatEvents for every tick, added by subscribe_beats. Senders whose receiver has
+//                        been dropped are removed the next time a beat is sent, so receivers can go away at any time
 //
 pub struct MetronomeSettings {
-    pub bpm: Arc<AtomicU64>,
+    pub bpm: Arc<AtomicF64>,
     pub ns_delay: Arc<AtomicU64>,
     pub ts_note: Arc<AtomicU64>,
     pub ts_value: Arc<AtomicU64>,
@@ -50,20 +230,83 @@ pub struct MetronomeSettings {
     pub sub_sixteens: Arc<AtomicBool>,
     pub current_beat_count: Arc<AtomicU64>,
     pub beats_per_bar: Arc<AtomicU64>,
+    pub beats_per_bar_override: Arc<AtomicU64>,
+    pub time_sig_on_downbeat: Arc<AtomicBool>,
+    pub pending_time_sig: Arc<Mutex<Option<TimeSignature>>>,
     pub bar_count: Arc<AtomicU64>,
+    pub total_bars: Arc<AtomicU64>,
+    pub stop_after_bars: Arc<AtomicU64>,
+    pub final_bar_cue: Arc<AtomicBool>,
     pub is_running: Arc<AtomicBool>,
+    pub restart: Arc<AtomicBool>,
+    pub muted: Arc<AtomicBool>,
+    pub downbeat_only: Arc<AtomicBool>,
+    pub silent: Arc<AtomicBool>,
+    pub mute_probability: Arc<AtomicF64>,
     pub volume: Arc<AtomicF64>,
-    pub sound_list: Vec<String>,
+    pub smooth_clicks: Arc<AtomicBool>,
+    pub fade_in_bars: Arc<AtomicU64>,
+    pub output_latency_ms: Arc<AtomicU64>,
+    pub pitch: Arc<AtomicF64>,
+    pub accent_pitch_mult: Arc<AtomicF64>,
+    pub tone_playing: Arc<AtomicBool>,
+    pub preview_sound: Arc<Mutex<Option<usize>>>,
+    pub stop_all_sound: Arc<AtomicBool>,
+    pub tone_hz: Arc<AtomicF64>,
+    pub sound_dir: Arc<Mutex<PathBuf>>,
+    pub sound_list: Arc<Mutex<Vec<String>>>,
+    pub normalize_sounds: Arc<AtomicBool>,
+    pub sound_gains: Arc<Mutex<HashMap<PathBuf, f32>>>,
     pub selected_sound: Arc<AtomicUsize>,
+    pub beat_sounds: Arc<Mutex<Vec<usize>>>,
+    pub subdivision_sound: Arc<AtomicUsize>,
+    pub intro_sound: Arc<Mutex<Option<usize>>>,
+    pub beat_enabled: Arc<Mutex<Vec<bool>>>,
+    pub beat_offsets: Arc<Mutex<Vec<i64>>>,
+    pub accent_groups: Arc<Mutex<Vec<u64>>>,
+    pub accent_beats: Arc<Mutex<Vec<u64>>>,
+    pub accent_every: Arc<AtomicU64>,
+    pub poly_enabled: Arc<AtomicBool>,
+    pub poly_beats: Arc<AtomicU64>,
+    pub poly_sound: Arc<AtomicUsize>,
+    pub click_pan: Arc<AtomicF64>,
+    pub subdivision_pan: Arc<AtomicF64>,
+    pub poly_pan: Arc<AtomicF64>,
+    pub output_device: Arc<Mutex<String>>,
+    pub output_device_changed: Arc<AtomicBool>,
+    pub audio_error: Arc<Mutex<String>>,
     pub tick_count: Arc<AtomicU64>,
+    pub last_jitter_ns: Arc<AtomicU64>,
+    pub max_jitter_ns: Arc<AtomicU64>,
+    pub missed_beats: Arc<AtomicU64>,
+    pub last_tick_time: Arc<Mutex<Option<Instant>>>,
+    pub beat_phase: Arc<AtomicF64>,
+    pub link_enabled: Arc<AtomicBool>,
+    pub link_peers: Arc<AtomicU64>,
+    pub link_phase: Arc<AtomicF64>,
+    pub midi_enabled: Arc<AtomicBool>,
+    pub midi_port: Arc<Mutex<String>>,
+    pub setlist_enabled: Arc<AtomicBool>,
+    pub setlist: Arc<Mutex<Setlist>>,
+    pub tempo_map_enabled: Arc<AtomicBool>,
+    pub tempo_map: Arc<Mutex<TempoMap>>,
+    pub trainer_enabled: Arc<AtomicBool>,
+    pub trainer_start_bpm: Arc<AtomicU64>,
+    pub trainer_target_bpm: Arc<AtomicU64>,
+    pub trainer_increment: Arc<AtomicI64>,
+    pub trainer_bars_per_step: Arc<AtomicU64>,
+    pub trainer_stop_at_target: Arc<AtomicBool>,
     pub debug: Arc<AtomicBool>,
     pub error: Arc<AtomicBool>,
+    pub error_message: Arc<Mutex<String>>,
+    pub shutdown: Arc<AtomicBool>,
+    pub beat_subscribers: Arc<Mutex<Vec<Sender<BeatEvent>>>>,
 }
 
 // This interface is used to set up the metronome without having to initialize internal variables
 #[derive(Clone, Copy)]
 pub struct InitMetronomeSettings {
-    pub bpm: u64,
+    pub bpm: f64,
     pub ts_note: u64,
     pub ts_value: u64,
     pub volume: f64,
@@ -71,7 +314,300 @@ pub struct InitMetronomeSettings {
     pub is_running: bool,
 }
 
+impl MetronomeSettings {
+    // Creates a fresh set of shared settings from the starting values, with the timing worked out to match
+    pub fn new(init: InitMetronomeSettings) -> MetronomeSettings {
+        let (ns_delay, beats_per_bar) =
+            compute_timing(init.bpm, init.ts_note, init.ts_value, false, false, false);
+        MetronomeSettings {
+            bpm: Arc::new(AtomicF64::new(init.bpm)),
+            ns_delay: Arc::new(AtomicU64::new(ns_delay)),
+            ts_note: Arc::new(AtomicU64::new(init.ts_note)),
+            ts_value: Arc::new(AtomicU64::new(init.ts_value)),
+            ts_triplets: Arc::new(AtomicBool::new(false)),
+            sub_eights: Arc::new(AtomicBool::new(false)),
+            sub_sixteens: Arc::new(AtomicBool::new(false)),
+            current_beat_count: Arc::new(AtomicU64::new(0)),
+            beats_per_bar: Arc::new(AtomicU64::new(beats_per_bar)),
+            beats_per_bar_override: Arc::new(AtomicU64::new(0)),
+            time_sig_on_downbeat: Arc::new(AtomicBool::new(false)),
+            pending_time_sig: Arc::new(Mutex::new(None)),
+            bar_count: Arc::new(AtomicU64::new(1)),
+            total_bars: Arc::new(AtomicU64::new(0)),
+            stop_after_bars: Arc::new(AtomicU64::new(0)),
+            final_bar_cue: Arc::new(AtomicBool::new(false)),
+            is_running: Arc::new(AtomicBool::new(init.is_running)),
+            restart: Arc::new(AtomicBool::new(false)),
+            muted: Arc::new(AtomicBool::new(false)),
+            downbeat_only: Arc::new(AtomicBool::new(false)),
+            silent: Arc::new(AtomicBool::new(false)),
+            mute_probability: Arc::new(AtomicF64::new(0.0)),
+            volume: Arc::new(AtomicF64::new(init.volume)),
+            smooth_clicks: Arc::new(AtomicBool::new(false)),
+            fade_in_bars: Arc::new(AtomicU64::new(0)),
+            output_latency_ms: Arc::new(AtomicU64::new(0)),
+            pitch: Arc::new(AtomicF64::new(1.0)),
+            accent_pitch_mult: Arc::new(AtomicF64::new(DEFAULT_ACCENT_PITCH)),
+            tone_playing: Arc::new(AtomicBool::new(false)),
+            preview_sound: Arc::new(Mutex::new(None)),
+            stop_all_sound: Arc::new(AtomicBool::new(false)),
+            tone_hz: Arc::new(AtomicF64::new(DEFAULT_TONE_HZ)),
+            sound_dir: Arc::new(Mutex::new(default_sound_dir())),
+            sound_list: Arc::new(Mutex::new(Vec::new())),
+            normalize_sounds: Arc::new(AtomicBool::new(false)),
+            sound_gains: Arc::new(Mutex::new(HashMap::new())),
+            selected_sound: Arc::new(AtomicUsize::new(0)),
+            beat_sounds: Arc::new(Mutex::new(Vec::new())),
+            subdivision_sound: Arc::new(AtomicUsize::new(0)),
+            intro_sound: Arc::new(Mutex::new(None)),
+            beat_enabled: Arc::new(Mutex::new(Vec::new())),
+            beat_offsets: Arc::new(Mutex::new(Vec::new())),
+            accent_groups: Arc::new(Mutex::new(Vec::new())),
+            accent_beats: Arc::new(Mutex::new(Vec::new())),
+            accent_every: Arc::new(AtomicU64::new(0)),
+            poly_enabled: Arc::new(AtomicBool::new(false)),
+            poly_beats: Arc::new(AtomicU64::new(3)),
+            poly_sound: Arc::new(AtomicUsize::new(0)),
+            click_pan: Arc::new(AtomicF64::new(0.0)),
+            subdivision_pan: Arc::new(AtomicF64::new(0.0)),
+            poly_pan: Arc::new(AtomicF64::new(0.0)),
+            output_device: Arc::new(Mutex::new(String::new())),
+            output_device_changed: Arc::new(AtomicBool::new(false)),
+            audio_error: Arc::new(Mutex::new(String::new())),
+            tick_count: Arc::new(AtomicU64::new(0)),
+            last_jitter_ns: Arc::new(AtomicU64::new(0)),
+            max_jitter_ns: Arc::new(AtomicU64::new(0)),
+            missed_beats: Arc::new(AtomicU64::new(0)),
+            last_tick_time: Arc::new(Mutex::new(None)),
+            beat_phase: Arc::new(AtomicF64::new(0.0)),
+            link_enabled: Arc::new(AtomicBool::new(false)),
+            link_peers: Arc::new(AtomicU64::new(0)),
+            link_phase: Arc::new(AtomicF64::new(0.0)),
+            midi_enabled: Arc::new(AtomicBool::new(false)),
+            midi_port: Arc::new(Mutex::new(String::new())),
+            setlist_enabled: Arc::new(AtomicBool::new(false)),
+            setlist: Arc::new(Mutex::new(Setlist::new())),
+            tempo_map_enabled: Arc::new(AtomicBool::new(false)),
+            tempo_map: Arc::new(Mutex::new(TempoMap::new())),
+            trainer_enabled: Arc::new(AtomicBool::new(false)),
+            trainer_start_bpm: Arc::new(AtomicU64::new(init.bpm.round() as u64)),
+            trainer_target_bpm: Arc::new(AtomicU64::new(160)),
+            trainer_increment: Arc::new(AtomicI64::new(5)),
+            trainer_bars_per_step: Arc::new(AtomicU64::new(4)),
+            trainer_stop_at_target: Arc::new(AtomicBool::new(false)),
+            debug: Arc::new(AtomicBool::new(init.debug)),
+            error: Arc::new(AtomicBool::new(false)),
+            error_message: Arc::new(Mutex::new(String::new())),
+            shutdown: Arc::new(AtomicBool::new(false)),
+            beat_subscribers: Arc::new(Mutex::new(Vec::new())),
+        }
+    }
+
+    // Returns a receiver that gets a BeatEvent for every tick played from now on. Dropping it is fine, the metronome
+    // just stops sending to it
+    pub fn subscribe_beats(&self) -> Receiver<BeatEvent> {
+        let (sender, receiver) = mpsc::channel();
+        self.beat_subscribers.lock().unwrap().push(sender);
+        receiver
+    }
+
+    // Sends a beat to every subscriber, forgetting the ones whose receiver has been dropped
+    pub fn send_beat(&self, event: BeatEvent) {
+        self.beat_subscribers
+            .lock()
+            .unwrap()
+            .retain(|sender| sender.send(event).is_ok());
+    }
+
+    // The time signature being played
+    pub fn time_signature(&self) -> TimeSignature {
+        TimeSignature {
+            ts_note: self.ts_note.load(Ordering::Relaxed),
+            ts_value: self.ts_value.load(Ordering::Relaxed),
+            triplets: self.ts_triplets.load(Ordering::Relaxed),
+            sub_eights: self.sub_eights.load(Ordering::Relaxed),
+            sub_sixteens: self.sub_sixteens.load(Ordering::Relaxed),
+            beats_per_bar_override: self.beats_per_bar_override.load(Ordering::Relaxed),
+        }
+    }
+
+    // The time signature the next change should start from, the one waiting for the next bar if there is one
+    pub fn next_time_signature(&self) -> TimeSignature {
+        self.pending_time_sig
+            .lock()
+            .unwrap()
+            .unwrap_or_else(|| self.time_signature())
+    }
+
+    // Changes the time signature straight away, or holds it until the next bar starts when time_sig_on_downbeat is
+    // set and the metronome is running
+    pub fn change_time_signature(&self, time_signature: TimeSignature) {
+        if self.time_sig_on_downbeat.load(Ordering::Relaxed)
+            && self.is_running.load(Ordering::Relaxed)
+        {
+            *self.pending_time_sig.lock().unwrap() = Some(time_signature);
+        } else {
+            *self.pending_time_sig.lock().unwrap() = None;
+            self.apply_time_signature(time_signature);
+        }
+    }
+
+    // Applies the time signature change waiting for the next bar, if there is one
+    pub fn apply_pending_time_signature(&self) {
+        let pending = self.pending_time_sig.lock().unwrap().take();
+        if let Some(time_signature) = pending {
+            self.apply_time_signature(time_signature);
+        }
+    }
+
+    // Sets the time signature and works out the timing for it at the current bpm. Like App::resize_beat_sounds, beats
+    // added to the bar use the selected sound and accent groups that no longer add up go back to accenting beat 1
+    fn apply_time_signature(&self, time_signature: TimeSignature) {
+        self.ts_note.swap(time_signature.ts_note, Ordering::Relaxed);
+        self.ts_value
+            .swap(time_signature.ts_value, Ordering::Relaxed);
+        self.ts_triplets
+            .swap(time_signature.triplets, Ordering::Relaxed);
+        self.sub_eights
+            .swap(time_signature.sub_eights, Ordering::Relaxed);
+        self.sub_sixteens
+            .swap(time_signature.sub_sixteens, Ordering::Relaxed);
+        self.beats_per_bar_override
+            .swap(time_signature.beats_per_bar_override, Ordering::Relaxed);
+        let (ns_delay, beats_per_bar) = compute_timing(
+            self.bpm.load(Ordering::Relaxed),
+            time_signature.ts_note,
+            time_signature.ts_value,
+            time_signature.triplets,
+            time_signature.sub_eights,
+            time_signature.sub_sixteens,
+        );
+        let beats_per_bar =
+            counted_beats_per_bar(beats_per_bar, time_signature.beats_per_bar_override);
+        self.ns_delay.swap(ns_delay, Ordering::Relaxed);
+        self.beats_per_bar.swap(beats_per_bar, Ordering::Relaxed);
+        let selected_sound = self.selected_sound.load(Ordering::Relaxed);
+        self.beat_sounds
+            .lock()
+            .unwrap()
+            .resize(beats_per_bar as usize, selected_sound);
+        self.beat_enabled
+            .lock()
+            .unwrap()
+            .resize(beats_per_bar as usize, true);
+        let mut accent_groups = self.accent_groups.lock().unwrap();
+        if accent_groups.iter().sum::<u64>() != beats_per_bar {
+            accent_groups.clear();
+        }
+    }
+}
+
+// The time signature along with the subdivision and beats per bar override, everything that sets the length of the
+// bar. They change together so a change held for the next bar is applied all at once
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TimeSignature {
+    pub ts_note: u64,
+    pub ts_value: u64,
+    pub triplets: bool,
+    pub sub_eights: bool,
+    pub sub_sixteens: bool,
+    pub beats_per_bar_override: u64,
+}
+
+// Sets up a Metronome for programs embedding the engine without the terminal ui. The Metronome is started on a
+// thread of its own and the MetronomeSettings returned alongside it control it from outside, ie.
+//
+//     let (mut metronome, settings) = Metronome::builder()
+//         .bpm(96.0)
+//         .time_signature(3, 4)
+//         .audio(false)
+//         .on_beat(|event| println!("bar {} beat {}", event.bar, event.beat))
+//         .build();
+//     thread::spawn(move || metronome.start(DEFAULT_REFRESH_RATE_NS));
+//     settings.is_running.swap(true, Ordering::Relaxed);
+//
+// Setting shutdown makes start return. Without audio the output device is never opened, like visual only mode, and
+// beats are only counted and passed to on_beat and settings.subscribe_beats() receivers
+pub struct MetronomeBuilder {
+    init_settings: InitMetronomeSettings,
+    audio: bool,
+    sounds: Vec<String>,
+    sound_dir: Option<PathBuf>,
+    on_beat: Option<BeatCallback>,
+}
+
+impl MetronomeBuilder {
+    pub fn bpm(mut self, bpm: f64) -> MetronomeBuilder {
+        self.init_settings.bpm = round_bpm(bpm);
+        self
+    }
+
+    pub fn time_signature(mut self, ts_note: u64, ts_value: u64) -> MetronomeBuilder {
+        self.init_settings.ts_note = ts_note;
+        self.init_settings.ts_value = ts_value;
+        self
+    }
+
+    pub fn volume(mut self, volume: f64) -> MetronomeBuilder {
+        self.init_settings.volume = volume;
+        self
+    }
+
+    // Sound files in the sound folder to choose from, the first is played on every beat
+    pub fn sounds(mut self, sounds: Vec<String>) -> MetronomeBuilder {
+        self.sounds = sounds;
+        self
+    }
+
+    // Folder the sounds are loaded from, relative paths are taken from the working directory
+    pub fn sound_dir(mut self, sound_dir: impl AsRef<Path>) -> MetronomeBuilder {
+        self.sound_dir = Some(absolute_path(sound_dir.as_ref()));
+        self
+    }
+
+    // Whether the metronome plays through an audio output at all, headless programs can turn it off
+    pub fn audio(mut self, audio: bool) -> MetronomeBuilder {
+        self.audio = audio;
+        self
+    }
+
+    // Called from the metronome thread with the BeatEvent of every tick as it plays
+    pub fn on_beat(mut self, on_beat: impl FnMut(BeatEvent) + Send + 'static) -> MetronomeBuilder {
+        self.on_beat = Some(Box::new(on_beat));
+        self
+    }
+
+    pub fn build(self) -> (Metronome, MetronomeSettings) {
+        let settings = MetronomeSettings::new(self.init_settings);
+        settings.silent.swap(!self.audio, Ordering::Relaxed);
+        *settings.sound_list.lock().unwrap() = self.sounds;
+        if let Some(sound_dir) = self.sound_dir {
+            *settings.sound_dir.lock().unwrap() = sound_dir;
+        }
+        let mut metronome = Metronome::new(&settings);
+        metronome.on_beat = self.on_beat;
+        (metronome, settings)
+    }
+}
+
 impl Metronome {
+    pub fn builder() -> MetronomeBuilder {
+        MetronomeBuilder {
+            init_settings: InitMetronomeSettings {
+                bpm: 120.0,
+                ts_note: 4,
+                ts_value: 4,
+                volume: 100.0,
+                debug: false,
+                is_running: false,
+            },
+            audio: true,
+            sounds: Vec::new(),
+            sound_dir: None,
+            on_beat: None,
+        }
+    }
+
     pub fn new(new_settings: &MetronomeSettings) -> Metronome {
         Metronome {
             settings: MetronomeSettings {
@@ -84,21 +620,100 @@ impl Metronome {
                 sub_sixteens: Arc::clone(&new_settings.sub_sixteens),
                 current_beat_count: Arc::clone(&new_settings.current_beat_count),
                 beats_per_bar: Arc::clone(&new_settings.beats_per_bar),
+                beats_per_bar_override: Arc::clone(&new_settings.beats_per_bar_override),
+                time_sig_on_downbeat: Arc::clone(&new_settings.time_sig_on_downbeat),
+                pending_time_sig: Arc::clone(&new_settings.pending_time_sig),
                 bar_count: Arc::clone(&new_settings.bar_count),
+                total_bars: Arc::clone(&new_settings.total_bars),
+                stop_after_bars: Arc::clone(&new_settings.stop_after_bars),
+                final_bar_cue: Arc::clone(&new_settings.final_bar_cue),
                 is_running: Arc::clone(&new_settings.is_running),
+                restart: Arc::clone(&new_settings.restart),
+                muted: Arc::clone(&new_settings.muted),
+                downbeat_only: Arc::clone(&new_settings.downbeat_only),
+                silent: Arc::clone(&new_settings.silent),
+                mute_probability: Arc::clone(&new_settings.mute_probability),
                 volume: Arc::clone(&new_settings.volume),
-                sound_list: new_settings.sound_list.clone(),
+                smooth_clicks: Arc::clone(&new_settings.smooth_clicks),
+                fade_in_bars: Arc::clone(&new_settings.fade_in_bars),
+                output_latency_ms: Arc::clone(&new_settings.output_latency_ms),
+                pitch: Arc::clone(&new_settings.pitch),
+                accent_pitch_mult: Arc::clone(&new_settings.accent_pitch_mult),
+                tone_playing: Arc::clone(&new_settings.tone_playing),
+                preview_sound: Arc::clone(&new_settings.preview_sound),
+                stop_all_sound: Arc::clone(&new_settings.stop_all_sound),
+                tone_hz: Arc::clone(&new_settings.tone_hz),
+                sound_dir: Arc::clone(&new_settings.sound_dir),
+                sound_list: Arc::clone(&new_settings.sound_list),
+                normalize_sounds: Arc::clone(&new_settings.normalize_sounds),
+                sound_gains: Arc::clone(&new_settings.sound_gains),
                 selected_sound: Arc::clone(&new_settings.selected_sound),
+                beat_sounds: Arc::clone(&new_settings.beat_sounds),
+                subdivision_sound: Arc::clone(&new_settings.subdivision_sound),
+                intro_sound: Arc::clone(&new_settings.intro_sound),
+                beat_enabled: Arc::clone(&new_settings.beat_enabled),
+                beat_offsets: Arc::clone(&new_settings.beat_offsets),
+                accent_groups: Arc::clone(&new_settings.accent_groups),
+                accent_beats: Arc::clone(&new_settings.accent_beats),
+                accent_every: Arc::clone(&new_settings.accent_every),
+                poly_enabled: Arc::clone(&new_settings.poly_enabled),
+                poly_beats: Arc::clone(&new_settings.poly_beats),
+                poly_sound: Arc::clone(&new_settings.poly_sound),
+                click_pan: Arc::clone(&new_settings.click_pan),
+                subdivision_pan: Arc::clone(&new_settings.subdivision_pan),
+                poly_pan: Arc::clone(&new_settings.poly_pan),
+                output_device: Arc::clone(&new_settings.output_device),
+                output_device_changed: Arc::clone(&new_settings.output_device_changed),
+                audio_error: Arc::clone(&new_settings.audio_error),
                 tick_count: Arc::clone(&new_settings.tick_count),
+                last_jitter_ns: Arc::clone(&new_settings.last_jitter_ns),
+                max_jitter_ns: Arc::clone(&new_settings.max_jitter_ns),
+                missed_beats: Arc::clone(&new_settings.missed_beats),
+                last_tick_time: Arc::clone(&new_settings.last_tick_time),
+                beat_phase: Arc::clone(&new_settings.beat_phase),
+                link_enabled: Arc::clone(&new_settings.link_enabled),
+                link_peers: Arc::clone(&new_settings.link_peers),
+                link_phase: Arc::clone(&new_settings.link_phase),
+                midi_enabled: Arc::clone(&new_settings.midi_enabled),
+                midi_port: Arc::clone(&new_settings.midi_port),
+                setlist_enabled: Arc::clone(&new_settings.setlist_enabled),
+                setlist: Arc::clone(&new_settings.setlist),
+                tempo_map_enabled: Arc::clone(&new_settings.tempo_map_enabled),
+                tempo_map: Arc::clone(&new_settings.tempo_map),
+                trainer_enabled: Arc::clone(&new_settings.trainer_enabled),
+                trainer_start_bpm: Arc::clone(&new_settings.trainer_start_bpm),
+                trainer_target_bpm: Arc::clone(&new_settings.trainer_target_bpm),
+                trainer_increment: Arc::clone(&new_settings.trainer_increment),
+                trainer_bars_per_step: Arc::clone(&new_settings.trainer_bars_per_step),
+                trainer_stop_at_target: Arc::clone(&new_settings.trainer_stop_at_target),
                 debug: Arc::clone(&new_settings.debug),
                 error: Arc::clone(&new_settings.error),
+                error_message: Arc::clone(&new_settings.error_message),
+                shutdown: Arc::clone(&new_settings.shutdown),
+                beat_subscribers: Arc::clone(&new_settings.beat_subscribers),
             },
+            silent_bar: false,
+            fade_scale: 1.0,
+            pitch_scale: 1.0,
+            setlist_section: None,
+            beats_played: 0,
+            click_voice: Voice::default(),
+            poly_voice: Voice::default(),
+            tone_voice: Voice::default(),
+            preview_voice: Voice::default(),
+            intro_voice: Voice::default(),
+            tone_hz: DEFAULT_TONE_HZ,
+            output_lost: false,
+            on_beat: None,
         }
     }
 
     pub fn start(&mut self, refresh_rate: u64) {
         let refresh_rate = Duration::from_nanos(refresh_rate);
-        let (_stream, stream_handle) = OutputStream::try_default().unwrap();
+        // the output stream has to be kept alive for as long as sounds are played through its handle, it is opened by
+        // the first loop unless the metronome starts in visual only mode
+        let mut output = None;
+        let mut last_output_attempt: Option<Instant> = None;
         let mut running = self.settings.is_running.load(Ordering::Relaxed);
         let mut last_refresh = Instant::now();
 
@@ -106,7 +721,80 @@ impl Metronome {
         let mut first_tick = true;
         let mut last_tick = Instant::now();
 
+        // When the intro played on starting finishes, None until the metronome starts
+        let mut intro_end: Option<Instant> = None;
+
+        // Link phase seen by the last loop, the Link session starts a new beat when the phase wraps around
+        let mut last_link_phase = 0.0;
+
+        // Last bar seen by the loop, used to detect when the tempo trainer should step
+        let mut last_bar = self.settings.bar_count.load(Ordering::Relaxed);
+
+        // Whether normalizing was on last loop, the sounds are measured here as it is turned on
+        let mut normalize_sounds = false;
+
+        // The polyrhythm voice spreads its beats evenly across the bar that began at poly_bar_start
+        let mut poly_bar_start = Instant::now();
+        let mut poly_next_beat = u64::MAX;
+
         loop {
+            // Return when the app is closing, this drops the output stream
+            if self.settings.shutdown.load(Ordering::Relaxed) {
+                return;
+            }
+            if self.settings.stop_all_sound.swap(false, Ordering::Relaxed) {
+                self.stop_all_voices();
+            }
+            // Measure the sounds on this thread once normalizing is turned on, decoding them all would stall the ui
+            if self.settings.normalize_sounds.load(Ordering::Relaxed) != normalize_sounds {
+                normalize_sounds = !normalize_sounds;
+                measure_sound_gains(&self.settings);
+            }
+            // Reopen the output stream if a new output device was selected, and keep trying every so often while
+            // there is no audio output so plugging in a device brings the sound back. An open stream is checked just
+            // as often and let go if its device was unplugged or a sound couldn't be played through it
+            let device_changed = self
+                .settings
+                .output_device_changed
+                .swap(false, Ordering::Relaxed);
+            if self.settings.silent.load(Ordering::Relaxed) {
+                // visual only mode lets go of the audio device entirely
+                if last_output_attempt.is_some() {
+                    drop(output.take());
+                    self.settings.audio_error.lock().unwrap().clear();
+                    last_output_attempt = None;
+                }
+            } else {
+                let retry_due = match last_output_attempt {
+                    Some(attempt) => attempt.elapsed() >= AUDIO_RETRY_DELAY,
+                    None => true,
+                };
+                let output_lost = std::mem::take(&mut self.output_lost)
+                    || match &output {
+                        Some((_, _, device_name)) if retry_due => {
+                            last_output_attempt = Some(Instant::now());
+                            !output_device_present(device_name)
+                        }
+                        _ => false,
+                    };
+                if output_lost {
+                    drop(output.take());
+                    self.stop_all_voices();
+                    *self.settings.audio_error.lock().unwrap() = "Lost the audio output".to_owned();
+                    last_output_attempt = Some(Instant::now());
+                }
+                if device_changed || (output.is_none() && retry_due) {
+                    // release the old device before opening the new one
+                    drop(output.take());
+                    output = self.open_output();
+                    last_output_attempt = Some(Instant::now());
+                }
+            }
+            let stream_handle = output.as_ref().map(|(_, handle, _)| handle.clone());
+            // the reference tone can be played whether or not the metronome is running
+            self.update_tone(stream_handle.as_ref());
+            self.update_preview(stream_handle.as_ref());
+
             let timeout_refresh = refresh_rate
                 .checked_sub(last_refresh.elapsed())
                 .unwrap_or(refresh_rate);
@@ -116,19 +804,120 @@ impl Metronome {
                 if self.settings.error.load(Ordering::Relaxed) {
                     return;
                 }
+                // Sounds are played the output latency ahead of the beat so they are heard on it, last_tick is when
+                // the beat is heard so it can be a little in the future
+                let latency =
+                    Duration::from_millis(self.settings.output_latency_ms.load(Ordering::Relaxed));
                 // Run the first tick if the metronome was just started
-                if first_tick {
-                    first_tick = false;
-                    self.start_tick_thread(stream_handle.clone());
-                    last_tick = Instant::now();
+                // With Link peers connected, hold the first tick until the session's next beat so it starts in phase
+                let link_phase = self.settings.link_phase.load(Ordering::Relaxed);
+                let wait_for_link = self.settings.link_enabled.load(Ordering::Relaxed)
+                    && self.settings.link_peers.load(Ordering::Relaxed) > 0
+                    && link_phase >= last_link_phase;
+                last_link_phase = link_phase;
+                if !first_tick && self.settings.restart.swap(false, Ordering::Relaxed) {
+                    // Resync by playing beat 1 of a fresh first bar now, the tempo and trainer carry on as they were
+                    self.settings.bar_count.swap(1, Ordering::Relaxed);
+                    self.settings.current_beat_count.swap(0, Ordering::Relaxed);
+                    last_bar = 1;
+                    self.beats_played = 0;
+                    self.start_tick_thread(stream_handle.clone(), Duration::ZERO);
+                    last_tick = Instant::now() + latency;
+                    *self.settings.last_tick_time.lock().unwrap() = Some(last_tick);
+                } else if first_tick {
+                    if !self.has_sounds() {
+                        // there is nothing to play, the app explains why it didn't start
+                        self.settings.is_running.swap(false, Ordering::Relaxed);
+                    } else if Instant::now()
+                        < *intro_end.get_or_insert_with(|| self.play_intro(stream_handle.as_ref()))
+                    {
+                        // the intro is still playing, beat 1 follows straight after it
+                    } else if !wait_for_link {
+                        first_tick = false;
+                        self.reset_timing_stats();
+                        self.start_trainer();
+                        self.start_tick_thread(stream_handle.clone(), Duration::ZERO);
+                        last_tick = Instant::now() + latency;
+                        *self.settings.last_tick_time.lock().unwrap() = Some(last_tick);
+                    }
+                } else {
+                    let time_since_last_tick =
+                        (Instant::now() + latency).saturating_duration_since(last_tick);
+                    let ns_delay = self.settings.ns_delay.load(Ordering::Relaxed);
+                    let delay = Duration::from_nanos(ns_delay);
+                    // the next beat is played its feel offset away from the click, last_tick stays on the click so
+                    // the offset doesn't carry on to the beats after it
+                    let offset = feel_offset_ns(
+                        beat_offset_ms(&self.settings, self.next_beat_index()),
+                        ns_delay,
+                    );
+                    let due = Duration::from_nanos(ns_delay.saturating_add_signed(offset));
+                    if time_since_last_tick >= due {
+                        if self.bars_finished() {
+                            // stopping here resets the counters below just like a manual stop
+                            self.settings.is_running.swap(false, Ordering::Relaxed);
+                        } else {
+                            let lateness = time_since_last_tick - due;
+                            self.record_timing(lateness, delay);
+                            let heard = Instant::now() + latency;
+                            last_tick = if offset >= 0 {
+                                heard - Duration::from_nanos(offset as u64)
+                            } else {
+                                heard + Duration::from_nanos(offset.unsigned_abs())
+                            };
+                            *self.settings.last_tick_time.lock().unwrap() = Some(heard);
+                            self.start_tick_thread(stream_handle.clone(), lateness);
+                        }
+                    }
+                }
+                // Follow the beat being heard, it stays at 0 until a tick played early for the latency is heard
+                let phase = if first_tick {
+                    0.0
                 } else {
-                    let time_since_last_tick = Instant::now().duration_since(last_tick);
-                    let delay =
-                        Duration::from_nanos(self.settings.ns_delay.load(Ordering::Relaxed));
-                    if time_since_last_tick >= delay {
-                        last_tick = Instant::now();
-                        self.start_tick_thread(stream_handle.clone());
+                    beat_phase(
+                        Instant::now().saturating_duration_since(last_tick),
+                        self.settings.ns_delay.load(Ordering::Relaxed),
+                    )
+                };
+                self.settings.beat_phase.swap(phase, Ordering::Relaxed);
+                // Line the polyrhythm voice back up with the main click on the first beat of every bar
+                if self.settings.current_beat_count.load(Ordering::Relaxed) == 1
+                    && poly_bar_start < last_tick
+                {
+                    poly_bar_start = last_tick;
+                    poly_next_beat = 0;
+                }
+                if self.settings.poly_enabled.load(Ordering::Relaxed) {
+                    let poly_beats = self.settings.poly_beats.load(Ordering::Relaxed).max(1);
+                    if poly_next_beat < poly_beats {
+                        let bar_ns = self.settings.ns_delay.load(Ordering::Relaxed)
+                            * self.settings.beats_per_bar.load(Ordering::Relaxed);
+                        let poly_delay = Duration::from_nanos(bar_ns * poly_next_beat / poly_beats);
+                        if (Instant::now() + latency).saturating_duration_since(poly_bar_start)
+                            >= poly_delay
+                        {
+                            poly_next_beat += 1;
+                            let poly_sound = self.settings.poly_sound.load(Ordering::Relaxed);
+                            let poly_pan = self.settings.poly_pan.load(Ordering::Relaxed);
+                            if let Some(sink) = self.play_sound(
+                                stream_handle.clone(),
+                                poly_sound,
+                                self.pitch_scale,
+                                poly_pan,
+                            ) {
+                                self.poly_voice.play(sink);
+                            }
+                        }
                     }
+                } else {
+                    // wait for the next bar when the voice is turned back on instead of catching up
+                    poly_next_beat = u64::MAX;
+                }
+                // Step the tempo trainer whenever a new bar begins
+                let current_bar = self.settings.bar_count.load(Ordering::Relaxed);
+                if current_bar != last_bar {
+                    last_bar = current_bar;
+                    self.trainer_step(current_bar);
                 }
             }
 
@@ -136,45 +925,492 @@ impl Metronome {
             if !running {
                 self.settings.bar_count.swap(1, Ordering::Relaxed);
                 self.settings.current_beat_count.swap(0, Ordering::Relaxed);
+                last_bar = 1;
+                self.beats_played = 0;
+                last_link_phase = 0.0;
                 first_tick = true;
+                // stopping during the intro cuts it off, the next start plays it from the beginning
+                intro_end = None;
+                self.intro_voice.stop();
+                self.settings.restart.swap(false, Ordering::Relaxed);
+                *self.settings.last_tick_time.lock().unwrap() = None;
+                self.settings.beat_phase.swap(0.0, Ordering::Relaxed);
             }
             // We always sleep for the tick duration regardless if the metronome is running
             spin_sleep::sleep(timeout_refresh);
 
             // Perform debug functionality
-            if self.settings.debug.load(Ordering::Relaxed) && last_refresh.elapsed() >= refresh_rate
-            {
-                let current_tick_count = self.settings.tick_count.load(Ordering::Relaxed);
-                let result = current_tick_count.checked_add(1).unwrap_or(0);
-                self.settings.tick_count.swap(result, Ordering::Relaxed);
+            // Every refresh that went by is counted, even when the loop was held up for several, and last_refresh
+            // moves on by whole refreshes so the count doesn't drift behind the clock. Outside debug mode the count
+            // starts from now so turning it on doesn't count the time it was off
+            if self.settings.debug.load(Ordering::Relaxed) {
+                let (ticks, counted) = refresh_ticks(last_refresh.elapsed(), refresh_rate);
+                if ticks > 0 {
+                    let current_tick_count = self.settings.tick_count.load(Ordering::Relaxed);
+                    self.settings
+                        .tick_count
+                        .swap(current_tick_count.wrapping_add(ticks), Ordering::Relaxed);
+                    last_refresh += counted;
+                }
+            } else {
                 last_refresh = Instant::now();
             }
         }
     }
 
-    // Load the tick function into a new thread for execution (that way this isn't tied to bpm anymore)
-    fn start_tick_thread(&mut self, stream_handle: OutputStreamHandle) {
-        let selected_sound_name =
-            self.settings.sound_list[self.settings.selected_sound.load(Ordering::Relaxed)].clone();
-        let volume = self.settings.volume.load(Ordering::Relaxed);
-        let error = self.settings.error.clone();
-        let handler = thread::spawn(move || {
-            match metronome_tick(stream_handle, selected_sound_name, volume) {
-                Ok(_) => {}
-                Err(_) => {
-                    error.swap(true, Ordering::Relaxed);
-                }
+    // Stops every voice, clearing whatever each one still had queued
+    fn stop_all_voices(&mut self) {
+        self.click_voice.stop();
+        self.poly_voice.stop();
+        self.tone_voice.stop();
+        self.preview_voice.stop();
+        self.intro_voice.stop();
+    }
+
+    // Plays the sound App asked to preview once through the same path as a tick, at the current volume and pitch. Like
+    // the reference tone it plays whether or not the metronome is running, and the beat and bar counts are left alone
+    fn update_preview(&mut self, stream_handle: Option<&OutputStreamHandle>) {
+        let sound_index = match self.settings.preview_sound.lock().unwrap().take() {
+            Some(sound_index) => sound_index,
+            None => return,
+        };
+        let stream_handle = match stream_handle {
+            Some(stream_handle) if !self.settings.silent.load(Ordering::Relaxed) => stream_handle,
+            _ => return,
+        };
+        let sound_name = match self.settings.sound_list.lock().unwrap().get(sound_index) {
+            Some(sound_name) => sound_name.clone(),
+            None => return,
+        };
+        let sound_dir = self.settings.sound_dir.lock().unwrap().clone();
+        let playback = Playback {
+            volume: self.settings.volume.load(Ordering::Relaxed),
+            gain: sound_gain(&self.settings, &sound_name),
+            pitch: self.settings.pitch.load(Ordering::Relaxed),
+            pan: 0.0,
+            smooth: self.settings.smooth_clicks.load(Ordering::Relaxed),
+        };
+        match metronome_tick(stream_handle, &sound_dir, &sound_name, playback) {
+            Ok(sink) => self.preview_voice.play(sink),
+            Err(report) => self.sound_error(report),
+        }
+    }
+
+    // Plays the intro sound once as the metronome starts and works out when it will finish, the first tick waits until
+    // then. The wait is kept while muted or in visual only mode so the count in still lines up, without an intro the
+    // first tick is straight away
+    fn play_intro(&mut self, stream_handle: Option<&OutputStreamHandle>) -> Instant {
+        let started = Instant::now();
+        let sound_index = match *self.settings.intro_sound.lock().unwrap() {
+            Some(sound_index) => sound_index,
+            None => return started,
+        };
+        let sound_name = match self.settings.sound_list.lock().unwrap().get(sound_index) {
+            Some(sound_name) => sound_name.clone(),
+            None => return started,
+        };
+        let sound_dir = self.settings.sound_dir.lock().unwrap().clone();
+        let pitch = self.settings.pitch.load(Ordering::Relaxed);
+        let duration = match sound_duration(&sound_dir, &sound_name) {
+            // playing faster raises the pitch and shortens the sound
+            Ok(duration) => duration.div_f64(pitch),
+            Err(report) => {
+                *self.settings.error_message.lock().unwrap() = report.to_string();
+                self.settings.error.swap(true, Ordering::Relaxed);
+                return started;
+            }
+        };
+        let audible = !self.settings.silent.load(Ordering::Relaxed)
+            && !self.settings.muted.load(Ordering::Relaxed);
+        if let Some(stream_handle) = stream_handle.filter(|_| audible) {
+            let playback = Playback {
+                volume: self.settings.volume.load(Ordering::Relaxed),
+                gain: sound_gain(&self.settings, &sound_name),
+                pitch,
+                pan: 0.0,
+                smooth: self.settings.smooth_clicks.load(Ordering::Relaxed),
+            };
+            match metronome_tick(stream_handle, &sound_dir, &sound_name, playback) {
+                Ok(sink) => self.intro_voice.play(sink),
+                Err(report) => self.sound_error(report),
             }
+        }
+        started + duration
+    }
+
+    // Starts the reference tone when App asks for it, and stops it when asked or once it has finished playing. A new
+    // frequency starts the tone again
+    fn update_tone(&mut self, stream_handle: Option<&OutputStreamHandle>) {
+        if !self.settings.tone_playing.load(Ordering::Relaxed) {
+            self.tone_voice.stop();
+            return;
+        }
+        let tone_hz = self.settings.tone_hz.load(Ordering::Relaxed);
+        if let Some(sink) = &self.tone_voice.sink {
+            if sink.empty() {
+                self.tone_voice.stop();
+                self.settings.tone_playing.swap(false, Ordering::Relaxed);
+                return;
+            }
+            if tone_hz == self.tone_hz {
+                return;
+            }
+        }
+        // without an audio output there is nothing to play the tone through
+        let tone = stream_handle.map(|stream_handle| {
+            reference_tone(
+                stream_handle,
+                tone_hz,
+                self.settings.volume.load(Ordering::Relaxed),
+            )
         });
-        // close the thread to prevent multiples from spawning
-        let _ = handler.join();
+        match tone {
+            Some(Ok(sink)) => {
+                self.tone_voice.play(sink);
+                self.tone_hz = tone_hz;
+            }
+            other => {
+                if let Some(Err(report)) = other {
+                    self.sound_error(report);
+                }
+                self.tone_voice.stop();
+                self.settings.tone_playing.swap(false, Ordering::Relaxed);
+            }
+        }
+    }
+
+    // Opens the selected output device. Without one the metronome keeps counting silently and the reason is left in
+    // audio_error for the ui to show
+    fn open_output(&mut self) -> Option<(OutputStream, OutputStreamHandle, String)> {
+        let device_name = self.settings.output_device.lock().unwrap().clone();
+        match open_output_stream(&device_name) {
+            Ok(output) => {
+                self.settings.audio_error.lock().unwrap().clear();
+                Some(output)
+            }
+            Err(report) => {
+                *self.settings.audio_error.lock().unwrap() = report.to_string();
+                None
+            }
+        }
+    }
+
+    // The tick of the bar (counting from 0) that will play next, current_beat_count holds the one that played last
+    fn next_beat_index(&self) -> usize {
+        let current_beat_count = self.settings.current_beat_count.load(Ordering::Relaxed);
+        if current_beat_count >= self.settings.beats_per_bar.load(Ordering::Relaxed) {
+            0
+        } else {
+            current_beat_count as usize
+        }
+    }
+
+    // Plays the next tick on the click voice from the metronome's own thread, sending its BeatEvent and counting it.
+    // Starting the sound returns straight away, so the loop's timing isn't held up while it plays
+    fn start_tick_thread(&mut self, stream_handle: Option<OutputStreamHandle>, late: Duration) {
+        // current_beat_count still holds the previous beat here, so work out which beat is about to play
+        let current_beat_count = self.settings.current_beat_count.load(Ordering::Relaxed);
+        // a time signature change held for the next bar is applied just before its first beat
+        if current_beat_count == 0
+            || current_beat_count >= self.settings.beats_per_bar.load(Ordering::Relaxed)
+        {
+            self.settings.apply_pending_time_signature();
+        }
+        let beats_per_bar = self.settings.beats_per_bar.load(Ordering::Relaxed);
+        let bar_count = self.settings.bar_count.load(Ordering::Relaxed);
+        let (bar, beat_index) = if current_beat_count >= beats_per_bar {
+            (bar_count + 1, 0)
+        } else {
+            (bar_count, current_beat_count)
+        };
+        let accented = is_accented(
+            beat_index,
+            &self.settings.accent_beats.lock().unwrap(),
+            &self.settings.accent_groups.lock().unwrap(),
+            beats_per_bar,
+        ) || accents_every(
+            self.beats_played,
+            self.settings.accent_every.load(Ordering::Relaxed),
+        );
+        self.beats_played += 1;
+        let event = BeatEvent {
+            bar,
+            beat: beat_index + 1,
+            is_accent: accented,
+            instant: Instant::now()
+                + Duration::from_millis(self.settings.output_latency_ms.load(Ordering::Relaxed)),
+            late,
+        };
+        self.settings.send_beat(event);
+        if let Some(on_beat) = self.on_beat.as_mut() {
+            on_beat(event);
+        }
+        self.fade_scale = fade_in_scale(
+            bar,
+            beat_index,
+            beats_per_bar,
+            self.settings.fade_in_bars.load(Ordering::Relaxed),
+        );
+        // Warn that the session is ending by pitching up the last bar's beats
+        let final_bar = is_final_bar(bar, self.settings.stop_after_bars.load(Ordering::Relaxed));
+        self.pitch_scale = if final_bar && self.settings.final_bar_cue.load(Ordering::Relaxed) {
+            FINAL_BAR_PITCH
+        } else {
+            1.0
+        };
+        // Roll once at the start of every bar to decide whether the whole bar is silent
+        if beat_index == 0 {
+            let mute_probability = self.settings.mute_probability.load(Ordering::Relaxed);
+            self.silent_bar = mute_probability > 0.0 && rand::random::<f64>() < mute_probability;
+        }
+        // in downbeat only mode the rest of the bar is still counted, just not played, and so are beats turned off
+        if (beat_index == 0 || !self.settings.downbeat_only.load(Ordering::Relaxed))
+            && beat_plays(&self.settings, beat_index as usize)
+        {
+            let beat_sound = get_beat_sound(&self.settings, beat_index as usize);
+            let beat_pan = get_beat_pan(&self.settings, beat_index as usize);
+            let pitch_scale = if accented {
+                self.pitch_scale * self.settings.accent_pitch_mult.load(Ordering::Relaxed)
+            } else {
+                self.pitch_scale
+            };
+            if let Some(sink) = self.play_sound(stream_handle, beat_sound, pitch_scale, beat_pan) {
+                self.click_voice.play(sink);
+            }
+        }
         self.beat_count();
+        // the first beat of a bar is played with the last section's settings, which also set the gap before it
+        if beat_index == 0 {
+            self.setlist_step(bar);
+            self.tempo_map_step(bar);
+        }
+    }
+
+    // Sets the bpm of a bar from the tempo map, bars before its first change keep the current tempo
+    fn tempo_map_step(&mut self, bar: u64) {
+        if !self.settings.tempo_map_enabled.load(Ordering::Relaxed) {
+            return;
+        }
+        let bpm = self.settings.tempo_map.lock().unwrap().bpm_at(bar);
+        if let Some(bpm) = bpm {
+            self.set_bpm(round_bpm(bpm));
+        }
+    }
+
+    // Loads the settings of the setlist section a bar falls in when it differs from the section already loaded
+    fn setlist_step(&mut self, bar: u64) {
+        if !self.settings.setlist_enabled.load(Ordering::Relaxed) {
+            self.setlist_section = None;
+            return;
+        }
+        let setlist = self.settings.setlist.lock().unwrap().clone();
+        let index = match setlist.section_for_bar(bar) {
+            Some((index, _)) => index,
+            None => return,
+        };
+        // the first bar always loads its section so starting again picks up the setlist's tempo
+        if bar != 1 && self.setlist_section == Some(index) {
+            return;
+        }
+        self.setlist_section = Some(index);
+        let section = &setlist.sections[index];
+        self.settings
+            .bpm
+            .swap(section.bpm as f64, Ordering::Relaxed);
+        self.settings
+            .ts_note
+            .swap(section.ts_note, Ordering::Relaxed);
+        self.settings
+            .ts_value
+            .swap(section.ts_value, Ordering::Relaxed);
+        let (ns_delay, beats_per_bar) = compute_timing(
+            section.bpm as f64,
+            section.ts_note,
+            section.ts_value,
+            self.settings.ts_triplets.load(Ordering::Relaxed),
+            self.settings.sub_eights.load(Ordering::Relaxed),
+            self.settings.sub_sixteens.load(Ordering::Relaxed),
+        );
+        let beats_per_bar = counted_beats_per_bar(
+            beats_per_bar,
+            self.settings.beats_per_bar_override.load(Ordering::Relaxed),
+        );
+        self.settings.ns_delay.swap(ns_delay, Ordering::Relaxed);
+        self.settings
+            .beats_per_bar
+            .swap(beats_per_bar, Ordering::Relaxed);
+        // like App::resize_beat_sounds, beats added by the new time signature use the selected sound and are played
+        let selected_sound = self.settings.selected_sound.load(Ordering::Relaxed);
+        self.settings
+            .beat_sounds
+            .lock()
+            .unwrap()
+            .resize(beats_per_bar as usize, selected_sound);
+        self.settings
+            .beat_enabled
+            .lock()
+            .unwrap()
+            .resize(beats_per_bar as usize, true);
+    }
+
+    // Starts playing a sound from the sound_list, returning the sink it plays in so the voice can cut it off later. The
+    // pitch_scale raises the pitch setting for accents and cues, and pan places the voice between the speakers. Nothing
+    // is played if the metronome is muted, in a silent bar, in visual only mode or has no audio output
+    fn play_sound(
+        &mut self,
+        stream_handle: Option<OutputStreamHandle>,
+        sound_index: usize,
+        pitch_scale: f64,
+        pan: f64,
+    ) -> Option<Sink> {
+        if self.settings.muted.load(Ordering::Relaxed)
+            || self.settings.silent.load(Ordering::Relaxed)
+            || self.silent_bar
+        {
+            return None;
+        }
+        let stream_handle = stream_handle?;
+        // the list may have been rescanned since the index was picked, a sound that's gone plays the first one
+        let selected_sound_name = {
+            let sound_list = self.settings.sound_list.lock().unwrap();
+            sound_list.get(sound_index).or(sound_list.first())?.clone()
+        };
+        let playback = Playback {
+            volume: self.settings.volume.load(Ordering::Relaxed) * self.fade_scale,
+            gain: sound_gain(&self.settings, &selected_sound_name),
+            pitch: self.settings.pitch.load(Ordering::Relaxed) * pitch_scale,
+            pan,
+            smooth: self.settings.smooth_clicks.load(Ordering::Relaxed),
+        };
+        let sound_dir = self.settings.sound_dir.lock().unwrap().clone();
+        match metronome_tick(&stream_handle, &sound_dir, &selected_sound_name, playback) {
+            Ok(sink) => Some(sink),
+            Err(report) => {
+                self.sound_error(report);
+                None
+            }
+        }
+    }
+
+    // Reports why a sound couldn't be played. A sink that can't be made means the output stream has gone, so it is
+    // reopened by the next loop instead of being shown as an error
+    fn sound_error(&mut self, report: Report) {
+        if report.downcast_ref::<PlayError>().is_some() {
+            self.output_lost = true;
+        } else {
+            *self.settings.error_message.lock().unwrap() = report.to_string();
+            self.settings.error.swap(true, Ordering::Relaxed);
+        }
+    }
+
+    // Records how late a beat was played for the debug panel
+    fn record_timing(&mut self, lateness: Duration, delay: Duration) {
+        let lateness_ns = lateness.as_nanos() as u64;
+        self.settings
+            .last_jitter_ns
+            .swap(lateness_ns, Ordering::Relaxed);
+        self.settings
+            .max_jitter_ns
+            .fetch_max(lateness_ns, Ordering::Relaxed);
+        self.settings
+            .missed_beats
+            .fetch_add(missed_beats(lateness, delay), Ordering::Relaxed);
+    }
+
+    fn reset_timing_stats(&mut self) {
+        self.settings.last_jitter_ns.swap(0, Ordering::Relaxed);
+        self.settings.max_jitter_ns.swap(0, Ordering::Relaxed);
+        self.settings.missed_beats.swap(0, Ordering::Relaxed);
+    }
+
+    // Whether there is a sound to play, visual only mode doesn't need one
+    fn has_sounds(&self) -> bool {
+        self.settings.silent.load(Ordering::Relaxed)
+            || !self.settings.sound_list.lock().unwrap().is_empty()
+    }
+
+    // Whether the next tick would start a bar past stop_after_bars
+    // or past the end of a setlist that doesn't loop
+    fn bars_finished(&self) -> bool {
+        let bar_count = self.settings.bar_count.load(Ordering::Relaxed);
+        if self.settings.current_beat_count.load(Ordering::Relaxed)
+            < self.settings.beats_per_bar.load(Ordering::Relaxed)
+        {
+            return false;
+        }
+        let stop_after_bars = self.settings.stop_after_bars.load(Ordering::Relaxed);
+        let setlist_finished = self.settings.setlist_enabled.load(Ordering::Relaxed) && {
+            let setlist = self.settings.setlist.lock().unwrap();
+            !setlist.sections.is_empty() && setlist.section_for_bar(bar_count + 1).is_none()
+        };
+        (stop_after_bars != 0 && bar_count >= stop_after_bars) || setlist_finished
+    }
+
+    // Resets the bpm to the trainer's starting bpm when the metronome is started
+    fn start_trainer(&mut self) {
+        if self.settings.trainer_enabled.load(Ordering::Relaxed) {
+            let start_bpm = self.settings.trainer_start_bpm.load(Ordering::Relaxed);
+            self.set_bpm(start_bpm as f64);
+        }
+    }
+
+    // Moves the bpm by the trainer increment every trainer_bars_per_step bars until the target is reached. Once at
+    // the target the bpm holds steady, or the metronome stops if trainer_stop_at_target is set
+    fn trainer_step(&mut self, bar: u64) {
+        if !self.settings.trainer_enabled.load(Ordering::Relaxed) {
+            return;
+        }
+        let bars_per_step = self
+            .settings
+            .trainer_bars_per_step
+            .load(Ordering::Relaxed)
+            .max(1);
+        if bar <= 1 || (bar - 1) % bars_per_step != 0 {
+            return;
+        }
+        // the trainer only moves in whole bpm from its whole starting bpm
+        match trainer_next_bpm(
+            self.settings.bpm.load(Ordering::Relaxed).round() as u64,
+            self.settings.trainer_target_bpm.load(Ordering::Relaxed),
+            self.settings.trainer_increment.load(Ordering::Relaxed),
+        ) {
+            Some(new_bpm) => self.set_bpm(new_bpm as f64),
+            None => {
+                if self.settings.trainer_stop_at_target.load(Ordering::Relaxed) {
+                    self.settings.is_running.swap(false, Ordering::Relaxed);
+                }
+            }
+        }
     }
 
-    // Counts the number of beats and updates bar_count
+    // Updates the bpm and the nanosecond delay to match, keeping the current time signature and subdivision
+    fn set_bpm(&mut self, new_bpm: f64) {
+        let old_bpm = self.settings.bpm.load(Ordering::Relaxed);
+        if new_bpm <= 0.0 || new_bpm == old_bpm {
+            return;
+        }
+        let (new_ns_delay, _) = compute_timing(
+            new_bpm,
+            self.settings.ts_note.load(Ordering::Relaxed),
+            self.settings.ts_value.load(Ordering::Relaxed),
+            self.settings.ts_triplets.load(Ordering::Relaxed),
+            self.settings.sub_eights.load(Ordering::Relaxed),
+            self.settings.sub_sixteens.load(Ordering::Relaxed),
+        );
+        self.settings.bpm.swap(new_bpm, Ordering::Relaxed);
+        self.settings.ns_delay.swap(new_ns_delay, Ordering::Relaxed);
+    }
+
+    // Counts the number of beats and updates bar_count and total_bars
     fn beat_count(&mut self) {
         let mut current_beat_count = self.settings.current_beat_count.load(Ordering::Relaxed);
-        if current_beat_count == self.settings.beats_per_bar.load(Ordering::Relaxed) {
+        let beats_per_bar = self.settings.beats_per_bar.load(Ordering::Relaxed);
+        // the first beat of a run starts bar 1, beat 0 isn't counted as a bar of its own
+        if current_beat_count == 0 || current_beat_count >= beats_per_bar {
+            self.settings.total_bars.fetch_add(1, Ordering::Relaxed);
+        }
+        // the bar may have just been shortened, so anything past the end starts a new bar
+        if current_beat_count >= beats_per_bar {
             self.settings.current_beat_count.swap(1, Ordering::Relaxed);
             let new_bar_count = self.settings.bar_count.load(Ordering::Relaxed) + 1;
             self.settings
@@ -189,22 +1425,1281 @@ impl Metronome {
     }
 }
 
+// Returns the sound to play on a tick of the bar (counting from 0). Subdivisions between the beats play the
+// subdivision sound, beats play theirs from the sound map, falling back to the selected sound
+pub fn get_beat_sound(settings: &MetronomeSettings, beat_index: usize) -> usize {
+    let beats_per_bar = settings.beats_per_bar.load(Ordering::Relaxed);
+    let ts_note = settings.ts_note.load(Ordering::Relaxed);
+    if !is_main_beat(beat_index as u64, beats_per_bar, ts_note) {
+        return settings.subdivision_sound.load(Ordering::Relaxed);
+    }
+    match settings.beat_sounds.lock().unwrap().get(beat_index) {
+        Some(sound) => *sound,
+        None => settings.selected_sound.load(Ordering::Relaxed),
+    }
+}
+
+// Returns where a tick of the bar (counting from 0) is panned, subdivisions between the beats follow the subdivision
+// pan like they do the subdivision sound
+pub fn get_beat_pan(settings: &MetronomeSettings, beat_index: usize) -> f64 {
+    let beats_per_bar = settings.beats_per_bar.load(Ordering::Relaxed);
+    let ts_note = settings.ts_note.load(Ordering::Relaxed);
+    if is_main_beat(beat_index as u64, beats_per_bar, ts_note) {
+        settings.click_pan.load(Ordering::Relaxed)
+    } else {
+        settings.subdivision_pan.load(Ordering::Relaxed)
+    }
+}
+
+// The gains for the left and right channels at a pan from -1.0 (left) to 1.0 (right). The pan law is equal power, so
+// a sound keeps the same loudness wherever it is placed
+pub fn pan_gains(pan: f64) -> (f32, f32) {
+    let angle = (pan.clamp(-1.0, 1.0) + 1.0) * std::f64::consts::FRAC_PI_4;
+    (angle.cos() as f32, angle.sin() as f32)
+}
+
+// Whether a tick of the bar (counting from 0) is played, ticks missing from beat_enabled are played
+pub fn beat_plays(settings: &MetronomeSettings, beat_index: usize) -> bool {
+    settings
+        .beat_enabled
+        .lock()
+        .unwrap()
+        .get(beat_index)
+        .copied()
+        .unwrap_or(true)
+}
+
+// How many milliseconds a tick of the bar (counting from 0) is played away from the click, ticks missing from
+// beat_offsets are on the click
+pub fn beat_offset_ms(settings: &MetronomeSettings, beat_index: usize) -> i64 {
+    settings
+        .beat_offsets
+        .lock()
+        .unwrap()
+        .get(beat_index)
+        .copied()
+        .unwrap_or(0)
+}
+
+// Turns a feel offset into nanoseconds, kept within half the gap between ticks so a beat can never be played before
+// the one in front of it at fast tempos
+pub fn feel_offset_ns(offset_ms: i64, ns_delay: u64) -> i64 {
+    let limit = (ns_delay / 2) as i64;
+    offset_ms.saturating_mul(1_000_000).clamp(-limit, limit)
+}
+
+// Rounds a bpm to the nearest tenth, the finest tempo the metronome is set to. Keeping bpm to tenths lets recordings
+// at tempos like 120.5 be matched while still showing cleanly in the menus
+pub fn round_bpm(bpm: f64) -> f64 {
+    (bpm * 10.0).round() / 10.0
+}
+
+// Works out the nanosecond delay between ticks and the number of ticks per bar from the bpm, time signature
+// (ts_note / ts_value) and subdivision. Everything that changes the tempo goes through this so they all agree
+pub fn compute_timing(
+    bpm: f64,
+    ts_note: u64,
+    ts_value: u64,
+    triplets: bool,
+    sub_eights: bool,
+    sub_sixteens: bool,
+) -> (u64, u64) {
+    // length of a quarter note
+    let mut ns_delay = (60_000_000_000.0_f64 / bpm.max(1.0)).round() as u64;
+
+    // Take the quarter note delay and divide it based on the value note in the time signature
+    // Handle triplet meters like 12/8
+    if ts_value == 8 {
+        ns_delay = (ns_delay as f64 / 3_f64).round() as u64;
+    } else if ts_value != 4 {
+        ns_delay = match ts_value {
+            64 => (ns_delay as f64 / 16_f64).round() as u64,
+            32 => (ns_delay as f64 / 8_f64).round() as u64,
+            16 => (ns_delay as f64 / 4_f64).round() as u64,
+            // cut time counts half notes
+            2 => ns_delay * 2,
+            _ => ns_delay,
+        }
+    }
+    // Calculate 8ths or 16ths subdivision in 4/4
+    if ts_value == 4 {
+        if sub_eights {
+            ns_delay = (ns_delay as f64 / 2_f64).round() as u64;
+        } else if sub_sixteens {
+            ns_delay = (ns_delay as f64 / 4_f64).round() as u64;
+        }
+        // This was helpful in thinking about triplet calculation:
+        // https://math.stackexchange.com/questions/2646908/calculating-delay-time-in-milliseconds
+        if triplets {
+            ns_delay = (ns_delay as f64 / 3_f64 * 2_f64).round() as u64;
+        }
+    }
+
+    // Number of metronome beats per bar
+    let mut beats_per_bar = ts_note;
+    if triplets {
+        beats_per_bar = (beats_per_bar as f64 * 1.5_f64).round() as u64;
+    }
+    if sub_eights {
+        beats_per_bar *= 2;
+    } else if sub_sixteens {
+        beats_per_bar *= 4;
+    }
+
+    (ns_delay, beats_per_bar)
+}
+
+// The refresh rate the metronome loop checks the time at unless --refresh-rate picks another, in nanoseconds
+pub const DEFAULT_REFRESH_RATE_NS: u64 = 1_000;
+
+// The shortest beat interval any tempo and time signature can ask for, 64th notes at 500 bpm
+pub fn shortest_ns_delay() -> u64 {
+    compute_timing(500.0, 1, 64, false, false, false).0
+}
+
+// Checks a refresh rate in nanoseconds leaves the loop plenty of checks per beat. It has to be no more than a tenth of
+// the shortest beat interval, or ticks at the fastest tempos could land noticeably late
+pub fn check_refresh_rate(refresh_rate: u64) -> Result<u64, Report> {
+    let max_refresh_rate = shortest_ns_delay() / 10;
+    if (DEFAULT_REFRESH_RATE_NS..=max_refresh_rate).contains(&refresh_rate) {
+        Ok(refresh_rate)
+    } else {
+        Err(eyre!(
+            "The refresh rate must be between {} and {} microseconds",
+            DEFAULT_REFRESH_RATE_NS / 1_000,
+            max_refresh_rate / 1_000
+        ))
+    }
+}
+
+// The fewest refresh checks a tick can get before timing_warning warns about it. check_refresh_rate always leaves at
+// least 10, but with fewer than this a tick can land more than 5% of its length late
+pub const MIN_CHECKS_PER_TICK: u64 = 20;
+
+// Warns when the gap between ticks is too short for the refresh rate to keep them steady, which happens with fine
+// subdivisions at fast tempos when --refresh-rate has been raised. None while the timing is fine
+pub fn timing_warning(ns_delay: u64, refresh_rate: u64) -> Option<String> {
+    let checks_per_tick = ns_delay / refresh_rate.max(1);
+    if checks_per_tick >= MIN_CHECKS_PER_TICK {
+        return None;
+    }
+    Some(format!(
+        "Ticks are only {:.1} ms apart, a refresh rate of {} microseconds can play them up to {}% late. Lower --refresh-rate for steadier timing",
+        ns_delay as f64 / 1_000_000.0,
+        refresh_rate / 1_000,
+        100 / checks_per_tick.max(1)
+    ))
+}
+
+// The subdivisions the (s) key steps through. Only one is played at a time, so moving to one clears the flags of the
+// others rather than toggling ts_triplets, sub_eights and sub_sixteens on their own
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Subdivision {
+    Straight,
+    Eighths,
+    Sixteenths,
+    Triplets,
+}
+
+impl Subdivision {
+    // Works out the subdivision from the shared flags, triplets win if more than one is set
+    pub fn from_flags(triplets: bool, sub_eights: bool, sub_sixteens: bool) -> Subdivision {
+        if triplets {
+            Subdivision::Triplets
+        } else if sub_sixteens {
+            Subdivision::Sixteenths
+        } else if sub_eights {
+            Subdivision::Eighths
+        } else {
+            Subdivision::Straight
+        }
+    }
+
+    // The ts_triplets, sub_eights and sub_sixteens flags that play this subdivision
+    pub fn flags(self) -> (bool, bool, bool) {
+        match self {
+            Subdivision::Straight => (false, false, false),
+            Subdivision::Eighths => (false, true, false),
+            Subdivision::Sixteenths => (false, false, true),
+            Subdivision::Triplets => (true, false, false),
+        }
+    }
+
+    pub fn next(self) -> Subdivision {
+        match self {
+            Subdivision::Straight => Subdivision::Eighths,
+            Subdivision::Eighths => Subdivision::Sixteenths,
+            Subdivision::Sixteenths => Subdivision::Triplets,
+            Subdivision::Triplets => Subdivision::Straight,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Subdivision::Straight => "straight",
+            Subdivision::Eighths => "8ths",
+            Subdivision::Sixteenths => "16ths",
+            Subdivision::Triplets => "triplets",
+        }
+    }
+}
+
+// The number of beats counted per bar, the override replaces the number worked out from the time signature unless
+// it is 0
+pub fn counted_beats_per_bar(beats_per_bar: u64, beats_per_bar_override: u64) -> u64 {
+    if beats_per_bar_override == 0 {
+        beats_per_bar
+    } else {
+        beats_per_bar_override
+    }
+}
+
+// Describes the time signature along with the subdivision and triplets being played, ie. "4/4 · 8ths · triplets".
+// Like compute_timing, subdivisions and triplets only apply to time signatures counted in quarter notes
+pub fn time_signature_label(
+    ts_note: u64,
+    ts_value: u64,
+    triplets: bool,
+    sub_eights: bool,
+    sub_sixteens: bool,
+) -> String {
+    let mut label = ts_note.to_string() + "/" + &ts_value.to_string();
+    if ts_value == 4 {
+        if sub_eights {
+            label += " · 8ths";
+        } else if sub_sixteens {
+            label += " · 16ths";
+        }
+        if triplets {
+            label += " · triplets";
+        }
+    }
+    label
+}
+
+// Whether a beat of the bar (counting from 0) is accented. Chosen accent beats come first, as long as one of them is
+// still in the bar. Otherwise the first beat of each accent group is, and groups that don't add up to the beats per bar
+// are left over from another time signature, so only beat 1 is accented
+pub fn is_accented(
+    beat_index: u64,
+    accent_beats: &[u64],
+    accent_groups: &[u64],
+    beats_per_bar: u64,
+) -> bool {
+    if accent_beats.iter().any(|beat| *beat < beats_per_bar) {
+        return accent_beats.contains(&beat_index);
+    }
+    if accent_groups.iter().sum::<u64>() != beats_per_bar {
+        return beat_index == 0;
+    }
+    let mut group_start = 0;
+    for group in accent_groups {
+        if beat_index == group_start {
+            return true;
+        }
+        group_start += group;
+    }
+    false
+}
+
+// Whether a tick (counting from 0 since the metronome started) lands on the accent every so many ticks, which runs
+// across the bar lines. An accent_every of 0 never accents
+pub fn accents_every(beat_number: u64, accent_every: u64) -> bool {
+    accent_every != 0 && beat_number % accent_every == 0
+}
+
+// Number of whole beats that went by without being played when a tick is this late, a loaded system can stall the
+// loop for longer than a beat at fast tempos
+pub fn missed_beats(lateness: Duration, delay: Duration) -> u64 {
+    (lateness.as_nanos() / delay.as_nanos().max(1)) as u64
+}
+
+// Number of whole refreshes that fit in the time since the last one counted, and how much of that time they cover.
+// The rest is left over for the next count so none of it is lost
+pub fn refresh_ticks(elapsed: Duration, refresh_rate: Duration) -> (u64, Duration) {
+    let refresh_ns = refresh_rate.as_nanos().max(1);
+    let ticks = elapsed.as_nanos() / refresh_ns;
+    (
+        ticks as u64,
+        Duration::from_nanos((ticks * refresh_ns) as u64),
+    )
+}
+
+// Whether a bar (counting from 1) is the last one played before stop_after_bars stops the metronome, never when it
+// runs indefinitely (0)
+pub fn is_final_bar(bar: u64, stop_after_bars: u64) -> bool {
+    stop_after_bars != 0 && bar == stop_after_bars
+}
+
+// How loud a beat plays during the fade in, from just above silent on the first beat up to full volume (1.0) once
+// fade_in_bars have been played. Bars count from 1 and beats from 0
+pub fn fade_in_scale(bar: u64, beat_index: u64, beats_per_bar: u64, fade_in_bars: u64) -> f64 {
+    let fade_beats = fade_in_bars * beats_per_bar;
+    let beats_played = bar.saturating_sub(1) * beats_per_bar + beat_index;
+    if beats_played >= fade_beats {
+        return 1.0;
+    }
+    (beats_played + 1) as f64 / (fade_beats + 1) as f64
+}
+
+// Works out the bpm for the next tempo trainer step, or None once the target has been reached. A positive increment
+// speeds up to the target and a negative one slows down to it, never stepping past the target or out of 20 - 500 bpm.
+// An increment of 0 holds the current tempo
+pub fn trainer_next_bpm(bpm: u64, target_bpm: u64, increment: i64) -> Option<u64> {
+    let reached = match increment {
+        0 => return Some(bpm),
+        1.. => bpm >= target_bpm,
+        _ => bpm <= target_bpm,
+    };
+    if reached {
+        return None;
+    }
+    let next_bpm = (bpm as i64 + increment).clamp(20, 500) as u64;
+    Some(if increment > 0 {
+        next_bpm.min(target_bpm)
+    } else {
+        next_bpm.max(target_bpm)
+    })
+}
+
+// How far through the bar the metronome is (0.0 - 1.0), counting the ticks already played in the bar plus the part of
+// the current tick that has passed. current_beat is the tick last played, counting from 1
+pub fn bar_progress(
+    current_beat: u64,
+    beats_per_bar: u64,
+    since_last_tick: Duration,
+    ns_delay: u64,
+) -> f64 {
+    let beat_fraction = (since_last_tick.as_nanos() as f64 / ns_delay.max(1) as f64).min(1.0);
+    let progress =
+        (current_beat.saturating_sub(1) as f64 + beat_fraction) / beats_per_bar.max(1) as f64;
+    progress.clamp(0.0, 1.0)
+}
+
+// How far through the current beat the metronome is (0.0 - 1.0), a tick that is running late holds just short of 1.0
+// rather than wrapping around before it plays
+pub fn beat_phase(since_last_tick: Duration, ns_delay: u64) -> f64 {
+    let phase = since_last_tick.as_nanos() as f64 / ns_delay.max(1) as f64;
+    phase.clamp(0.0, 1.0 - f64::EPSILON)
+}
+
+// Where a pendulum swinging in time with the ticks is across its swing (0.0 on the left - 1.0 on the right). It reaches
+// a side on every tick, counting from 1 since starting, and slows into each side like a real pendulum
+pub fn pendulum_position(tick: u64, beat_phase: f64) -> f64 {
+    let swing = (1.0 - (std::f64::consts::PI * beat_phase.clamp(0.0, 1.0)).cos()) / 2.0;
+    if tick % 2 == 1 {
+        swing
+    } else {
+        1.0 - swing
+    }
+}
+
+// The length in milliseconds of the note values commonly used for delay times at a bpm, where the bpm counts quarter
+// notes. Dotted notes are half as long again and triplets fit three in the space of two
+pub fn delay_times(bpm: f64) -> Vec<(&'static str, f64)> {
+    let quarter = 60_000.0 / bpm.max(1.0);
+    vec![
+        ("1/4", quarter),
+        ("1/8.", quarter * 0.75),
+        ("1/8", quarter / 2.0),
+        ("1/8T", quarter / 3.0),
+        ("1/16", quarter / 4.0),
+    ]
+}
+
+// How far the output meter is lit (0.0 - 1.0) after a click, jumping up with the volume as the click is heard and
+// dying away over the following ticks. Full volume (100) lights the whole meter
+pub fn click_level(since_last_tick: Duration, volume: f64) -> f64 {
+    let peak = (volume / 100.0).clamp(0.0, 1.0);
+    peak * (-since_last_tick.as_secs_f64() / CLICK_LEVEL_DECAY.as_secs_f64()).exp()
+}
+
+// Whether a tick of the bar (counting from 0) lands on one of the time signature's beats rather than a subdivision
+// between them, ie. every other tick with eighths or every fourth with sixteenths
+pub fn is_main_beat(tick: u64, beats_per_bar: u64, ts_note: u64) -> bool {
+    (tick * ts_note) % beats_per_bar.max(1) == 0
+}
+
+// Lists the names of every audio output device available on the default host
+pub fn get_output_device_names() -> Vec<String> {
+    match cpal::default_host().output_devices() {
+        Ok(devices) => devices.filter_map(|device| device.name().ok()).collect(),
+        Err(_) => Vec::new(),
+    }
+}
+
+// Opens an output stream on the named device, falling back to the system default if it can't be found or opened. The
+// name of the device opened is returned with the stream so it can be checked for later
+fn open_output_stream(
+    device_name: &str,
+) -> Result<(OutputStream, OutputStreamHandle, String), Report> {
+    if !device_name.is_empty() {
+        let device = cpal::default_host()
+            .output_devices()
+            .ok()
+            .and_then(|mut devices| {
+                devices.find(|device| device.name().is_ok_and(|name| name == device_name))
+            });
+        if let Some(device) = device {
+            if let Ok((stream, handle)) = OutputStream::try_from_device(&device) {
+                return Ok((stream, handle, device_name.to_owned()));
+            }
+        }
+    }
+    let default_name = cpal::default_host()
+        .default_output_device()
+        .and_then(|device| device.name().ok())
+        .unwrap_or_default();
+    OutputStream::try_default()
+        .map(|(stream, handle)| (stream, handle, default_name))
+        .map_err(|error| eyre!("No audio output available, {}", error))
+}
+
+// Whether the named output device is still plugged in. A device without a name, or a host that can't list its devices,
+// is taken to still be there
+fn output_device_present(device_name: &str) -> bool {
+    if device_name.is_empty() {
+        return true;
+    }
+    match cpal::default_host().output_devices() {
+        Ok(mut devices) => {
+            devices.any(|device| device.name().is_ok_and(|name| name == device_name))
+        }
+        Err(_) => true,
+    }
+}
+
+// Works out where sounds are loaded from when no folder is given. An assets folder next to the executable comes
+// first, then one in the user's data folder ($XDG_DATA_HOME/readymetronome/assets, or ~/.local/share when that isn't
+// set), and otherwise the assets folder in the working directory so running from the repository keeps working
+pub fn default_sound_dir() -> PathBuf {
+    let mut candidates = Vec::new();
+    if let Some(exe_dir) = env::current_exe()
+        .ok()
+        .and_then(|exe| exe.parent().map(Path::to_path_buf))
+    {
+        candidates.push(exe_dir.join(SOUND_DIR_NAME));
+    }
+    let data_dir = env::var_os("XDG_DATA_HOME")
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".local/share")));
+    if let Some(data_dir) = data_dir {
+        candidates.push(data_dir.join("readymetronome").join(SOUND_DIR_NAME));
+    }
+    candidates
+        .into_iter()
+        .find(|dir| dir.is_dir())
+        .unwrap_or_else(|| absolute_path(Path::new(SOUND_DIR_NAME)))
+}
+
+// Joins a relative path onto the working directory so it keeps pointing at the same folder
+pub fn absolute_path(path: &Path) -> PathBuf {
+    if path.is_absolute() {
+        return path.to_path_buf();
+    }
+    match env::current_dir() {
+        Ok(current_dir) => current_dir.join(path),
+        Err(_) => path.to_path_buf(),
+    }
+}
+
+// Opens and decodes a sound from the sound folder, this is shared by the live metronome and the wav exporter
+pub fn load_sound(
+    sound_dir: &Path,
+    sound_name: &str,
+) -> Result<Decoder<io::BufReader<File>>, Report> {
+    let file = io::BufReader::new(match File::open(sound_dir.join(sound_name)) {
+        Ok(value) => value,
+        Err(_) => {
+            return Err(eyre!("Error: Problem loading sound"));
+        }
+    });
+
+    match Decoder::new(file) {
+        Ok(source) => Ok(source),
+        Err(error) => Err(eyre!("Error: Could not decode {}, {}", sound_name, error)),
+    }
+}
+
+// How long a sound from the sound folder plays for at its normal speed. Formats that don't know their length up front
+// are decoded to count their samples
+pub fn sound_duration(sound_dir: &Path, sound_name: &str) -> Result<Duration, Report> {
+    let source = load_sound(sound_dir, sound_name)?;
+    if let Some(duration) = source.total_duration() {
+        return Ok(duration);
+    }
+    let samples_per_second = source.channels() as u64 * source.sample_rate() as u64;
+    let samples = source.count() as u64;
+    Ok(Duration::from_nanos(
+        samples * 1_000_000_000 / samples_per_second.max(1),
+    ))
+}
+
+// Whether a file in the sound folder has one of the SOUND_EXTENSIONS
+pub fn is_supported_sound(file_name: &str) -> bool {
+    Path::new(file_name)
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .is_some_and(|extension| SOUND_EXTENSIONS.contains(&extension.to_lowercase().as_str()))
+}
+
+// The gain that brings a sound's loudest sample to NORMALIZE_PEAK, found by decoding the whole sound once. Silent
+// sounds are left as they are
+pub fn normalize_gain(sound_dir: &Path, sound_name: &str) -> Result<f32, Report> {
+    let peak = load_sound(sound_dir, sound_name)?
+        .map(|sample| sample.unsigned_abs())
+        .max()
+        .unwrap_or(0);
+    if peak == 0 {
+        return Ok(1.0);
+    }
+    Ok((NORMALIZE_PEAK * i16::MAX as f32 / peak as f32).min(MAX_NORMALIZE_GAIN))
+}
+
+// Measures the normalizing gain of every sound in the sound list that hasn't been measured yet, so the metronome
+// doesn't have to decode them while it plays. Nothing is measured while normalize_sounds is off
+fn measure_sound_gains(settings: &MetronomeSettings) {
+    if !settings.normalize_sounds.load(Ordering::Relaxed) {
+        return;
+    }
+    let sound_list = settings.sound_list.lock().unwrap().clone();
+    for sound_name in &sound_list {
+        sound_gain(settings, sound_name);
+    }
+    forget_sound_gains(settings);
+}
+
+// Forgets the gains of sounds that have left the folder, so a new file with the same name is measured again
+pub fn forget_sound_gains(settings: &MetronomeSettings) {
+    let sound_dir = settings.sound_dir.lock().unwrap().clone();
+    let sound_list = settings.sound_list.lock().unwrap().clone();
+    let sound_paths: Vec<PathBuf> = sound_list
+        .iter()
+        .map(|sound_name| sound_dir.join(sound_name))
+        .collect();
+    settings
+        .sound_gains
+        .lock()
+        .unwrap()
+        .retain(|path, _| sound_paths.contains(path));
+}
+
+// The gain a sound is played with on top of the volume, 1.0 unless normalize_sounds is on. A sound that hasn't been
+// measured yet is measured now and remembered, and one that can't be decoded plays as it is so the error is reported
+// when it's played
+pub fn sound_gain(settings: &MetronomeSettings, sound_name: &str) -> f32 {
+    if !settings.normalize_sounds.load(Ordering::Relaxed) {
+        return 1.0;
+    }
+    let sound_dir = settings.sound_dir.lock().unwrap().clone();
+    let path = sound_dir.join(sound_name);
+    if let Some(gain) = settings.sound_gains.lock().unwrap().get(&path) {
+        return *gain;
+    }
+    let gain = normalize_gain(&sound_dir, sound_name).unwrap_or(1.0);
+    settings.sound_gains.lock().unwrap().insert(path, gain);
+    gain
+}
+
+// Starts a sound playing in a new sink on the output stream as the playback describes. The gain normalizes the sound
+// and is applied along with the volume
 fn metronome_tick(
-    stream_handle: OutputStreamHandle,
-    selected_sound_name: String,
-    volume: f64,
-) -> Result<(), Report> {
+    stream_handle: &OutputStreamHandle,
+    sound_dir: &Path,
+    selected_sound_name: &str,
+    playback: Playback,
+) -> Result<Sink, Report> {
     // TODO: Don't load the sample every time, if possible load once and replay.
-    let file = io::BufReader::new(
-        match File::open("./assets/".to_owned() + &selected_sound_name) {
-            Ok(value) => value,
-            Err(_) => {
-                return Err(eyre!("Error: Problem loading sound"));
-            }
-        },
+    let source = load_sound(sound_dir, selected_sound_name)?
+        .speed(playback.pitch as f32)
+        .amplify((playback.volume / 100.0) as f32 * playback.gain);
+    // centered sounds play untouched, panned ones are mixed down to mono (so mono samples pan too) and spread across a
+    // left and right channel
+    let source: Box<dyn Source<Item = i16> + Send> = if playback.pan == 0.0 {
+        Box::new(source)
+    } else {
+        let (left, right) = pan_gains(playback.pan);
+        Box::new(ChannelVolume::new(source, vec![left, right]))
+    };
+    let sink = Sink::try_new(stream_handle)?;
+    if playback.smooth {
+        sink.append(source.fade_in(CLICK_ATTACK));
+    } else {
+        sink.append(source);
+    }
+    Ok(sink)
+}
+
+// Plays a sine wave at the frequency for TONE_DURATION
+fn reference_tone(
+    stream_handle: &OutputStreamHandle,
+    frequency: f64,
+    volume: f64,
+) -> Result<Sink, Report> {
+    let sink = Sink::try_new(stream_handle)?;
+    sink.append(
+        SineWave::new(frequency as f32)
+            .take_duration(TONE_DURATION)
+            .amplify((volume / 100.0 * TONE_LEVEL) as f32),
     );
+    Ok(sink)
+}
+
+// Tests ---------------------------------------------------------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    // the length of a sound should come from the file, missing sounds are an error
+    #[test]
+    fn metronome_sound_duration() {
+        let sound_dir = absolute_path(Path::new("assets"));
+        let duration = sound_duration(&sound_dir, "EmeryBoardClick.wav").unwrap();
+        assert!(duration > Duration::ZERO && duration < Duration::from_secs(1));
+        assert!(sound_duration(&sound_dir, "missing.wav").is_err());
+    }
+
+    // normalizing should bring a sound's peak to NORMALIZE_PEAK, only while it is turned on, and remember the gain
+    #[test]
+    fn metronome_sound_gain() {
+        let sound_dir = absolute_path(Path::new("assets"));
+        let gain = normalize_gain(&sound_dir, "EmeryBoardClick.wav").unwrap();
+        let peak = load_sound(&sound_dir, "EmeryBoardClick.wav")
+            .unwrap()
+            .map(|sample| sample.unsigned_abs())
+            .max()
+            .unwrap();
+        assert!(
+            gain == MAX_NORMALIZE_GAIN
+                || (peak as f32 * gain / i16::MAX as f32 - NORMALIZE_PEAK).abs() < 0.001
+        );
+        assert!(normalize_gain(&sound_dir, "missing.wav").is_err());
+
+        let (_metronome, settings) = Metronome::builder().audio(false).build();
+        *settings.sound_dir.lock().unwrap() = sound_dir.clone();
+        *settings.sound_list.lock().unwrap() = vec!["EmeryBoardClick.wav".to_owned()];
+        assert_eq!(sound_gain(&settings, "EmeryBoardClick.wav"), 1.0);
+        measure_sound_gains(&settings);
+        assert!(settings.sound_gains.lock().unwrap().is_empty());
+
+        settings.normalize_sounds.swap(true, Ordering::Relaxed);
+        settings
+            .sound_gains
+            .lock()
+            .unwrap()
+            .insert(sound_dir.join("gone.wav"), 2.0);
+        measure_sound_gains(&settings);
+        assert_eq!(
+            *settings.sound_gains.lock().unwrap(),
+            HashMap::from([(sound_dir.join("EmeryBoardClick.wav"), gain)])
+        );
+        assert_eq!(sound_gain(&settings, "EmeryBoardClick.wav"), gain);
+    }
+
+    // a file that can't be decoded should be reported through the error flag with its name instead of panicking the
+    // metronome's thread
+    #[test]
+    fn metronome_undecodable_sound() {
+        let sound_dir = std::env::temp_dir().join("readymetronome_test_undecodable");
+        fs::create_dir_all(&sound_dir).unwrap();
+        fs::write(
+            sound_dir.join("garbage.wav"),
+            b"RIFF\x10\0\0\0WAVEnot really audio",
+        )
+        .unwrap();
+        let error = load_sound(&sound_dir, "garbage.wav").err().unwrap();
+        assert!(error.to_string().contains("Could not decode garbage.wav"));
+
+        let (mut metronome, settings) = Metronome::builder()
+            .sounds(vec!["garbage.wav".to_owned()])
+            .sound_dir(&sound_dir)
+            .audio(false)
+            .build();
+        *settings.intro_sound.lock().unwrap() = Some(0);
+        metronome.play_intro(None);
+        assert!(settings.error.load(Ordering::Relaxed));
+        assert!(settings
+            .error_message
+            .lock()
+            .unwrap()
+            .contains("Could not decode garbage.wav"));
+        let _ = fs::remove_dir_all(&sound_dir);
+    }
+
+    // a sink that can't be made on the output stream should reopen the output rather than show as an error
+    #[test]
+    fn metronome_sound_error() {
+        let (mut metronome, settings) = Metronome::builder().audio(false).build();
+        metronome.sound_error(Report::from(PlayError::NoDevice));
+        assert!(metronome.output_lost);
+        assert!(!settings.error.load(Ordering::Relaxed));
+
+        metronome.sound_error(eyre!("Could not find missing.wav"));
+        assert!(settings.error.load(Ordering::Relaxed));
+        assert!(output_device_present(""));
+        assert!(!output_device_present(
+            "readymetronome test device that isn't there"
+        ));
+    }
+
+    // relative sound folders should be taken from the working directory
+    #[test]
+    fn metronome_absolute_path() {
+        let current_dir = env::current_dir().unwrap();
+        assert_eq!(
+            absolute_path(Path::new("samples")),
+            current_dir.join("samples")
+        );
+        assert_eq!(
+            absolute_path(&current_dir.join("assets")),
+            current_dir.join("assets")
+        );
+        assert!(default_sound_dir().is_absolute());
+    }
+
+    // the builder should set up the settings it was given with matching timing
+    #[test]
+    fn metronome_builder() {
+        let (_, settings) = Metronome::builder()
+            .bpm(90.0)
+            .time_signature(3, 4)
+            .sounds(vec!["EmeryBoardClick.wav".to_owned()])
+            .audio(false)
+            .build();
+        assert_eq!(settings.bpm.load(Ordering::Relaxed), 90.0);
+        assert_eq!(settings.ts_note.load(Ordering::Relaxed), 3);
+        assert_eq!(settings.beats_per_bar.load(Ordering::Relaxed), 3);
+        assert_eq!(settings.ns_delay.load(Ordering::Relaxed), 666_666_667);
+        assert!(settings.silent.load(Ordering::Relaxed));
+        assert_eq!(settings.sound_list.lock().unwrap().len(), 1);
+    }
+
+    // a headless metronome should count beats and pass them to on_beat without an audio output
+    #[test]
+    fn metronome_builder_on_beat() {
+        let (sender, receiver) = mpsc::channel();
+        let (mut metronome, settings) = Metronome::builder()
+            .bpm(500.0)
+            .audio(false)
+            .on_beat(move |event| {
+                let _ = sender.send((event.bar, event.beat));
+            })
+            .build();
+        let beat_events = settings.subscribe_beats();
+        // a receiver that has gone away shouldn't stop the others getting beats
+        drop(settings.subscribe_beats());
+        let handle = std::thread::spawn(move || metronome.start(DEFAULT_REFRESH_RATE_NS));
+        settings.is_running.swap(true, Ordering::Relaxed);
+        let timeout = Duration::from_secs(2);
+        assert_eq!(receiver.recv_timeout(timeout), Ok((1, 1)));
+        assert_eq!(receiver.recv_timeout(timeout), Ok((1, 2)));
+        let event = beat_events.recv_timeout(timeout).unwrap();
+        assert_eq!((event.bar, event.beat, event.is_accent), (1, 1, true));
+        assert_eq!(settings.beat_subscribers.lock().unwrap().len(), 1);
+        settings.shutdown.swap(true, Ordering::Relaxed);
+        handle.join().unwrap();
+    }
+
+    // rapid ticks should each cut off the one before, leaving only the last one sounding
+    #[test]
+    fn metronome_voice_plays_one_tick_at_a_time() {
+        let mut voice = Voice::default();
+        let mut outputs = Vec::new();
+        for _ in 0..10 {
+            let (sink, output) = Sink::new_idle();
+            sink.append(rodio::source::SineWave::new(440.0).take_duration(Duration::from_secs(1)));
+            voice.play(sink);
+            outputs.push(output);
+        }
+        // the sinks notice they were stopped within a few milliseconds, well inside the second each tone lasts
+        let is_sounding = |output: rodio::queue::SourcesQueueOutput<f32>| {
+            output.skip(4_000).take(1_000).any(|sample| sample != 0.0)
+        };
+        let last_output = outputs.pop().unwrap();
+        for output in outputs {
+            assert!(!is_sounding(output));
+        }
+        assert!(is_sounding(last_output));
+    }
+
+    // the refresh rate should stay well under the shortest beat
+    #[test]
+    fn metronome_check_refresh_rate() {
+        assert_eq!(shortest_ns_delay(), 7_500_000);
+        assert!(check_refresh_rate(DEFAULT_REFRESH_RATE_NS).is_ok());
+        assert!(check_refresh_rate(750_000).is_ok());
+        assert!(check_refresh_rate(751_000).is_err());
+        assert!(check_refresh_rate(0).is_err());
+    }
+
+    // the warning should only start once a tick gets fewer than MIN_CHECKS_PER_TICK refresh checks
+    #[test]
+    fn metronome_timing_warning() {
+        let (sixty_fourths, _) = compute_timing(500.0, 4, 64, false, false, false);
+        assert_eq!(timing_warning(sixty_fourths, DEFAULT_REFRESH_RATE_NS), None);
+        assert_eq!(timing_warning(sixty_fourths, 375_000), None);
+        let warning = timing_warning(sixty_fourths, 376_000).unwrap();
+        assert!(warning.contains("7.5 ms"));
+        assert!(warning.contains("up to 5% late"));
+        assert!(timing_warning(sixty_fourths, 750_000)
+            .unwrap()
+            .contains("up to 10% late"));
+    }
+
+    // metronome::compute_timing should work out the delay and beats per bar for each meter and subdivision
+    #[test]
+    fn metronome_compute_timing_4_4() {
+        assert_eq!(
+            compute_timing(120.0, 4, 4, false, false, false),
+            (500_000_000, 4)
+        );
+    }
+
+    #[test]
+    fn metronome_compute_timing_6_8() {
+        assert_eq!(
+            compute_timing(120.0, 6, 8, false, false, false),
+            (166_666_667, 6)
+        );
+    }
+
+    #[test]
+    fn metronome_compute_timing_2_2() {
+        assert_eq!(
+            compute_timing(120.0, 2, 2, false, false, false),
+            (1_000_000_000, 2)
+        );
+    }
+
+    #[test]
+    fn metronome_compute_timing_triplets() {
+        assert_eq!(
+            compute_timing(120.0, 4, 4, true, false, false),
+            (333_333_333, 6)
+        );
+    }
+
+    #[test]
+    fn metronome_compute_timing_sixteenths() {
+        assert_eq!(
+            compute_timing(120.0, 4, 4, false, false, true),
+            (125_000_000, 16)
+        );
+        // eighths win when both subdivisions are set
+        assert_eq!(
+            compute_timing(120.0, 4, 4, false, true, true),
+            (250_000_000, 8)
+        );
+    }
+
+    // fractional tempos should get their own delay, rounded to tenths of a bpm
+    #[test]
+    fn metronome_compute_timing_fractional_bpm() {
+        assert_eq!(
+            compute_timing(120.5, 4, 4, false, false, false),
+            (497_925_311, 4)
+        );
+        assert_eq!(round_bpm(120.54), 120.5);
+        assert_eq!(round_bpm(120.55), 120.6);
+        assert_eq!(round_bpm(120.0), 120.0);
+    }
+
+    // the label should only list the subdivisions that compute_timing plays
+    #[test]
+    fn metronome_time_signature_label() {
+        assert_eq!(time_signature_label(4, 4, false, false, false), "4/4");
+        assert_eq!(
+            time_signature_label(4, 4, true, false, true),
+            "4/4 · 16ths · triplets"
+        );
+        assert_eq!(time_signature_label(7, 8, false, true, false), "7/8");
+    }
+
+    // the output meter should start at the volume and die away after the click
+    #[test]
+    fn metronome_click_level() {
+        assert_eq!(click_level(Duration::ZERO, 100.0), 1.0);
+        assert_eq!(click_level(Duration::ZERO, 50.0), 0.5);
+        let later = click_level(Duration::from_millis(60), 100.0);
+        assert!(later < 1.0 && later > click_level(Duration::from_millis(120), 100.0));
+        assert!(click_level(Duration::from_secs(2), 100.0) < 0.001);
+    }
+
+    // the first beat of each group should be accented, falling back to beat 1 when the groups don't fit the bar
+    #[test]
+    fn metronome_is_accented() {
+        let accented: Vec<u64> = (0..7)
+            .filter(|beat| is_accented(*beat, &[], &[2, 2, 3], 7))
+            .collect();
+        assert_eq!(accented, vec![0, 2, 4]);
+        assert!(is_accented(0, &[], &[], 4));
+        assert!(!is_accented(2, &[], &[], 4));
+        assert!(!is_accented(2, &[], &[2, 2, 3], 8));
+
+        // chosen beats replace beat 1 and the groups, unless none of them fit in the bar
+        let accented: Vec<u64> = (0..4)
+            .filter(|beat| is_accented(*beat, &[1, 3], &[], 4))
+            .collect();
+        assert_eq!(accented, vec![1, 3]);
+        assert!(!is_accented(0, &[3], &[2, 2], 4));
+        assert!(is_accented(0, &[5], &[], 4));
+    }
+
+    // accenting every few ticks should carry on across bars, and 0 should turn it off
+    #[test]
+    fn metronome_accents_every() {
+        let accented: Vec<u64> = (0..10).filter(|beat| accents_every(*beat, 3)).collect();
+        assert_eq!(accented, vec![0, 3, 6, 9]);
+        assert!(!(0..10).any(|beat| accents_every(beat, 0)));
+        assert!(accents_every(4, 1));
+    }
+
+    // only ticks late by a whole beat or more should count as missing beats
+    #[test]
+    fn metronome_missed_beats() {
+        let delay = Duration::from_millis(500);
+        assert_eq!(missed_beats(Duration::from_micros(40), delay), 0);
+        assert_eq!(missed_beats(Duration::from_millis(499), delay), 0);
+        assert_eq!(missed_beats(Duration::from_millis(1100), delay), 2);
+    }
+
+    // the debug tick count should keep up with the clock when the loop wakes up late or early, counting the refreshes
+    // that went by instead of one per wake up
+    #[test]
+    fn metronome_refresh_ticks() {
+        let refresh_rate = Duration::from_micros(1_000);
+        assert_eq!(
+            refresh_ticks(Duration::from_micros(999), refresh_rate),
+            (0, Duration::ZERO)
+        );
+        assert_eq!(
+            refresh_ticks(Duration::from_micros(3_400), refresh_rate),
+            (3, Duration::from_micros(3_000))
+        );
+
+        // simulate a second of the loop waking up at uneven times, like it does under load
+        let wake_ups = [700, 2_300, 150, 5_000, 1_000, 1_999, 60];
+        let mut now = Duration::ZERO;
+        let mut last_refresh = Duration::ZERO;
+        let mut tick_count = 0;
+        for wake_up in wake_ups.iter().cycle() {
+            if now >= Duration::from_secs(1) {
+                break;
+            }
+            now += Duration::from_micros(*wake_up);
+            let (ticks, counted) = refresh_ticks(now - last_refresh, refresh_rate);
+            tick_count += ticks;
+            last_refresh += counted;
+        }
+        assert_eq!(tick_count, (now.as_micros() / 1_000) as u64);
+    }
+
+    // only the last bar before stopping should be the final bar
+    #[test]
+    fn metronome_is_final_bar() {
+        assert!(is_final_bar(8, 8));
+        assert!(!is_final_bar(7, 8));
+        assert!(!is_final_bar(1, 0));
+    }
+
+    // the fade in should ramp up over the first bars and then stay at full volume
+    #[test]
+    fn metronome_fade_in_scale() {
+        assert_eq!(fade_in_scale(1, 0, 4, 0), 1.0);
+        assert_eq!(fade_in_scale(1, 0, 4, 1), 0.2);
+        assert_eq!(fade_in_scale(1, 3, 4, 1), 0.8);
+        assert_eq!(fade_in_scale(2, 0, 4, 1), 1.0);
+        assert_eq!(fade_in_scale(2, 1, 2, 2), 0.8);
+        assert_eq!(fade_in_scale(9, 0, 4, 2), 1.0);
+    }
 
-    let source = Decoder::new(file).unwrap();
-    let _ = stream_handle.play_raw(source.amplify((volume / 100.0) as f32).convert_samples());
-    Ok(())
+    // cycling should visit every subdivision once and set exactly one flag for each
+    #[test]
+    fn metronome_subdivision_cycle() {
+        let mut subdivision = Subdivision::Straight;
+        let mut names = Vec::new();
+        for _ in 0..4 {
+            subdivision = subdivision.next();
+            let (triplets, sub_eights, sub_sixteens) = subdivision.flags();
+            assert_eq!(
+                Subdivision::from_flags(triplets, sub_eights, sub_sixteens),
+                subdivision
+            );
+            names.push(subdivision.name());
+        }
+        assert_eq!(names, ["8ths", "16ths", "triplets", "straight"]);
+        assert_eq!(
+            Subdivision::from_flags(true, true, false),
+            Subdivision::Triplets
+        );
+    }
+
+    // the trainer should step towards the target in either direction and stop there
+    #[test]
+    fn metronome_trainer_next_bpm() {
+        assert_eq!(trainer_next_bpm(100, 160, 5), Some(105));
+        assert_eq!(trainer_next_bpm(158, 160, 5), Some(160));
+        assert_eq!(trainer_next_bpm(160, 160, 5), None);
+        // ritardando
+        assert_eq!(trainer_next_bpm(120, 80, -10), Some(110));
+        assert_eq!(trainer_next_bpm(85, 80, -10), Some(80));
+        assert_eq!(trainer_next_bpm(80, 80, -10), None);
+        // never below the minimum bpm, even with a target below it
+        assert_eq!(trainer_next_bpm(25, 10, -10), Some(20));
+        // 0 holds the tempo
+        assert_eq!(trainer_next_bpm(100, 160, 0), Some(100));
+    }
+
+    // metronome::delay_times should give each note value's length in ms at the bpm
+    #[test]
+    fn metronome_delay_times() {
+        let times = delay_times(120.0);
+        assert_eq!(times[0], ("1/4", 500.0));
+        assert_eq!(times[1], ("1/8.", 375.0));
+        assert_eq!(times[2], ("1/8", 250.0));
+        assert_eq!(times[4], ("1/16", 125.0));
+        assert!((times[3].1 - 166.667).abs() < 0.001);
+    }
+
+    // the beat phase should move through the beat and hold short of 1.0 when a tick is late
+    #[test]
+    fn metronome_beat_phase() {
+        let ns_delay = 500_000_000;
+        assert_eq!(beat_phase(Duration::ZERO, ns_delay), 0.0);
+        assert_eq!(beat_phase(Duration::from_millis(125), ns_delay), 0.25);
+        assert!(beat_phase(Duration::from_millis(600), ns_delay) < 1.0);
+    }
+
+    // the pendulum should reach a side on every tick and swing back the other way after it
+    #[test]
+    fn metronome_pendulum_position() {
+        assert_eq!(pendulum_position(1, 0.0), 0.0);
+        assert!((pendulum_position(1, 0.5) - 0.5).abs() < 1e-9);
+        assert!((pendulum_position(1, 1.0) - 1.0).abs() < 1e-9);
+        assert_eq!(pendulum_position(2, 0.0), 1.0);
+        assert!(pendulum_position(2, 0.25) > 0.5);
+    }
+
+    // the bar progress should move smoothly through each tick and never run past the end of the bar
+    #[test]
+    fn metronome_bar_progress() {
+        let ns_delay = 500_000_000;
+        assert_eq!(bar_progress(1, 4, Duration::ZERO, ns_delay), 0.0);
+        assert_eq!(
+            bar_progress(1, 4, Duration::from_millis(250), ns_delay),
+            0.125
+        );
+        assert_eq!(
+            bar_progress(3, 4, Duration::from_millis(500), ns_delay),
+            0.75
+        );
+        // a late tick holds at the end of its beat
+        assert_eq!(bar_progress(4, 4, Duration::from_secs(2), ns_delay), 1.0);
+    }
+
+    // with time_sig_on_downbeat a change made while running should wait until it is applied at the next bar
+    #[test]
+    fn metronome_change_time_signature() {
+        let (_, settings) = Metronome::builder().audio(false).build();
+        let mut eighths = settings.time_signature();
+        eighths.sub_eights = true;
+        settings.change_time_signature(eighths);
+        assert_eq!(settings.beats_per_bar.load(Ordering::Relaxed), 8);
+        assert_eq!(settings.beat_sounds.lock().unwrap().len(), 8);
+
+        settings.time_sig_on_downbeat.swap(true, Ordering::Relaxed);
+        settings.is_running.swap(true, Ordering::Relaxed);
+        let mut waltz = settings.next_time_signature();
+        waltz.ts_note = 3;
+        settings.change_time_signature(waltz);
+        assert_eq!(settings.beats_per_bar.load(Ordering::Relaxed), 8);
+        assert_eq!(settings.next_time_signature(), waltz);
+        settings.apply_pending_time_signature();
+        assert_eq!(settings.time_signature(), waltz);
+        assert_eq!(settings.beats_per_bar.load(Ordering::Relaxed), 6);
+        assert_eq!(*settings.pending_time_sig.lock().unwrap(), None);
+    }
+
+    // beat_count should count 1 to beats_per_bar and start the next bar from 1, with subdivisions counted as beats
+    #[test]
+    fn metronome_beat_count() {
+        let (mut metronome, settings) = Metronome::builder()
+            .time_signature(3, 4)
+            .audio(false)
+            .build();
+        let mut counts = vec![];
+        for _ in 0..7 {
+            metronome.beat_count();
+            counts.push((
+                settings.current_beat_count.load(Ordering::Relaxed),
+                settings.bar_count.load(Ordering::Relaxed),
+            ));
+        }
+        assert_eq!(
+            counts,
+            vec![(1, 1), (2, 1), (3, 1), (1, 2), (2, 2), (3, 2), (1, 3)]
+        );
+
+        // eighths double the ticks in the bar
+        let mut eighths = settings.time_signature();
+        eighths.sub_eights = true;
+        settings.change_time_signature(eighths);
+        for _ in 0..5 {
+            metronome.beat_count();
+        }
+        assert_eq!(settings.current_beat_count.load(Ordering::Relaxed), 6);
+        assert_eq!(settings.bar_count.load(Ordering::Relaxed), 3);
+        metronome.beat_count();
+        assert_eq!(settings.current_beat_count.load(Ordering::Relaxed), 1);
+        assert_eq!(settings.bar_count.load(Ordering::Relaxed), 4);
+
+        // a bar shortened past the current beat starts the next bar
+        for _ in 0..4 {
+            metronome.beat_count();
+        }
+        assert_eq!(settings.current_beat_count.load(Ordering::Relaxed), 5);
+        eighths.sub_eights = false;
+        settings.change_time_signature(eighths);
+        metronome.beat_count();
+        assert_eq!(settings.current_beat_count.load(Ordering::Relaxed), 1);
+        assert_eq!(settings.bar_count.load(Ordering::Relaxed), 5);
+    }
+
+    // total_bars should keep counting when the metronome is stopped and started again
+    #[test]
+    fn metronome_total_bars() {
+        let (mut metronome, settings) = Metronome::builder().audio(false).build();
+        for _ in 0..5 {
+            metronome.beat_count();
+        }
+        assert_eq!(settings.total_bars.load(Ordering::Relaxed), 2);
+        // stopping puts the bar back to the start like the engine loop does
+        settings.bar_count.swap(1, Ordering::Relaxed);
+        settings.current_beat_count.swap(0, Ordering::Relaxed);
+        metronome.beat_count();
+        assert_eq!(settings.bar_count.load(Ordering::Relaxed), 1);
+        assert_eq!(settings.total_bars.load(Ordering::Relaxed), 3);
+    }
+
+    // feel offsets should be looked up per beat and kept within half the gap between beats
+    #[test]
+    fn metronome_feel_offset() {
+        let (mut metronome, settings) = Metronome::builder().audio(false).build();
+        *settings.beat_offsets.lock().unwrap() = vec![0, 15, -10];
+        assert_eq!(beat_offset_ms(&settings, 1), 15);
+        assert_eq!(beat_offset_ms(&settings, 2), -10);
+        assert_eq!(beat_offset_ms(&settings, 3), 0);
+
+        // 120 bpm leaves 500ms between beats
+        assert_eq!(feel_offset_ns(15, 500_000_000), 15_000_000);
+        assert_eq!(feel_offset_ns(-10, 500_000_000), -10_000_000);
+        assert_eq!(feel_offset_ns(400, 500_000_000), 250_000_000);
+        assert_eq!(feel_offset_ns(-400, 500_000_000), -250_000_000);
+        assert_eq!(feel_offset_ns(0, 500_000_000), 0);
+
+        // the next beat wraps back to the first once the bar is finished
+        assert_eq!(metronome.next_beat_index(), 0);
+        for _ in 0..3 {
+            metronome.beat_count();
+        }
+        assert_eq!(metronome.next_beat_index(), 3);
+        metronome.beat_count();
+        assert_eq!(metronome.next_beat_index(), 0);
+    }
+
+    // the beats and subdivisions should take their own pans, with equal power gains across the speakers
+    #[test]
+    fn metronome_pan() {
+        let (_, settings) = Metronome::builder().audio(false).build();
+        let mut eighths = settings.time_signature();
+        eighths.sub_eights = true;
+        settings.change_time_signature(eighths);
+        settings.click_pan.swap(-0.5, Ordering::Relaxed);
+        settings.subdivision_pan.swap(0.5, Ordering::Relaxed);
+        assert_eq!(get_beat_pan(&settings, 0), -0.5);
+        assert_eq!(get_beat_pan(&settings, 1), 0.5);
+        assert_eq!(get_beat_pan(&settings, 2), -0.5);
+
+        let (left, right) = pan_gains(0.0);
+        assert!((left - right).abs() < 1e-6);
+        assert!((left * left + right * right - 1.0).abs() < 1e-6);
+        assert_eq!(pan_gains(-1.0), (1.0, 0.0));
+        let (left, right) = pan_gains(2.0);
+        assert!(left.abs() < 1e-6 && (right - 1.0).abs() < 1e-6);
+    }
+
+    // beats turned off should still be counted, and beats added to the bar should be played
+    #[test]
+    fn metronome_beat_plays() {
+        let (mut metronome, settings) = Metronome::builder().audio(false).build();
+        *settings.beat_enabled.lock().unwrap() = vec![true, false, true, true];
+        assert!(beat_plays(&settings, 0));
+        assert!(!beat_plays(&settings, 1));
+        assert!(beat_plays(&settings, 7));
+
+        metronome.start_tick_thread(None, Duration::ZERO);
+        metronome.start_tick_thread(None, Duration::ZERO);
+        assert_eq!(settings.current_beat_count.load(Ordering::Relaxed), 2);
+
+        let mut eighths = settings.time_signature();
+        eighths.sub_eights = true;
+        settings.change_time_signature(eighths);
+        assert_eq!(
+            *settings.beat_enabled.lock().unwrap(),
+            vec![true, false, true, true, true, true, true, true]
+        );
+    }
+
+    // subdivisions should play the subdivision sound and beats their own sound
+    #[test]
+    fn metronome_get_beat_sound() {
+        let (_, settings) = Metronome::builder().audio(false).build();
+        *settings.beat_sounds.lock().unwrap() = vec![0, 1, 2, 1];
+        settings.subdivision_sound.swap(3, Ordering::Relaxed);
+        let sounds = |settings: &MetronomeSettings| {
+            (0..settings.beats_per_bar.load(Ordering::Relaxed) as usize)
+                .map(|tick| get_beat_sound(settings, tick))
+                .collect::<Vec<usize>>()
+        };
+        // straight 4/4 has no subdivisions so the setting is ignored
+        assert_eq!(sounds(&settings), vec![0, 1, 2, 1]);
+        // eighths in 4/4, the sound map is counted in ticks
+        settings.beats_per_bar.swap(8, Ordering::Relaxed);
+        *settings.beat_sounds.lock().unwrap() = vec![0, 0, 2, 0, 1, 0, 2, 0];
+        assert_eq!(sounds(&settings), vec![0, 3, 2, 3, 1, 3, 2, 3]);
+    }
+
+    // subdivisions should fall between the beats of the time signature
+    #[test]
+    fn metronome_is_main_beat() {
+        let main_beats = |beats_per_bar, ts_note| {
+            (0..beats_per_bar)
+                .map(|tick| is_main_beat(tick, beats_per_bar, ts_note))
+                .collect::<Vec<bool>>()
+        };
+        // straight 4/4 is all main beats
+        assert_eq!(main_beats(4, 4), vec![true; 4]);
+        // eighths in 4/4
+        assert_eq!(
+            main_beats(8, 4),
+            vec![true, false, true, false, true, false, true, false]
+        );
+        // sixteenths in 3/4
+        assert_eq!(
+            main_beats(12, 3),
+            vec![true, false, false, false, true, false, false, false, true, false, false, false]
+        );
+    }
+
+    // metronome::is_supported_sound should match the sound formats by extension, ignoring case
+    #[test]
+    fn metronome_is_supported_sound() {
+        assert!(is_supported_sound("EmeryBoardClick.wav"));
+        assert!(is_supported_sound("cowbell.OGG"));
+        assert!(is_supported_sound("rim.flac"));
+        assert!(is_supported_sound("clap.mp3"));
+        assert!(!is_supported_sound("notes.txt"));
+        assert!(!is_supported_sound("wav"));
+    }
 }
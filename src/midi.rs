@@ -0,0 +1,213 @@
+/// Midi.rs sends MIDI clock so drum machines and DAWs can follow the metronome's tempo. It runs on its own thread
+/// next to the metronome, sending 24 clock pulses per quarter note from the shared bpm along with Start / Stop
+/// messages whenever the metronome is started or stopped. Start / Stop, and the clock pulses counted from them, wait out
+/// output_latency_ms so the clock follows the click as it is heard
+use crate::metronome::MetronomeSettings;
+use atomic_float::AtomicF64;
+use midir::{MidiOutput, MidiOutputConnection};
+use std::{
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+// MIDI real time messages
+const CLOCK: u8 = 0xF8;
+const START: u8 = 0xFA;
+const STOP: u8 = 0xFC;
+
+const PULSES_PER_QUARTER_NOTE: u64 = 24;
+
+// How long the thread sleeps between checks while MIDI clock is off, and the longest it sleeps while on so the
+// metronome starting or stopping is noticed quickly. Otherwise it sleeps until the next message is due
+const IDLE_POLL: Duration = Duration::from_millis(10);
+const RUNNING_POLL: Duration = Duration::from_millis(2);
+
+// Name Ready Metronome shows up as to other MIDI software
+const CLIENT_NAME: &str = "Ready Metronome";
+
+pub struct MidiClock {
+    bpm: Arc<AtomicF64>,
+    is_running: Arc<AtomicBool>,
+    output_latency_ms: Arc<AtomicU64>,
+    enabled: Arc<AtomicBool>,
+    port_name: Arc<Mutex<String>>,
+    shutdown: Arc<AtomicBool>,
+}
+
+impl MidiClock {
+    pub fn new(settings: &MetronomeSettings) -> MidiClock {
+        MidiClock {
+            bpm: Arc::clone(&settings.bpm),
+            is_running: Arc::clone(&settings.is_running),
+            output_latency_ms: Arc::clone(&settings.output_latency_ms),
+            enabled: Arc::clone(&settings.midi_enabled),
+            port_name: Arc::clone(&settings.midi_port),
+            shutdown: Arc::clone(&settings.shutdown),
+        }
+    }
+
+    pub fn start(&mut self) {
+        let mut connection: Option<MidiOutputConnection> = None;
+        // the port the connection was last attempted on, a failed port isn't retried until the setting changes
+        let mut connected_port: Option<String> = None;
+        let mut was_running = false;
+        let mut next_pulse = Instant::now();
+        // a Start or Stop waiting out the output latency, and when it is due
+        let mut pending_transport: Option<(u8, Instant)> = None;
+
+        loop {
+            if self.shutdown.load(Ordering::Relaxed) {
+                if let Some(mut connection) = connection {
+                    let _ = connection.send(&[STOP]);
+                    connection.close();
+                }
+                return;
+            }
+
+            if !self.enabled.load(Ordering::Relaxed) {
+                // dropping the connection closes the port
+                connection = None;
+                connected_port = None;
+                was_running = false;
+                pending_transport = None;
+                thread::sleep(IDLE_POLL);
+                continue;
+            }
+
+            let port_name = self.port_name.lock().unwrap().clone();
+            if connected_port.as_ref() != Some(&port_name) {
+                connection = open_port(&port_name);
+                connected_port = Some(port_name);
+                was_running = false;
+                pending_transport = None;
+            }
+
+            if let Some(connection) = connection.as_mut() {
+                let running = self.is_running.load(Ordering::Relaxed);
+                if running != was_running {
+                    was_running = running;
+                    let latency = self.output_latency_ms.load(Ordering::Relaxed);
+                    pending_transport = Some((
+                        if running { START } else { STOP },
+                        transport_time(Instant::now(), latency),
+                    ));
+                }
+                if let Some((message, due)) = pending_transport {
+                    if Instant::now() >= due {
+                        let _ = connection.send(&[message]);
+                        next_pulse = Instant::now();
+                        pending_transport = None;
+                    }
+                }
+
+                // keep sending clock while stopped so the receiving gear already has the tempo when started
+                let pulse_delay = Duration::from_secs_f64(
+                    60.0 / self.bpm.load(Ordering::Relaxed).max(1.0)
+                        / PULSES_PER_QUARTER_NOTE as f64,
+                );
+                let now = Instant::now();
+                if now >= next_pulse {
+                    let _ = connection.send(&[CLOCK]);
+                    next_pulse += pulse_delay;
+                    // don't rush to catch up if the thread fell behind
+                    if now >= next_pulse {
+                        next_pulse = now + pulse_delay;
+                    }
+                }
+            }
+
+            // a port that couldn't be opened has nothing to send, so it waits like MIDI clock being off
+            let now = Instant::now();
+            let wake = if connection.is_some() {
+                wake_time(now, next_pulse, pending_transport.map(|(_, due)| due))
+            } else {
+                now + IDLE_POLL
+            };
+            spin_sleep::sleep(wake.saturating_duration_since(now));
+        }
+    }
+}
+
+// When a Start or Stop for the metronome starting or stopping at now is sent. The metronome plays its sounds the
+// output latency early, so the clock waits that long to line up with the click as it is heard
+fn transport_time(now: Instant, output_latency_ms: u64) -> Instant {
+    now + Duration::from_millis(output_latency_ms)
+}
+
+// When the thread should next wake while sending clock, at the next pulse or pending Start / Stop, and no later than
+// RUNNING_POLL from now
+fn wake_time(now: Instant, next_pulse: Instant, pending_transport: Option<Instant>) -> Instant {
+    let wake = (now + RUNNING_POLL).min(next_pulse);
+    match pending_transport {
+        Some(due) => wake.min(due),
+        None => wake,
+    }
+}
+
+// Lists the names of the MIDI output ports that clock can be sent to
+pub fn get_midi_port_names() -> Vec<String> {
+    let output = match MidiOutput::new(CLIENT_NAME) {
+        Ok(output) => output,
+        Err(_) => return Vec::new(),
+    };
+    output
+        .ports()
+        .iter()
+        .filter_map(|port| output.port_name(port).ok())
+        .collect()
+}
+
+// Connects to the named MIDI port. An empty name creates a virtual port where the platform supports it, otherwise
+// the first port found is used
+fn open_port(port_name: &str) -> Option<MidiOutputConnection> {
+    let output = MidiOutput::new(CLIENT_NAME).ok()?;
+
+    #[cfg(unix)]
+    {
+        use midir::os::unix::VirtualOutput;
+        if port_name.is_empty() {
+            return output.create_virtual(CLIENT_NAME).ok();
+        }
+    }
+
+    let ports = output.ports();
+    let port = if port_name.is_empty() {
+        ports.first()
+    } else {
+        ports
+            .iter()
+            .find(|port| output.port_name(port).is_ok_and(|name| name == port_name))
+    }?
+    .clone();
+    output.connect(&port, CLIENT_NAME).ok()
+}
+
+// Tests ---------------------------------------------------------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Start and Stop, and the clock after them, should wait out the output latency
+    #[test]
+    fn midi_transport_time() {
+        let now = Instant::now();
+        assert_eq!(transport_time(now, 0), now);
+        assert_eq!(transport_time(now, 150), now + Duration::from_millis(150));
+    }
+
+    // the thread should sleep until whatever is due next instead of spinning, but still check in every RUNNING_POLL
+    #[test]
+    fn midi_wake_time() {
+        let now = Instant::now();
+        let soon = now + Duration::from_micros(500);
+        let later = now + Duration::from_millis(20);
+        assert_eq!(wake_time(now, later, None), now + RUNNING_POLL);
+        assert_eq!(wake_time(now, soon, None), soon);
+        assert_eq!(wake_time(now, later, Some(soon)), soon);
+        assert_eq!(wake_time(now, now, Some(later)), now);
+    }
+}
@@ -0,0 +1,173 @@
+/// Practice_log.rs keeps a record of every practice session so progress can be followed over time. A session runs from
+/// starting the metronome to stopping it, and each one is added to practice_log.jsonl in the directory Ready Metronome
+/// is run from as a line of JSON, ie.
+///
+/// {"date":"2026-10-16","duration_secs":754,"min_bpm":90.0,"max_bpm":120.0,"bars":301}
+use color_eyre::Report;
+use std::{
+    fs::{self, OpenOptions},
+    io::Write,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+pub const PRACTICE_LOG_PATH: &str = "./practice_log.jsonl";
+
+// Sessions shorter than this are left out of the log, so starting and stopping by mistake isn't counted as practice
+pub const MIN_SESSION_LENGTH: Duration = Duration::from_secs(1);
+
+// What was played during a session, App updates it from the metronome's state every frame while it runs
+pub struct SessionStats {
+    started: Instant,
+    date: String, // the day the session started on as YYYY-MM-DD (UTC)
+    min_bpm: f64,
+    max_bpm: f64,
+    bars: u64,     // bars started during the session, restarts from bar 1 included
+    last_bar: u64, // the bar count seen by the last update
+}
+
+impl SessionStats {
+    pub fn new(bpm: f64) -> SessionStats {
+        SessionStats {
+            started: Instant::now(),
+            date: today(),
+            min_bpm: bpm,
+            max_bpm: bpm,
+            bars: 0,
+            last_bar: 0,
+        }
+    }
+
+    // Follows the bpm and bar count. The bar count only goes backwards when the bar is started again from 1, so the
+    // bars from there are added on top of the ones already played
+    pub fn update(&mut self, bpm: f64, bar: u64) {
+        self.min_bpm = self.min_bpm.min(bpm);
+        self.max_bpm = self.max_bpm.max(bpm);
+        if bar > self.last_bar {
+            self.bars += bar - self.last_bar;
+        } else if bar < self.last_bar {
+            self.bars += bar;
+        }
+        self.last_bar = bar;
+    }
+
+    pub fn duration(&self) -> Duration {
+        self.started.elapsed()
+    }
+
+    // Describes the session as a line of the log
+    pub fn to_json(&self, duration: Duration) -> String {
+        format!(
+            "{{\"date\":\"{}\",\"duration_secs\":{},\"min_bpm\":{:.1},\"max_bpm\":{:.1},\"bars\":{}}}",
+            self.date,
+            duration.as_secs(),
+            self.min_bpm,
+            self.max_bpm,
+            self.bars
+        )
+    }
+}
+
+// Adds a finished session to the end of the log, the log is created by the first session
+pub fn log_session(path: &str, stats: &SessionStats) -> Result<(), Report> {
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    writeln!(file, "{}", stats.to_json(stats.duration()))?;
+    Ok(())
+}
+
+// Counts the sessions in the log and adds up how long they lasted. Lines without a duration are skipped, and a missing
+// log has no sessions
+pub fn practice_totals(path: &str) -> (u64, Duration) {
+    let contents = match fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(_) => return (0, Duration::ZERO),
+    };
+    let mut sessions = 0;
+    let mut total = Duration::ZERO;
+    for line in contents.lines() {
+        if let Some(seconds) = line
+            .split_once("\"duration_secs\":")
+            .and_then(|(_, rest)| rest.split([',', '}']).next())
+            .and_then(|seconds| seconds.trim().parse().ok())
+        {
+            sessions += 1;
+            total += Duration::from_secs(seconds);
+        }
+    }
+    (sessions, total)
+}
+
+// Today's date as YYYY-MM-DD in UTC
+fn today() -> String {
+    let seconds = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    let (year, month, day) = civil_date(seconds / 86_400);
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+// Works out the year, month and day a number of days after 1970-01-01, see
+// https://howardhinnant.github.io/date_algorithms.html#civil_from_days
+fn civil_date(days: u64) -> (u64, u64, u64) {
+    let days = days + 719_468;
+    let era = days / 146_097;
+    let day_of_era = days % 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = year_of_era + era * 400 + u64::from(month <= 2);
+    (year, month, day)
+}
+
+// Tests ---------------------------------------------------------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // the stats should follow the bpm range and keep counting bars through a restart
+    #[test]
+    fn practice_log_session_stats() {
+        let mut stats = SessionStats::new(100.0);
+        stats.update(100.0, 1);
+        stats.update(120.0, 4);
+        stats.update(90.0, 1);
+        stats.update(90.0, 2);
+        let line = stats.to_json(Duration::from_secs(75));
+        assert!(
+            line.ends_with("\"duration_secs\":75,\"min_bpm\":90.0,\"max_bpm\":120.0,\"bars\":6}")
+        );
+        assert!(line.starts_with("{\"date\":\"20"));
+    }
+
+    // sessions written to the log should be added up, skipping lines that can't be read
+    #[test]
+    fn practice_log_practice_totals() {
+        let path = std::env::temp_dir().join("readymetronome_test_practice_log.jsonl");
+        let path = path.to_str().unwrap();
+        let _ = fs::remove_file(path);
+        assert_eq!(practice_totals(path), (0, Duration::ZERO));
+
+        let stats = SessionStats::new(120.0);
+        log_session(path, &stats).unwrap();
+        let mut file = OpenOptions::new().append(true).open(path).unwrap();
+        writeln!(file, "not a session").unwrap();
+        writeln!(file, "{}", stats.to_json(Duration::from_secs(90))).unwrap();
+        assert_eq!(practice_totals(path), (2, Duration::from_secs(90)));
+        let _ = fs::remove_file(path);
+    }
+
+    // days since 1970 should turn into calendar dates, leap days included
+    #[test]
+    fn practice_log_civil_date() {
+        assert_eq!(civil_date(0), (1970, 1, 1));
+        assert_eq!(civil_date(11_016), (2000, 2, 29));
+        assert_eq!(civil_date(20_742), (2026, 10, 16));
+    }
+}
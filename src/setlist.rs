@@ -0,0 +1,225 @@
+/// Setlist.rs holds an ordered list of sections, each with its own tempo, time signature and length in bars. While the
+/// setlist is on the metronome plays through the sections in order, loading each one's settings as its first bar
+/// begins, and then stops or goes back to the first section. Setlists are kept in a setlist.toml file in the
+/// directory Ready Metronome is run from, ie.
+///
+/// loop = false
+///
+/// [[section]]
+/// name = "Intro"
+/// bpm = 90
+/// time_signature = "4/4"
+/// bars = 8
+///
+/// [[section]]
+/// name = "Verse"
+/// bpm = 120
+/// time_signature = "7/8"
+/// bars = 16
+/// note = "Watch the pickup into bar 9"
+use std::fs;
+
+pub const SETLIST_PATH: &str = "./setlist.toml";
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Section {
+    pub name: String,
+    pub bpm: u64,
+    pub ts_note: u64,
+    pub ts_value: u64,
+    pub bars: u64,
+    pub note: String, // free text shown while the section plays, ie. a reminder of what to practice (empty for none)
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Setlist {
+    pub sections: Vec<Section>,
+    pub looping: bool, // go back to the first section after the last one instead of stopping
+}
+
+impl Setlist {
+    pub fn new() -> Setlist {
+        Setlist::default()
+    }
+
+    // Loads the setlist from a toml file, returning an empty setlist if it is absent. Invalid sections are skipped and
+    // described in the returned warnings
+    pub fn load(path: &str) -> (Setlist, Vec<String>) {
+        let mut setlist = Setlist::new();
+        let contents = match fs::read_to_string(path) {
+            Ok(contents) => contents,
+            Err(_) => return (setlist, Vec::new()),
+        };
+        let warnings = setlist.apply(&contents);
+        (setlist, warnings)
+    }
+
+    // Reads the sections found in the contents of a setlist.toml file
+    fn apply(&mut self, contents: &str) -> Vec<String> {
+        let table = match contents.parse::<toml::Table>() {
+            Ok(table) => table,
+            Err(_) => return vec!["setlist.toml could not be read".to_owned()],
+        };
+
+        let mut warnings = Vec::new();
+        for (name, value) in table {
+            match (name.as_str(), value) {
+                ("loop", toml::Value::Boolean(looping)) => self.looping = looping,
+                ("section", toml::Value::Array(sections)) => {
+                    for (index, section) in sections.iter().enumerate() {
+                        match parse_section(section) {
+                            Some(section) => self.sections.push(section),
+                            None => warnings.push(format!(
+                                "Section {} in setlist.toml needs a bpm (20 - 500), time_signature and bars",
+                                index + 1
+                            )),
+                        }
+                    }
+                }
+                _ => warnings
+                    .push("Unknown or invalid setting '".to_owned() + &name + "' in setlist.toml"),
+            }
+        }
+        warnings
+    }
+
+    // Total length of one pass through the setlist in bars
+    pub fn total_bars(&self) -> u64 {
+        self.sections.iter().map(|section| section.bars).sum()
+    }
+
+    // Finds which section a bar (counting from 1 since the metronome started) falls in, along with the bar within that
+    // section (also counting from 1). None once a setlist that doesn't loop has finished, or if it is empty
+    pub fn section_for_bar(&self, bar: u64) -> Option<(usize, u64)> {
+        let total_bars = self.total_bars();
+        if total_bars == 0 || bar == 0 {
+            return None;
+        }
+        let mut bar_index = bar - 1;
+        if bar_index >= total_bars {
+            if !self.looping {
+                return None;
+            }
+            bar_index %= total_bars;
+        }
+        for (index, section) in self.sections.iter().enumerate() {
+            if bar_index < section.bars {
+                return Some((index, bar_index + 1));
+            }
+            bar_index -= section.bars;
+        }
+        None
+    }
+
+    // The section played after the given one, None after the last section unless the setlist loops
+    pub fn next_section(&self, index: usize) -> Option<&Section> {
+        match self.sections.get(index + 1) {
+            Some(section) => Some(section),
+            None if self.looping => self.sections.first(),
+            None => None,
+        }
+    }
+}
+
+// Reads a section from a toml table, None if any of its settings are missing or out of range
+fn parse_section(value: &toml::Value) -> Option<Section> {
+    let table = value.as_table()?;
+    let bpm = table.get("bpm")?.as_integer()?;
+    let bars = table.get("bars")?.as_integer()?;
+    let (ts_note, ts_value) = parse_time_signature(table.get("time_signature")?.as_str()?)?;
+    if !(20..=500).contains(&bpm) || bars < 1 {
+        return None;
+    }
+    let name = match table.get("name").and_then(|name| name.as_str()) {
+        Some(name) => name.to_owned(),
+        None => "Untitled".to_owned(),
+    };
+    let note = table
+        .get("note")
+        .and_then(|note| note.as_str())
+        .unwrap_or_default()
+        .to_owned();
+    Some(Section {
+        name,
+        bpm: bpm as u64,
+        ts_note,
+        ts_value,
+        bars: bars as u64,
+        note,
+    })
+}
+
+// Reads a time signature like "7/8", the value must be a note length the metronome can count
+pub fn parse_time_signature(time_signature: &str) -> Option<(u64, u64)> {
+    let (note, value) = time_signature.split_once('/')?;
+    let note: u64 = note.trim().parse().ok()?;
+    let value: u64 = value.trim().parse().ok()?;
+    if (1..=32).contains(&note) && [2, 4, 8, 16, 32, 64].contains(&value) {
+        Some((note, value))
+    } else {
+        None
+    }
+}
+
+// Tests ---------------------------------------------------------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn section(name: &str, bars: u64) -> Section {
+        Section {
+            name: name.to_owned(),
+            bpm: 120,
+            ts_note: 4,
+            ts_value: 4,
+            bars,
+            note: String::new(),
+        }
+    }
+
+    // sections should be read in order and invalid ones reported
+    #[test]
+    fn setlist_apply() {
+        let mut setlist = Setlist::new();
+        let warnings = setlist.apply(
+            "loop = true\n\
+             [[section]]\nname = \"Intro\"\nbpm = 90\ntime_signature = \"7/8\"\nbars = 2\nnote = \"Count in\"\n\
+             [[section]]\nbpm = 600\ntime_signature = \"4/4\"\nbars = 2\n\
+             [[section]]\nbpm = 120\ntime_signature = \"3/4\"\nbars = 4\n",
+        );
+        assert_eq!(warnings.len(), 1);
+        assert!(setlist.looping);
+        assert_eq!(setlist.sections.len(), 2);
+        assert_eq!(setlist.sections[0].name, "Intro");
+        assert_eq!(
+            (setlist.sections[0].ts_note, setlist.sections[0].ts_value),
+            (7, 8)
+        );
+        assert_eq!(setlist.sections[0].note, "Count in");
+        assert_eq!(setlist.sections[1].name, "Untitled");
+        assert!(setlist.sections[1].note.is_empty());
+        assert_eq!(parse_time_signature("4/3"), None);
+        assert_eq!(parse_time_signature(" 2 / 2 "), Some((2, 2)));
+        assert_eq!(parse_time_signature("0/4"), None);
+        assert_eq!(parse_time_signature("7"), None);
+    }
+
+    // bars should be placed in the right section, wrapping around only when looping
+    #[test]
+    fn setlist_section_for_bar() {
+        let mut setlist = Setlist {
+            sections: vec![section("Intro", 2), section("Verse", 3)],
+            looping: false,
+        };
+        assert_eq!(setlist.section_for_bar(1), Some((0, 1)));
+        assert_eq!(setlist.section_for_bar(3), Some((1, 1)));
+        assert_eq!(setlist.section_for_bar(5), Some((1, 3)));
+        assert_eq!(setlist.section_for_bar(6), None);
+        assert_eq!(setlist.next_section(1), None);
+
+        setlist.looping = true;
+        assert_eq!(setlist.section_for_bar(6), Some((0, 1)));
+        assert_eq!(setlist.next_section(1).unwrap().name, "Intro");
+        assert_eq!(Setlist::new().section_for_bar(1), None);
+    }
+}
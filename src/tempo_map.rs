@@ -0,0 +1,164 @@
+/// Tempo_map.rs holds a list of tempo changes, each one a bar number and the bpm to play from that bar. While the tempo
+/// map is on the metronome looks up the bpm for each bar as it begins, either jumping to each new tempo on its bar or
+/// changing the tempo a little every bar so it arrives at the next one gradually. Tempo maps are kept in a
+/// tempo_map.csv file in the directory Ready Metronome is run from, one change per line, ie.
+///
+/// # bar,bpm
+/// mode,linear
+/// 1,90
+/// 9,120
+/// 17,120
+/// 25,100
+use std::fs;
+
+pub const TEMPO_MAP_PATH: &str = "./tempo_map.csv";
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TempoPoint {
+    pub bar: u64, // counting from 1 since the metronome started
+    pub bpm: f64,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct TempoMap {
+    pub points: Vec<TempoPoint>, // kept in order of their bars, with one point per bar
+    pub interpolate: bool, // change the tempo every bar between points instead of on the points' bars
+}
+
+impl TempoMap {
+    pub fn new() -> TempoMap {
+        TempoMap::default()
+    }
+
+    // Loads the tempo map from a csv file, returning an empty map if it is absent. Invalid lines are skipped and
+    // described in the returned warnings
+    pub fn load(path: &str) -> (TempoMap, Vec<String>) {
+        let mut tempo_map = TempoMap::new();
+        let contents = match fs::read_to_string(path) {
+            Ok(contents) => contents,
+            Err(_) => return (tempo_map, Vec::new()),
+        };
+        let warnings = tempo_map.apply(&contents);
+        (tempo_map, warnings)
+    }
+
+    // Reads the tempo changes found in the contents of a tempo_map.csv file. Blank lines, lines starting with # and a
+    // "bar,bpm" header are ignored, and a later change on the same bar replaces the earlier one
+    fn apply(&mut self, contents: &str) -> Vec<String> {
+        let mut warnings = Vec::new();
+        for (index, line) in contents.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') || line.eq_ignore_ascii_case("bar,bpm") {
+                continue;
+            }
+            let (first, second) = match line.split_once(',') {
+                Some((first, second)) => (first.trim(), second.trim()),
+                None => ("", ""),
+            };
+            if first.eq_ignore_ascii_case("mode") {
+                match second.to_ascii_lowercase().as_str() {
+                    "linear" => self.interpolate = true,
+                    "step" => self.interpolate = false,
+                    _ => warnings.push(format!(
+                        "Line {} in tempo_map.csv needs a mode of step or linear",
+                        index + 1
+                    )),
+                }
+                continue;
+            }
+            match parse_point(first, second) {
+                Some(point) => {
+                    self.points.retain(|existing| existing.bar != point.bar);
+                    self.points.push(point);
+                }
+                None => warnings.push(format!(
+                    "Line {} in tempo_map.csv needs a bar (1 or more) and a bpm (20 - 500)",
+                    index + 1
+                )),
+            }
+        }
+        self.points.sort_by_key(|point| point.bar);
+        warnings
+    }
+
+    // The bpm a bar should be played at, None before the first change or if the map is empty. After the last change
+    // its bpm is kept
+    pub fn bpm_at(&self, bar: u64) -> Option<f64> {
+        let next_index = self.points.iter().position(|point| point.bar > bar);
+        let previous = match next_index {
+            Some(0) => return None,
+            Some(index) => self.points[index - 1],
+            None => *self.points.last()?,
+        };
+        match next_index {
+            Some(index) if self.interpolate => {
+                let next = self.points[index];
+                let progress = (bar - previous.bar) as f64 / (next.bar - previous.bar) as f64;
+                Some(previous.bpm + (next.bpm - previous.bpm) * progress)
+            }
+            _ => Some(previous.bpm),
+        }
+    }
+}
+
+// Reads a "bar,bpm" line, the bar counts from 1 and the bpm can have a fraction
+fn parse_point(bar: &str, bpm: &str) -> Option<TempoPoint> {
+    let bar: u64 = bar.parse().ok()?;
+    let bpm: f64 = bpm.parse().ok()?;
+    if bar >= 1 && (20.0..=500.0).contains(&bpm) {
+        Some(TempoPoint { bar, bpm })
+    } else {
+        None
+    }
+}
+
+// Tests ---------------------------------------------------------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // changes should be read in bar order and invalid lines reported
+    #[test]
+    fn tempo_map_apply() {
+        let mut tempo_map = TempoMap::new();
+        let warnings = tempo_map.apply(
+            "bar,bpm\n\
+             # intro\n\
+             mode, linear\n\
+             9,120\n\
+             1, 90.5\n\
+             0,100\n\
+             5,600\n\
+             tempo\n\
+             9,130\n",
+        );
+        assert_eq!(warnings.len(), 3);
+        assert!(tempo_map.interpolate);
+        assert_eq!(
+            tempo_map.points,
+            vec![
+                TempoPoint { bar: 1, bpm: 90.5 },
+                TempoPoint { bar: 9, bpm: 130.0 }
+            ]
+        );
+        assert_eq!(tempo_map.apply("mode,swing\n").len(), 1);
+    }
+
+    // the bpm should step or slide between changes and hold after the last one
+    #[test]
+    fn tempo_map_bpm_at() {
+        let mut tempo_map = TempoMap::new();
+        assert_eq!(tempo_map.bpm_at(1), None);
+        tempo_map.apply("3,100\n7,120\n11,80\n");
+        assert_eq!(tempo_map.bpm_at(1), None);
+        assert_eq!(tempo_map.bpm_at(3), Some(100.0));
+        assert_eq!(tempo_map.bpm_at(5), Some(100.0));
+        assert_eq!(tempo_map.bpm_at(7), Some(120.0));
+        assert_eq!(tempo_map.bpm_at(40), Some(80.0));
+        tempo_map.interpolate = true;
+        assert_eq!(tempo_map.bpm_at(5), Some(110.0));
+        assert_eq!(tempo_map.bpm_at(10), Some(90.0));
+        assert_eq!(tempo_map.bpm_at(11), Some(80.0));
+        assert_eq!(tempo_map.bpm_at(40), Some(80.0));
+    }
+}
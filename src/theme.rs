@@ -0,0 +1,64 @@
+/// Theme.rs holds the colors used to draw Ready Metronome. Every color in ui.rs comes from the active Theme apart from
+/// the beat flash, which is set with --flash-color and kept in App. Adding a new theme only takes another entry in THEMES
+use ratatui::style::Color;
+
+pub struct Theme {
+    pub name: &'static str,
+    pub title: Color,          // the "Ready Metronome" title
+    pub text: Color,           // menu items
+    pub highlight: Color,      // background of the selected menu item and the value being edited
+    pub highlight_text: Color, // text of the selected menu item and the value being edited
+    pub background: Color,     // background of the pop ups
+    pub main: Color,           // main screen and help hints
+    pub editing: Color,        // edit screen hints and help key names
+    pub warning: Color,        // quit dialog and notifications
+    pub error: Color,          // the error screen
+    pub flash_text: Color,     // title text while the title bar flashes
+}
+
+pub const THEMES: [Theme; 3] = [
+    Theme {
+        name: "dark",
+        title: Color::Green,
+        text: Color::White,
+        highlight: Color::LightYellow,
+        highlight_text: Color::Black,
+        background: Color::Black,
+        main: Color::Green,
+        editing: Color::Yellow,
+        warning: Color::LightRed,
+        error: Color::Red,
+        flash_text: Color::Black,
+    },
+    Theme {
+        name: "light",
+        title: Color::Blue,
+        text: Color::Black,
+        highlight: Color::Blue,
+        highlight_text: Color::White,
+        background: Color::White,
+        main: Color::Blue,
+        editing: Color::Magenta,
+        warning: Color::Red,
+        error: Color::Red,
+        flash_text: Color::Black,
+    },
+    Theme {
+        name: "high-contrast",
+        title: Color::White,
+        text: Color::White,
+        highlight: Color::White,
+        highlight_text: Color::Black,
+        background: Color::Black,
+        main: Color::LightCyan,
+        editing: Color::LightYellow,
+        warning: Color::LightRed,
+        error: Color::LightRed,
+        flash_text: Color::Black,
+    },
+];
+
+// Looks up the index of a theme in THEMES by its name
+pub fn theme_index(name: &str) -> Option<usize> {
+    THEMES.iter().position(|theme| theme.name == name)
+}
@@ -0,0 +1,105 @@
+/// Tone.rs works out the frequency of the reference tone, a sustained sine wave singers and string players can tune
+/// to. The metronome plays it through its own voice so it carries on over the click. A tone can be picked by note name
+/// (A, Bb, B♭, C#5...) or by frequency in Hz, notes are tuned to A4 = 440 Hz in equal temperament
+
+// The reference tone's default frequency, A4
+pub const DEFAULT_TONE_HZ: f64 = 440.0;
+
+// Range of frequencies the reference tone can be set to
+pub const MIN_TONE_HZ: f64 = 20.0;
+pub const MAX_TONE_HZ: f64 = 2000.0;
+
+// Note names within an octave starting from C, flats are used for the black keys except F# and C#
+const NOTE_NAMES: [&str; 12] = [
+    "C", "C#", "D", "Eb", "E", "F", "F#", "G", "Ab", "A", "Bb", "B",
+];
+
+// Reads a tone written as a frequency in Hz (ie. 442) or a note name with an optional octave (ie. A, Bb3, C#5), the
+// octave defaults to 4. None if it isn't either or is out of range
+pub fn parse_tone(text: &str) -> Option<f64> {
+    let text = text.trim();
+    let frequency = match text.trim_end_matches("Hz").trim().parse::<f64>() {
+        Ok(frequency) => frequency,
+        Err(_) => note_frequency(text)?,
+    };
+    if (MIN_TONE_HZ..=MAX_TONE_HZ).contains(&frequency) {
+        Some(frequency)
+    } else {
+        None
+    }
+}
+
+// Works out the frequency of a note name like "Bb3", None if it isn't a note
+fn note_frequency(note: &str) -> Option<f64> {
+    let mut chars = note.chars();
+    let letter = chars.next()?.to_ascii_uppercase();
+    let mut semitone: i32 = match letter {
+        'C' => 0,
+        'D' => 2,
+        'E' => 4,
+        'F' => 5,
+        'G' => 7,
+        'A' => 9,
+        'B' => 11,
+        _ => return None,
+    };
+    let rest = chars.as_str();
+    let octave_text = match rest.chars().next() {
+        Some('#') | Some('♯') => {
+            semitone += 1;
+            &rest[rest.chars().next()?.len_utf8()..]
+        }
+        Some('b') | Some('♭') => {
+            semitone -= 1;
+            &rest[rest.chars().next()?.len_utf8()..]
+        }
+        _ => rest,
+    };
+    let octave: i32 = if octave_text.is_empty() {
+        4
+    } else {
+        octave_text
+            .parse()
+            .ok()
+            .filter(|octave| (0..=8).contains(octave))?
+    };
+    // MIDI note numbers put A4 at 69 with C4 at 60
+    let midi_note = (octave + 1) * 12 + semitone;
+    Some(DEFAULT_TONE_HZ * 2_f64.powf((midi_note - 69) as f64 / 12.0))
+}
+
+// Describes a frequency with the name of the nearest note, ie. "A4 (440.0 Hz)"
+pub fn tone_label(frequency: f64) -> String {
+    let midi_note = (69.0 + 12.0 * (frequency / DEFAULT_TONE_HZ).log2()).round() as i32;
+    let name = NOTE_NAMES[midi_note.rem_euclid(12) as usize];
+    let octave = midi_note.div_euclid(12) - 1;
+    format!("{}{} ({:.1} Hz)", name, octave, frequency)
+}
+
+// Tests ---------------------------------------------------------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // note names and frequencies should both be read, sharps and flats moving by a semitone
+    #[test]
+    fn tone_parse_tone() {
+        assert_eq!(parse_tone("A"), Some(440.0));
+        assert_eq!(parse_tone("a5"), Some(880.0));
+        assert_eq!(parse_tone("442"), Some(442.0));
+        assert_eq!(parse_tone("442 Hz"), Some(442.0));
+        assert_eq!(parse_tone("Bb"), parse_tone("A#"));
+        assert_eq!(parse_tone("B♭"), parse_tone("Bb"));
+        assert!((parse_tone("C4").unwrap() - 261.626).abs() < 0.001);
+        assert_eq!(parse_tone("H"), None);
+        assert_eq!(parse_tone("5000"), None);
+    }
+
+    // the label should name the nearest note
+    #[test]
+    fn tone_tone_label() {
+        assert_eq!(tone_label(440.0), "A4 (440.0 Hz)");
+        assert_eq!(tone_label(442.0), "A4 (442.0 Hz)");
+        assert_eq!(tone_label(parse_tone("Bb3").unwrap()), "Bb3 (233.1 Hz)");
+    }
+}
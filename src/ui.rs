@@ -1,28 +1,93 @@
 /// This file controls the ratatui user interface display. It conditionally renders different screens based on the state
 /// defined in App.rs
 /// This is loosely based on the JSON Editor tutorial for ratatui. Tutorial found here https://ratatui.rs/tutorials/json-editor/ui/
-use crate::app::{App, CurrentScreen, CurrentlyEditing};
+use crate::app::{App, CurrentScreen};
+use crate::menu::Menu;
 use ratatui::{
-    layout::{Constraint, Direction, Layout, Rect},
-    style::{Color, Style, Stylize},
-    text::{Line, Span, Text},
-    widgets::{Block, Borders, Clear, List, ListItem, Paragraph},
+    layout::{Alignment, Constraint, Direction, Layout, Rect},
+    style::{Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, Gauge, List, ListItem, Paragraph, Wrap},
     Frame,
 };
 
+// Height of the current and new value boxes in the edit pop up, and the narrowest the pop up gets on a wide enough screen
+const EDIT_VALUE_HEIGHT: u16 = 3;
+const EDIT_POPUP_MIN_WIDTH: u16 = 60;
+
+// Height of the pendulum box under the main menu, borders included
+const PENDULUM_HEIGHT: u16 = 7;
+
+// Width and height of the quit pop up as percentages of the screen
+const QUIT_POPUP_WIDTH_PERCENT: u16 = 50;
+const QUIT_POPUP_HEIGHT_PERCENT: u16 = 30;
+
 // This is the function to render the UI to the screen
 pub fn ui(f: &mut Frame, app: &mut App) {
+    let theme = app.theme();
+
     // pop up block to use for editing / quit dialog
     let popup_block = Block::default()
         .title("Editing Value")
         .borders(Borders::NONE)
-        .style(Style::default().bg(Color::Black));
+        .style(Style::default().bg(theme.background));
     let area = centered_rect(50, 50, f.size());
 
     // various text styles for different situations
-    let active_style = Style::default().bg(Color::LightYellow).fg(Color::Black);
-    let quit_style = Style::default().fg(Color::Red);
-    let error_style = Style::default().fg(Color::Red);
+    let active_style = Style::default()
+        .bg(theme.highlight)
+        .fg(theme.highlight_text);
+    let quit_style = Style::default().fg(theme.warning);
+    let error_style = Style::default().fg(theme.error);
+
+    // This displays the current keys the user can use, it is worked out first so the footer can grow to fit it
+    let current_keys_hint = {
+        match app.current_screen {
+            CurrentScreen::Main => Span::styled(
+                "Use (arrow keys) to navigate, (enter) to select an option, (space) to start / stop, (m) to mute, (?) for help, or (q) to quit",
+                Style::default().fg(theme.main),
+            ),
+            CurrentScreen::Editing => {
+                if app.currently_editing.is_some() {
+                    Span::styled("Please enter a new value. Press (enter) to save, (esc) to discard changes or (q) to quit", Style::default().fg(theme.editing))
+                } else {
+                    Span::styled("Use (arrow keys) to navigate, (enter) to select, (esc) to go to main menu, or (q) to quit", Style::default().fg(theme.editing))
+                }
+            }
+            CurrentScreen::SoundSelection => {
+                if app.currently_editing.is_some() {
+                    Span::styled("Please enter a name for the sound. Press (enter) to save or (esc) to leave it as it was", Style::default().fg(theme.editing))
+                } else {
+                    Span::styled("Type to search, use (arrow keys) to navigate, (ctrl + p) to hear a sound, (ctrl + r) to rename it, (enter) to select, or (esc) to clear the search / go back to edit menu", Style::default().fg(theme.editing))
+                }
+            },
+            CurrentScreen::DeviceSelection => {
+                Span::styled("Use (arrow keys) to navigate, (enter) to select, (esc) to go back to edit menu, or (q) to quit", Style::default().fg(theme.editing))
+            },
+            CurrentScreen::Exiting => Span::styled(
+                "(y) or (enter) to quit / (n) or (esc) to go back",
+                error_style,
+            ),
+            CurrentScreen::Calibration => Span::styled(
+                "Tap (space) with the click, (enter) to use the suggested latency, or (esc) to go back to edit menu",
+                Style::default().fg(theme.editing),
+            ),
+            CurrentScreen::Help => Span::styled(
+                "Use (arrow keys) or (page up / page down) to scroll, (esc) or (?) to go back",
+                Style::default().fg(theme.main),
+            ),
+            CurrentScreen::Error => Span::styled(
+                "Something went wrong! Please press 'q' to quit",
+                error_style,
+            ),
+        }
+    };
+
+    // the hints get 75% of the footer width, less the borders. The footer grows taller when they need to wrap but never
+    // takes more than a third of the screen
+    let hint_width = (f.size().width * 3 / 4).saturating_sub(2);
+    let footer_height = (wrapped_line_count(&current_keys_hint.content, hint_width) + 2)
+        .clamp(3, (f.size().height / 3).max(3));
 
     // this defines the overall layout into three sections with the middle one being resizeable
     let chunks = Layout::default()
@@ -30,205 +95,385 @@ pub fn ui(f: &mut Frame, app: &mut App) {
         .constraints([
             Constraint::Length(3),
             Constraint::Min(1),
-            Constraint::Length(3),
+            Constraint::Length(footer_height),
         ])
         .split(f.size());
 
     // Title bar -------------------------------------------------------------------------------------------------------
+    // the title bar flashes on the first beat of each bar
+    let (title_block_style, title_style) = if app.flash_frames > 0 {
+        (
+            Style::default().bg(app.flash_color),
+            Style::default().fg(theme.flash_text),
+        )
+    } else {
+        (Style::default(), Style::default().fg(theme.title))
+    };
     let title_block = Block::default()
         .borders(Borders::ALL)
-        .style(Style::default());
+        .style(title_block_style);
+
+    // the beat indicator follows the title with a cell for every tick of the bar, main beats pulse brightly and
+    // subdivisions pulse dimly
+    let mut title_spans = vec![Span::styled("Ready Metronome   ", title_style)];
+    for (main_beat, lit) in app.get_beat_indicator() {
+        let (symbol, style) = match (main_beat, lit) {
+            (true, true) => (
+                "● ",
+                Style::default()
+                    .fg(theme.highlight)
+                    .add_modifier(Modifier::BOLD),
+            ),
+            (false, true) => (
+                "• ",
+                Style::default()
+                    .fg(theme.highlight)
+                    .add_modifier(Modifier::DIM),
+            ),
+            (true, false) => ("○ ", title_style),
+            (false, false) => ("· ", title_style),
+        };
+        title_spans.push(Span::styled(symbol, style));
+    }
 
-    let title = Paragraph::new(Text::styled(
-        "Ready Metronome",
-        Style::default().fg(Color::Green),
-    ))
-    .block(title_block);
+    // the time signature and what is being played sits on the right of the title bar so it can be read at a glance
+    let time_signature_area = title_block.inner(chunks[0]);
+    let time_signature = Paragraph::new(Line::from(Span::styled(
+        app.get_time_signature_label() + " ",
+        title_style.add_modifier(Modifier::BOLD),
+    )))
+    .alignment(Alignment::Right);
+
+    let title = Paragraph::new(Line::from(title_spans)).block(title_block);
 
     f.render_widget(title, chunks[0]);
+    f.render_widget(time_signature, time_signature_area);
 
     // Main screen -----------------------------------------------------------------------------------------------------
     // for the main menu screen we will use a widgets::List and ListState which we define from items in main.rs
     // loading in vector of items from main_menu and edit_menu for rendering
+    // starting is dimmed until there is a sound in the sound folder to play
+    let can_start = app.can_start();
     let main_items: Vec<ListItem> = app
         .main_menu
         .items
         .iter()
-        .map(|i| ListItem::new(i.as_str()))
+        .enumerate()
+        .map(|(index, i)| {
+            if index == 0 && !can_start {
+                ListItem::new(i.as_str()).style(Style::default().add_modifier(Modifier::DIM))
+            } else {
+                ListItem::new(i.as_str())
+            }
+        })
         .collect();
     let main_list = List::new(main_items)
         .block(
             Block::default()
-                .title("Control Panel")
+                .title(with_position("Control Panel".to_owned(), &app.main_menu))
                 .borders(Borders::ALL),
         )
-        .style(Style::default().fg(Color::White))
+        .style(Style::default().fg(theme.text))
         .highlight_style(active_style);
 
-    // define the main page layout and render (between the header and footer bars)
+    // while the metronome runs a gauge across the top of the page fills through each bar, it is hidden while stopped
+    let bar_progress = app.get_bar_progress();
+    let page_area = match bar_progress {
+        Some(progress) => {
+            let page_chunks = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints([Constraint::Length(3), Constraint::Min(1)])
+                .split(chunks[1]);
+            // the final bar cue turns the gauge to the warning color as the session ends
+            let (gauge_color, gauge_title) = if app.get_is_final_bar() {
+                (theme.warning, "Final Bar")
+            } else {
+                (theme.main, "Bar")
+            };
+            let bar_gauge = Gauge::default()
+                .block(Block::default().title(gauge_title).borders(Borders::ALL))
+                .gauge_style(Style::default().fg(gauge_color).bg(theme.background))
+                .ratio(progress)
+                .label(
+                    "Bar ".to_owned()
+                        + &app.get_bar_count_string()
+                        + ", beat "
+                        + &app.get_beat_count_string(),
+                );
+            f.render_widget(bar_gauge, page_chunks[0]);
+            page_chunks[1]
+        }
+        None => chunks[1],
+    };
+
+    // define the main page layout and render (between the header and footer bars), the < and > keys move the split
     let main_chunks = Layout::default()
         .direction(Direction::Horizontal)
-        .constraints([Constraint::Percentage(25), Constraint::Percentage(75)])
-        .split(chunks[1]);
+        .constraints([
+            Constraint::Percentage(app.panel_width),
+            Constraint::Percentage(100 - app.panel_width),
+        ])
+        .split(page_area);
 
-    f.render_stateful_widget(main_list, main_chunks[0], &mut app.main_menu.state);
+    // the tap accuracy results sit under the main menu while tap practice is on
+    let (main_menu_area, tap_results_area) = if app.tap_practice {
+        let control_chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Min(3), Constraint::Length(6)])
+            .split(main_chunks[0]);
+        (control_chunks[0], Some(control_chunks[1]))
+    } else {
+        (main_chunks[0], None)
+    };
+
+    // the output meter sits under the main menu while the metronome runs, pulsing with each click
+    let main_menu_area = match app.get_output_level() {
+        Some(level) => {
+            let meter_chunks = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints([Constraint::Min(3), Constraint::Length(3)])
+                .split(main_menu_area);
+            let output_meter = Gauge::default()
+                .block(Block::default().title("Output").borders(Borders::ALL))
+                .gauge_style(Style::default().fg(theme.editing).bg(theme.background))
+                .ratio(level)
+                .label("");
+            f.render_widget(output_meter, meter_chunks[1]);
+            meter_chunks[0]
+        }
+        None => main_menu_area,
+    };
+
+    // the pendulum swings under the main menu while the metronome runs, reaching a side on every tick
+    let main_menu_area = match app.get_pendulum_position() {
+        Some(position) => {
+            let pendulum_chunks = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints([Constraint::Min(3), Constraint::Length(PENDULUM_HEIGHT)])
+                .split(main_menu_area);
+            let pendulum_area = pendulum_chunks[1];
+            let pendulum_lines: Vec<Line> = pendulum_lines(
+                position,
+                pendulum_area.width.saturating_sub(2),
+                pendulum_area.height.saturating_sub(2),
+            )
+            .into_iter()
+            .map(Line::from)
+            .collect();
+            let pendulum = Paragraph::new(pendulum_lines)
+                .block(Block::default().title("Pendulum").borders(Borders::ALL))
+                .style(Style::default().fg(theme.text));
+            f.render_widget(pendulum, pendulum_area);
+            pendulum_chunks[0]
+        }
+        None => main_menu_area,
+    };
+
+    f.render_stateful_widget(main_list, main_menu_area, &mut app.main_menu.state);
+    app.main_menu_area = main_menu_area;
+
+    // Tap Accuracy ----------------------------------------------------------------------------------------------------
+    if let Some(tap_results_area) = tap_results_area {
+        let tap_lines: Vec<Line> = app
+            .get_tap_results()
+            .into_iter()
+            .map(|line| Line::from(Span::raw(line)))
+            .collect();
+        let tap_results = Paragraph::new(tap_lines)
+            .block(Block::default().title("Tap Accuracy").borders(Borders::ALL))
+            .style(Style::default().fg(theme.text));
+        f.render_widget(tap_results, tap_results_area);
+    }
 
     // Right Panel -----------------------------------------------------------------------------------------------------
-    let right_panel_items: Vec<ListItem> = if app.current_screen != CurrentScreen::SoundSelection {
-        app.edit_menu
-            .items
-            .iter()
-            .map(|i| ListItem::new(i.as_str()))
-            .collect()
-    } else {
-        app.sound_selection_menu
-            .items
-            .iter()
-            .map(|i| ListItem::new(i.as_str()))
-            .collect()
+    // show the search filter in the title while searching for a sound
+    let right_panel_title = match app.current_screen {
+        CurrentScreen::SoundSelection if !app.sound_selection_menu.filter.is_empty() => {
+            "Sound Selection (search: ".to_owned() + &app.sound_selection_menu.filter + ")"
+        }
+        CurrentScreen::SoundSelection => "Sound Selection".to_owned(),
+        CurrentScreen::DeviceSelection => "Output Device Selection".to_owned(),
+        _ => "Status".to_owned(),
+    };
+    let right_panel_menu = match app.current_screen {
+        CurrentScreen::SoundSelection => &mut app.sound_selection_menu,
+        CurrentScreen::DeviceSelection => &mut app.device_selection_menu,
+        _ => &mut app.edit_menu,
     };
+    let right_panel_title = with_position(right_panel_title, right_panel_menu);
+    let right_panel_items: Vec<ListItem> = right_panel_menu
+        .items
+        .iter()
+        .map(|i| ListItem::new(i.as_str()))
+        .collect();
     let right_panel_list = List::new(right_panel_items)
         .block(
             Block::default()
-                .title(if app.current_screen == CurrentScreen::SoundSelection {
-                    "Sound Selection"
-                } else {
-                    "Status"
-                })
+                .title(right_panel_title)
                 .borders(Borders::ALL),
         )
-        .style(Style::default().fg(Color::White))
+        .style(Style::default().fg(theme.text))
         .highlight_style(active_style);
 
-    if app.current_screen == CurrentScreen::SoundSelection {
-        f.render_stateful_widget(
-            right_panel_list,
-            main_chunks[1],
-            &mut app.sound_selection_menu.state,
-        );
-    } else {
-        f.render_stateful_widget(right_panel_list, main_chunks[1], &mut app.edit_menu.state);
-    }
+    f.render_stateful_widget(
+        right_panel_list,
+        main_chunks[1],
+        &mut right_panel_menu.state,
+    );
+    app.right_panel_area = main_chunks[1];
 
     // Editing Value Pop Up --------------------------------------------------------------------------------------------
     if let Some(editing) = app.currently_editing {
         f.render_widget(Clear, f.size()); //this clears the entire screen and anything already drawn
-        f.render_widget(popup_block, area);
 
-        // here we create two layouts, one to split the pop up vertically into two slices, and another to split the top
-        // slice into two slices to hold the original value and the new value currently being edited
-        let layout = Layout::default()
-            .direction(Direction::Vertical)
-            .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
-            .split(area);
+        // the pop up lists every setting it can edit down the left with the one being edited highlighted, and the
+        // current value, the new value and any notification on the right. It grows to fit whichever side is taller
+        // and the list scrolls to keep the highlighted setting in view once the screen is too short for it
+        let current_value = app.get_edit_value_string(editing);
+        let screen = f.size();
+        let popup_width = (screen.width * 3 / 4)
+            .max(EDIT_POPUP_MIN_WIDTH)
+            .min(screen.width);
+        let field_width = app
+            .edit_field_menu
+            .items
+            .iter()
+            .map(|field| field.chars().count() as u16 + 4)
+            .max()
+            .unwrap_or(0)
+            .min(popup_width / 2);
+        let value_width = popup_width - field_width;
+        let alert_height = wrapped_line_count(&app.alert_string, value_width.saturating_sub(2)) + 2;
+        let edit_area = edit_popup_rect(
+            screen,
+            popup_width,
+            app.edit_field_menu.items.len() as u16 + 2,
+            EDIT_VALUE_HEIGHT + alert_height,
+        );
+        f.render_widget(popup_block, edit_area);
 
-        let sub_layout = Layout::default()
+        let columns = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Length(field_width), Constraint::Min(1)])
+            .split(edit_area);
+        let value_layout = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Length(EDIT_VALUE_HEIGHT), Constraint::Min(3)])
+            .split(columns[1]);
+        let value_columns = Layout::default()
             .direction(Direction::Horizontal)
             .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
-            .split(layout[0]);
+            .split(value_layout[0]);
 
-        // get variables ready for conditional assignment
-        let original_block;
-        let key_block;
-        let original_text;
+        let field_items: Vec<ListItem> = app
+            .edit_field_menu
+            .items
+            .iter()
+            .map(|field| ListItem::new(field.as_str()))
+            .collect();
+        let field_list = List::new(field_items)
+            .block(Block::default().title("Settings").borders(Borders::ALL))
+            .style(Style::default().fg(theme.text))
+            .highlight_style(active_style);
+
+        let original_block = Block::default()
+            .title("Current ".to_owned() + editing.name())
+            .borders(Borders::ALL);
+        let original_text = Paragraph::new(current_value).block(original_block);
 
-        // the alert block is always the same
-        let alert_block = Block::default().title("Notification").borders(Borders::ALL);
-        let alert_text = Paragraph::new(app.alert_string.clone().red()).block(alert_block);
-
-        match editing {
-            CurrentlyEditing::Volume => {
-                key_block = Block::default()
-                    .title("Enter New Volume")
-                    .borders(Borders::ALL);
-                original_block = Block::default()
-                    .title("Current Volume")
-                    .borders(Borders::ALL);
-                original_text = Paragraph::new(app.get_volume().to_string()).block(original_block);
-            }
-            CurrentlyEditing::Bpm => {
-                key_block = Block::default()
-                    .title("Enter New Bpm")
-                    .borders(Borders::ALL);
-                original_block = Block::default().title("Current Bpm").borders(Borders::ALL);
-                original_text = Paragraph::new(app.get_bpm().to_string()).block(original_block);
-            }
-        }
         // get the current state of the edit_string for display while editing
+        let key_block = Block::default()
+            .title(editing.prompt())
+            .borders(Borders::ALL);
         let key_text =
             Paragraph::new(Span::styled(app.edit_string.clone(), active_style)).block(key_block);
 
-        f.render_widget(original_text, sub_layout[0]);
-        f.render_widget(key_text, sub_layout[1]);
-        f.render_widget(alert_text, layout[1]);
+        let alert_block = Block::default().title("Notification").borders(Borders::ALL);
+        let alert_text = Paragraph::new(Span::styled(app.alert_string.clone(), quit_style))
+            .block(alert_block)
+            .wrap(Wrap { trim: true });
+
+        f.render_stateful_widget(field_list, columns[0], &mut app.edit_field_menu.state);
+        f.render_widget(original_text, value_columns[0]);
+        f.render_widget(key_text, value_columns[1]);
+        f.render_widget(alert_text, value_layout[1]);
     }
 
     // Quit pop up -----------------------------------------------------------------------------------------------------
+    // sits over the screen it was opened from, which is where (n) goes back to
     if app.current_screen == CurrentScreen::Exiting {
-        f.render_widget(Clear, f.size()); //this clears the entire screen and anything already drawn
-        let quit_layout = Layout::default()
-            .direction(Direction::Horizontal)
-            .constraints([Constraint::Percentage(100)])
-            .split(area);
-
-        let quit_block = Block::default().title("Quitting").borders(Borders::ALL);
-        let quit_text = Paragraph::new(Span::styled(
-            "Are you sure you wish to quit? y / n".to_string(),
-            quit_style,
-        ))
+        let quit_area = centered_rect(
+            QUIT_POPUP_WIDTH_PERCENT,
+            QUIT_POPUP_HEIGHT_PERCENT,
+            f.size(),
+        );
+        f.render_widget(Clear, quit_area);
+        let quit_block = Block::default()
+            .title("Quitting")
+            .borders(Borders::ALL)
+            .border_style(quit_style)
+            .style(Style::default().bg(theme.background));
+        let quit_text = Paragraph::new(vec![
+            Line::from(Span::styled("Really quit? (y/n)", quit_style)),
+            Line::from(""),
+            Line::from(Span::styled(
+                "(y) or (enter) to quit, (n) or (esc) to go back",
+                Style::default().fg(theme.main),
+            )),
+        ])
+        .alignment(Alignment::Center)
+        .wrap(Wrap { trim: true })
         .block(quit_block);
-        f.render_widget(quit_text, quit_layout[0]);
+        f.render_widget(quit_text, quit_area);
     }
 
     // Bottom nav ------------------------------------------------------------------------------------------------------
     // it displays information about the current screen and controls for the user
-    let current_navigation_text = vec![match app.current_screen {
-        CurrentScreen::Main => Span::styled("Main Screen", Style::default().fg(Color::Green)),
-        CurrentScreen::Editing => Span::styled("Editing Mode", Style::default().fg(Color::Yellow)),
+    let mut current_navigation_text = vec![match app.current_screen {
+        CurrentScreen::Main => Span::styled("Main Screen", Style::default().fg(theme.main)),
+        CurrentScreen::Editing => Span::styled("Editing Mode", Style::default().fg(theme.editing)),
         CurrentScreen::SoundSelection => {
-            Span::styled("Sound Selection Mode", Style::default().fg(Color::Yellow))
+            Span::styled("Sound Selection Mode", Style::default().fg(theme.editing))
+        }
+        CurrentScreen::DeviceSelection => {
+            Span::styled("Device Selection Mode", Style::default().fg(theme.editing))
         }
-        CurrentScreen::Exiting => {
-            Span::styled("Really Quit?", Style::default().fg(Color::LightRed))
+        CurrentScreen::Exiting => Span::styled("Really Quit?", quit_style),
+        CurrentScreen::Calibration => {
+            Span::styled("Latency Calibration", Style::default().fg(theme.editing))
         }
-        CurrentScreen::Error => Span::styled("ERROR", Style::default().fg(Color::Red)),
+        CurrentScreen::Help => Span::styled("Help", Style::default().fg(theme.main)),
+        CurrentScreen::Error => Span::styled("ERROR", error_style),
     }
     .to_owned()];
+    if let Some(status_message) = app.get_status_message() {
+        current_navigation_text.push(Span::styled(
+            "   ".to_owned() + &status_message,
+            Style::default().fg(theme.warning),
+        ));
+    }
+    // make it clear the counters aren't moving because the display is frozen, not because the metronome stopped
+    if app.display_frozen.is_some() {
+        current_navigation_text.push(Span::styled(
+            "   Display frozen, (shift + f) to resume",
+            Style::default().fg(theme.warning),
+        ));
+    }
+    // show the step the arrows are turning the bpm by so fine and coarse can be told apart
+    if app.knob_mode {
+        current_navigation_text.push(Span::styled(
+            format!("   Knob: ±{} bpm", app.knob_step),
+            Style::default().fg(theme.editing),
+        ));
+    }
 
     let mode_footer = Paragraph::new(Line::from(current_navigation_text))
-        .block(Block::default().borders(Borders::ALL));
-
-    // This displays the current keys the user can use
-    // TODO: sometimes this text is scrolled off the screen on smaller terminals, figure out how to scroll it
-    let current_keys_hint = {
-        match app.current_screen {
-            CurrentScreen::Main => Span::styled(
-                "Use (arrow keys) to navigate, (enter) to select an option, or (q) to quit",
-                Style::default().fg(Color::Green),
-            ),
-            CurrentScreen::Editing => {
-                if app.currently_editing.is_some() {
-                    Span::styled("Please enter a new value. Press (enter) to save, (esc) to discard changes or (q) to quit", Style::default().fg(Color::Yellow))
-                } else {
-                    Span::styled("Use (arrow keys) to navigate, (enter) to select, (esc) to go to main menu, or (q) to quit", Style::default().fg(Color::Yellow))
-                }
-            }
-            CurrentScreen::SoundSelection => {
-                Span::styled("Use (arrow keys) to navigate, (enter) to select, (esc) to go back to edit menu, or (q) to quit", Style::default().fg(Color::Yellow))
-            },
-            CurrentScreen::Exiting => Span::styled(
-                "(q) to quit / (n) to return to main menu",
-                Style::default().fg(Color::Red),
-            ),
-            CurrentScreen::Error => Span::styled(
-                "Something went wrong! Please press 'q' to quit",
-                Style::default().fg(Color::Red),
-            ),
-        }
-    };
+        .block(Block::default().borders(Borders::ALL))
+        .wrap(Wrap { trim: true });
 
-    let key_notes_footer =
-        Paragraph::new(Line::from(current_keys_hint)).block(Block::default().borders(Borders::ALL));
+    let key_notes_footer = Paragraph::new(Line::from(current_keys_hint))
+        .block(Block::default().borders(Borders::ALL))
+        .wrap(Wrap { trim: true });
 
     // here is where we create the actual footer chunks for rendering, we pass the last chunks[] element (footer)
     // to split and render those. The screen name gets 25% of the length and the hints get 75%
@@ -241,6 +486,94 @@ pub fn ui(f: &mut Frame, app: &mut App) {
     f.render_widget(mode_footer, footer_chunks[0]);
     f.render_widget(key_notes_footer, footer_chunks[1]);
 
+    // Debug panel -----------------------------------------------------------------------------------------------------
+    // sits in the top right corner of the page while debug mode is on, showing how well the engine is keeping time
+    if app.get_debug() {
+        let debug_lines: Vec<Line> = app.get_debug_lines().into_iter().map(Line::from).collect();
+        let width = 34.min(chunks[1].width);
+        let height = (debug_lines.len() as u16 + 2).min(chunks[1].height);
+        let debug_area = Rect::new(
+            chunks[1].x + chunks[1].width - width,
+            chunks[1].y,
+            width,
+            height,
+        );
+        f.render_widget(Clear, debug_area);
+        let debug_text = Paragraph::new(debug_lines).block(
+            Block::default()
+                .title("Debug")
+                .borders(Borders::ALL)
+                .style(Style::default().fg(theme.warning).bg(theme.background)),
+        );
+        f.render_widget(debug_text, debug_area);
+    }
+
+    // Help screen ---------------------------------------------------------------------------------------------------
+    // covers everything but the footer with the list of keys and features
+    if app.current_screen == CurrentScreen::Help {
+        let help_area = Rect::new(0, 0, f.size().width, chunks[2].y);
+        f.render_widget(Clear, help_area);
+        let help_lines: Vec<Line> = help_entries()
+            .into_iter()
+            .map(|(name, description)| {
+                Line::from(vec![
+                    Span::styled(format!("{:<28}", name), Style::default().fg(theme.editing)),
+                    Span::raw(description),
+                ])
+            })
+            .collect();
+        // keep the scroll within the list so the last page stays full
+        let visible_lines = help_area.height.saturating_sub(2);
+        let max_scroll = (help_lines.len() as u16).saturating_sub(visible_lines);
+        app.help_scroll = app.help_scroll.min(max_scroll);
+        let help_title = format!(
+            "Help ({} - {} of {})",
+            app.help_scroll + 1,
+            (app.help_scroll + visible_lines).min(help_lines.len() as u16),
+            help_lines.len()
+        );
+        let help_text = Paragraph::new(help_lines)
+            .block(Block::default().title(help_title).borders(Borders::ALL))
+            .scroll((app.help_scroll, 0));
+        f.render_widget(help_text, help_area);
+    }
+
+    // Calibration screen --------------------------------------------------------------------------------------------
+    // sits over the menus while the metronome plays, showing the taps so far and the latency they suggest
+    if app.current_screen == CurrentScreen::Calibration {
+        f.render_widget(Clear, area);
+        let calibration_lines: Vec<Line> = app
+            .get_calibration_lines()
+            .into_iter()
+            .map(Line::from)
+            .collect();
+        let calibration_text = Paragraph::new(calibration_lines)
+            .block(
+                Block::default()
+                    .title("Output Latency Calibration")
+                    .borders(Borders::ALL)
+                    .style(Style::default().bg(theme.background)),
+            )
+            .wrap(Wrap { trim: true });
+        f.render_widget(calibration_text, area);
+    }
+
+    // Screen flash ----------------------------------------------------------------------------------------------------
+    // with screen flash on the whole screen is filled for a frame on every beat so it can be seen from across the room,
+    // beat 1 flashes in the brighter flash color
+    if app.screen_flash_frames > 0 {
+        let flash_color = if app.screen_flash_downbeat {
+            app.flash_color
+        } else {
+            theme.main
+        };
+        f.render_widget(Clear, f.size());
+        f.render_widget(
+            Block::default().style(Style::default().bg(flash_color)),
+            f.size(),
+        );
+    }
+
     // Error Pop Up ----------------------------------------------------------------------------------------------------
     // hopefully no one will be seeing this :) this error pop's up if app.settings.error gets set to true by the metronome
     if app.current_screen == CurrentScreen::Error {
@@ -253,15 +586,293 @@ pub fn ui(f: &mut Frame, app: &mut App) {
         let error_block = Block::default()
             .title("Unexpected ERROR!")
             .borders(Borders::ALL);
-        let error_text = Paragraph::new(Span::styled(
-            "Something went wrong! Please press 'q' to quit".to_string(),
-            error_style,
-        ))
-        .block(error_block);
+        // show what went wrong when the metronome reported it
+        let error_message = app.get_error_message();
+        let error_lines = if error_message.is_empty() {
+            vec![Line::from(Span::styled(
+                "Something went wrong! Please press 'q' to quit",
+                error_style,
+            ))]
+        } else {
+            vec![
+                Line::from(Span::styled(error_message, error_style)),
+                Line::from(Span::styled("Please press 'q' to quit", error_style)),
+            ]
+        };
+        let error_text = Paragraph::new(error_lines)
+            .block(error_block)
+            .wrap(Wrap { trim: true });
         f.render_widget(error_text, error_layout[0]);
     }
 }
 
+// The keys and features listed on the help screen, keys are the defaults before any keybindings.toml changes
+fn help_entries() -> Vec<(&'static str, &'static str)> {
+    vec![
+        ("arrow keys / tab", "move through the menus"),
+        (
+            "page up / page down",
+            "move through long menus a page at a time",
+        ),
+        ("home / end", "jump to the top or bottom of a menu"),
+        (
+            "enter / mouse click",
+            "select a menu item or save the value being edited",
+        ),
+        (
+            "esc",
+            "back out of a menu or discard the value being edited",
+        ),
+        ("space / t", "start / stop the metronome"),
+        ("m", "mute / unmute the click, the bars keep counting"),
+        ("a", "tap along with the click while tap accuracy is on"),
+        ("+ / -", "raise / lower the bpm by 1"),
+        ("] / [", "raise / lower the bpm by 10"),
+        (
+            "shift + d / shift + h",
+            "double / half time, press again for the base tempo",
+        ),
+        (". / ,", "raise / lower the volume by 5"),
+        (
+            "n",
+            "knob mode, up / down change the bpm by 1 and by 10 with shift",
+        ),
+        ("1 - 9", "jump to a quickslot bpm on the main screen"),
+        (
+            "shift + 1 - 9",
+            "save the bpm to a quickslot on the main screen",
+        ),
+        ("r", "start again from beat 1 of bar 1 without stopping"),
+        ("s", "step through straight, 8ths, 16ths and triplets"),
+        (
+            "shift + s",
+            "triplets on / off, going back to the subdivision before",
+        ),
+        ("u", "undo the last setting change, up to 5 times"),
+        ("< / >", "narrow / widen the control panel"),
+        ("b / v / shift + t", "edit the bpm / volume / time signature"),
+        ("f", "play / stop the reference tone"),
+        (
+            "x",
+            "stop all sound now, the metronome, reference tone and any preview",
+        ),
+        ("z", "reset the total bar count"),
+        (
+            "shift + f",
+            "freeze / unfreeze the counters and beat displays, the click keeps playing",
+        ),
+        (
+            "c",
+            "clear the per beat sounds, accents and feel offsets, every beat plays the selected sound",
+        ),
+        ("d", "show / hide the debug panel with timing measurements"),
+        ("?", "show this help"),
+        ("q", "quit"),
+        ("", ""),
+        (
+            "Select sound",
+            "choose the click, type to search the sounds and press ctrl + p to hear one",
+        ),
+        ("Beat N sound", "give a beat of the bar its own sound"),
+        (
+            "Tempo trainer",
+            "raise the bpm every few bars until the target is reached",
+        ),
+        ("Output device", "choose which audio device to play through"),
+        (
+            "Visual only",
+            "play nothing and leave the audio device alone, everything still counts",
+        ),
+        (
+            "Polyrhythm",
+            "play a second voice spread evenly across each bar",
+        ),
+        (
+            "Beats per bar",
+            "count and accent the bar in a different number of beats than the time signature",
+        ),
+        (
+            "Reference tone",
+            "a sine wave to tune to, pick a note like A or Bb or a frequency",
+        ),
+        (
+            "Accent groups",
+            "group the beats of the bar, ie. 2+2+3 for 7/8, and accent the first of each",
+        ),
+        (
+            "Feel offsets",
+            "play each beat a few milliseconds late or early for a human feel",
+        ),
+        (
+            "Click / subdivision / polyrhythm pan",
+            "place each voice left or right so they're easier to tell apart",
+        ),
+        (
+            "Accent pitch",
+            "play the accented beats faster and higher with the same sound, 1.0 is no accent",
+        ),
+        (
+            "Setlist",
+            "play through the sections in setlist.toml, each with its own bpm and time signature",
+        ),
+        (
+            "Tempo map",
+            "follow the tempo changes in tempo_map.csv, stepped or gradual between them",
+        ),
+        (
+            "Downbeat only",
+            "click only on beat 1 of each bar, the other beats still count",
+        ),
+        (
+            "Silent bar chance",
+            "randomly drop whole bars to test your timing",
+        ),
+        ("Stop after", "stop by itself after a number of bars"),
+        (
+            "Final bar cue",
+            "play the last bar before stopping at a higher pitch",
+        ),
+        (
+            "Smooth click edges",
+            "fade each sound in over 2 ms so short samples don't pop",
+        ),
+        (
+            "Screen flash",
+            "flash the whole screen on every beat, brighter on beat 1, for when the click can't be heard",
+        ),
+        (
+            "Subdivision sound",
+            "play a different sound on the subdivisions between beats",
+        ),
+        (
+            "Time signature changes",
+            "change the bar straight away, or hold changes until the next bar starts",
+        ),
+        (
+            "Pendulum",
+            "swing a pendulum under the main menu in time with the ticks while playing",
+        ),
+        (
+            "Intro sound",
+            "play a count in sample once before beat 1, select it again to turn it off",
+        ),
+        (
+            "Fade in",
+            "ramp the volume up over the first bars after starting",
+        ),
+        (
+            "Live preview",
+            "hear the bpm and volume while typing them, esc puts them back",
+        ),
+        (
+            "Output latency",
+            "play the click early so it is heard on the beat through slow devices",
+        ),
+        (
+            "Calibrate latency",
+            "tap along with the click to measure the output latency",
+        ),
+        (
+            "Pitch",
+            "speed up or slow down the sounds to raise or lower their pitch",
+        ),
+        (
+            "Ableton Link",
+            "share the tempo and beat with other apps on the network",
+        ),
+        (
+            "MIDI clock",
+            "send MIDI clock so drum machines and DAWs follow the tempo",
+        ),
+        (
+            "Tap accuracy",
+            "score your taps against the beat, early / late and by how much",
+        ),
+        ("Export Click Track", "render 16 bars to click_track.wav"),
+        (
+            "keybindings.toml",
+            "change the keys, see the README for details",
+        ),
+    ]
+}
+
+// Adds the selected item's position to a list's title, ie. "Control Panel (3/20)", so it is clear where the list can scroll
+fn with_position(title: String, menu: &Menu) -> String {
+    match menu.position() {
+        Some((selected, total)) => format!("{} ({}/{})", title, selected, total),
+        None => title,
+    }
+}
+
+// Counts the lines text takes up when word wrapped to width, words longer than the width are split across lines
+fn wrapped_line_count(text: &str, width: u16) -> u16 {
+    let width = width.max(1) as usize;
+    let mut lines = 1;
+    let mut line_length = 0;
+    for word in text.split_whitespace() {
+        let word_length = word.chars().count();
+        if line_length > 0 && line_length + 1 + word_length <= width {
+            line_length += 1 + word_length;
+        } else {
+            if line_length > 0 {
+                lines += 1;
+            }
+            lines += (word_length.max(1) - 1) / width;
+            line_length = (word_length - 1) % width + 1;
+        }
+    }
+    lines as u16
+}
+
+// Draws a pendulum hanging from the middle of the top row with its bob on the bottom row, the position (0.0 - 1.0)
+// places the bob from the left edge to the right edge
+fn pendulum_lines(position: f64, width: u16, height: u16) -> Vec<String> {
+    if width == 0 || height == 0 {
+        return Vec::new();
+    }
+    let pivot = (width as f64 - 1.0) / 2.0;
+    let bob = position.clamp(0.0, 1.0) * (width as f64 - 1.0);
+    let arm = if (bob - pivot).abs() < 0.5 {
+        '|'
+    } else if bob < pivot {
+        '/'
+    } else {
+        '\\'
+    };
+    (0..height)
+        .map(|row| {
+            // the arm runs in a straight line from the pivot down to the bob
+            let along = if height == 1 {
+                1.0
+            } else {
+                row as f64 / (height - 1) as f64
+            };
+            let column = (pivot + (bob - pivot) * along).round() as usize;
+            let mark = if row == 0 {
+                '+'
+            } else if row == height - 1 {
+                'O'
+            } else {
+                arm
+            };
+            " ".repeat(column) + &mark.to_string()
+        })
+        .collect()
+}
+
+// Centers the edit pop up on the screen, tall enough for the taller of its list of settings and its values but never
+// taller than the screen
+fn edit_popup_rect(screen: Rect, width: u16, list_height: u16, value_height: u16) -> Rect {
+    let width = width.min(screen.width);
+    let height = list_height.max(value_height).min(screen.height);
+    Rect {
+        x: screen.x + (screen.width - width) / 2,
+        y: screen.y + (screen.height - height) / 2,
+        width,
+        height,
+    }
+}
+
 /// helper function to create a centered rect using up certain percentage of the available rect `r`
 // note: This is taken wholesale from the ratatui popup example: https://github.com/ratatui-org/ratatui/blob/main/examples/popup.rs
 // it is used to create a rectangle in the center of the screen for pop ups
@@ -286,3 +897,58 @@ fn centered_rect(percent_x: u16, percent_y: u16, r: Rect) -> Rect {
         ])
         .split(popup_layout[1])[1] // Return the middle chunk
 }
+
+// Tests ---------------------------------------------------------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // ui::wrapped_line_count should count the lines needed to word wrap the key hints
+    #[test]
+    fn ui_wrapped_line_count() {
+        assert_eq!(wrapped_line_count("", 10), 1);
+        assert_eq!(wrapped_line_count("(q) to quit", 20), 1);
+        assert_eq!(wrapped_line_count("(q) to quit", 6), 2);
+        assert_eq!(wrapped_line_count("(enter) to save", 7), 2);
+        assert_eq!(wrapped_line_count("abcdefghij", 4), 3);
+    }
+
+    // ui::pendulum_lines should draw the arm from the pivot to the bob on whichever side it has swung to
+    #[test]
+    fn ui_pendulum_lines() {
+        assert_eq!(pendulum_lines(0.0, 5, 3), vec!["  +", " /", "O"]);
+        assert_eq!(pendulum_lines(0.5, 5, 3), vec!["  +", "  |", "  O"]);
+        assert_eq!(pendulum_lines(1.0, 5, 3), vec!["  +", "   \\", "    O"]);
+        assert!(pendulum_lines(0.5, 0, 3).is_empty());
+    }
+
+    // ui::edit_popup_rect should fit the taller side of the pop up and shrink to a short screen
+    #[test]
+    fn ui_edit_popup_rect() {
+        let screen = Rect::new(0, 0, 100, 40);
+        assert_eq!(
+            edit_popup_rect(screen, 60, 18, 6),
+            Rect::new(20, 11, 60, 18)
+        );
+        assert_eq!(edit_popup_rect(screen, 60, 4, 9), Rect::new(20, 15, 60, 9));
+        assert_eq!(
+            edit_popup_rect(Rect::new(0, 0, 50, 10), 60, 18, 6),
+            Rect::new(0, 0, 50, 10)
+        );
+    }
+
+    // ui::with_position should only add the position while an item is selected
+    #[test]
+    fn ui_with_position() {
+        let mut menu = Menu::new(vec!["Start".to_owned(), "Edit".to_owned()]);
+        assert_eq!(
+            with_position("Control Panel".to_owned(), &menu),
+            "Control Panel"
+        );
+        menu.select(1);
+        assert_eq!(
+            with_position("Control Panel".to_owned(), &menu),
+            "Control Panel (2/2)"
+        );
+    }
+}